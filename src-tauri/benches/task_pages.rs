@@ -0,0 +1,171 @@
+//! Compares loading a page of tasks through `search_tasks`, which loads the related rows with
+//! one query per kind of row, against the previous approach of querying them for every task.
+//!
+//! Run with `cargo bench --bench task_pages`.
+
+use std::time::{Duration, Instant};
+
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use tauri::async_runtime::block_on;
+use timely_lib::{
+    data_access::Data,
+    features::tasks::{search_tasks, TaskSearchParams},
+};
+
+const TASK_COUNT: i64 = 5_000;
+const TAG_COUNT: i64 = 50;
+const PAGE_SIZES: [i64; 3] = [25, 100, 500];
+const RUNS: u32 = 20;
+
+async fn seed() -> SqlitePool {
+    // An in-memory database only lives as long as its connection, so the pool keeps just one.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Could not open the database.");
+
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Could not run migrations.");
+
+    let mut transaction = pool.begin().await.unwrap();
+
+    for tag in 1..=TAG_COUNT {
+        sqlx::query("INSERT INTO tags (value) VALUES (?)")
+            .bind(format!("tag-{}", tag))
+            .execute(&mut *transaction)
+            .await
+            .unwrap();
+    }
+
+    for task in 1..=TASK_COUNT {
+        let task_id = sqlx::query("INSERT INTO tasks (title, description) VALUES (?, ?)")
+            .bind(format!("Task {:05}", task))
+            .bind("Seeded for the benchmark")
+            .execute(&mut *transaction)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        for tag_id in [task % TAG_COUNT + 1, (task + 7) % TAG_COUNT + 1] {
+            sqlx::query("INSERT INTO task_tags (task_id, tag_id) VALUES (?, ?)")
+                .bind(task_id)
+                .bind(tag_id)
+                .execute(&mut *transaction)
+                .await
+                .unwrap();
+        }
+
+        for comment in 0..2 {
+            sqlx::query("INSERT INTO comments (task_id, message, created) VALUES (?, ?, ?)")
+                .bind(task_id)
+                .bind(format!("Comment {}", comment))
+                .bind(1_700_000_000 + task)
+                .execute(&mut *transaction)
+                .await
+                .unwrap();
+        }
+
+        for session in 0..3 {
+            let start_date = 1_700_000_000 + task * 10_000 + session * 1_000;
+            sqlx::query(
+                "INSERT INTO task_work_history (task_id, start_date, end_date) VALUES (?, ?, ?)",
+            )
+            .bind(task_id)
+            .bind(start_date)
+            .bind(start_date + 600)
+            .execute(&mut *transaction)
+            .await
+            .unwrap();
+        }
+    }
+
+    transaction.commit().await.unwrap();
+    pool
+}
+
+fn search_params(page_size: i64) -> TaskSearchParams {
+    serde_json::from_value(serde_json::json!({
+        "page": 1,
+        "pageSize": page_size,
+        "queryString": null,
+        "statuses": ["To Do", "Doing", "Paused", "Done", "Cancelled"],
+        "quickFilter": null,
+        "startByFilter": null,
+        "dueByFilter": null,
+        "ordering": { "orderBy": "title", "sortDirection": 0 },
+        "estimateFilter": null,
+        "searchQuery": null,
+    }))
+    .unwrap()
+}
+
+/// Load the page the way it was loaded before, with a query for every task and kind of row.
+async fn load_page_per_task(pool: &SqlitePool, page_size: i64) {
+    let task_ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM tasks WHERE deleted_at IS NULL ORDER BY LOWER(title) ASC LIMIT ?",
+    )
+    .bind(page_size)
+    .fetch_all(pool)
+    .await
+    .unwrap();
+
+    for task_id in task_ids {
+        for query in [
+            "SELECT * FROM comments WHERE comments.task_id = ?",
+            "SELECT tags.* FROM tags INNER JOIN task_tags ON task_tags.tag_id = tags.id WHERE task_tags.task_id = ?",
+            "SELECT * FROM task_work_history WHERE task_work_history.task_id = ? ORDER BY start_date DESC",
+            "SELECT * FROM task_recurrences WHERE task_recurrences.task_id = ?",
+        ] {
+            sqlx::query(query)
+                .bind(task_id)
+                .fetch_all(pool)
+                .await
+                .unwrap();
+        }
+    }
+}
+
+/// The average time of a run, after one run to warm the cache up.
+fn time<F: std::future::Future<Output = ()>>(mut run: impl FnMut() -> F) -> Duration {
+    block_on(run());
+
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        block_on(run());
+    }
+
+    started.elapsed() / RUNS
+}
+
+fn main() {
+    println!("Seeding {} tasks...", TASK_COUNT);
+    let db = &Data {
+        pool: block_on(seed()),
+    };
+
+    println!(
+        "{:>9} | {:>14} | {:>14} | {:>7}",
+        "page size", "per task", "batched", "speedup"
+    );
+
+    for page_size in PAGE_SIZES {
+        let per_task = time(|| load_page_per_task(&db.pool, page_size));
+        let batched = time(|| async move {
+            let page = search_tasks(search_params(page_size), db)
+                .await
+                .expect("The search should succeed.");
+            assert_eq!(page.data.len() as i64, page_size);
+        });
+
+        println!(
+            "{:>9} | {:>14?} | {:>14?} | {:>6.1}x",
+            page_size,
+            per_task,
+            batched,
+            per_task.as_secs_f64() / batched.as_secs_f64()
+        );
+    }
+}
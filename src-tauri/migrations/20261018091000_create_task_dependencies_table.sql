@@ -0,0 +1,8 @@
+-- A task cannot be started until every task it depends on is done.
+CREATE TABLE IF NOT EXISTS task_dependencies (
+    task_id INTEGER NOT NULL,
+    depends_on_task_id INTEGER NOT NULL,
+    PRIMARY KEY(task_id, depends_on_task_id),
+    FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY(depends_on_task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
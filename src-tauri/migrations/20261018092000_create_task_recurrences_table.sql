@@ -0,0 +1,12 @@
+-- A recurring task generates its next occurrence when it is finished.
+CREATE TABLE IF NOT EXISTS task_recurrences (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL UNIQUE,
+    frequency TEXT NOT NULL DEFAULT 'Weekly',
+    interval INTEGER NOT NULL DEFAULT 1,
+    weekdays INTEGER NOT NULL DEFAULT 0,
+    end_after_occurrences INTEGER,
+    end_date INTEGER,
+    occurrence INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,2 @@
+-- When disabled, starting a task pauses whichever task was being worked before.
+ALTER TABLE user_settings ADD COLUMN allow_concurrent_timers BOOL NOT NULL DEFAULT 0;
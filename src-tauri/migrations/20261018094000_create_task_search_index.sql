@@ -0,0 +1,54 @@
+-- Full text index over each task's title, description and comments, keyed by the task id.
+CREATE VIRTUAL TABLE IF NOT EXISTS task_search USING fts5(
+    title,
+    description,
+    comments,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO task_search (rowid, title, description, comments)
+SELECT tasks.id,
+    tasks.title,
+    tasks.description,
+    COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = tasks.id), '')
+FROM tasks;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_task_insert AFTER INSERT ON tasks
+BEGIN
+    INSERT INTO task_search (rowid, title, description, comments)
+    VALUES (new.id, new.title, new.description, '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_task_update AFTER UPDATE OF title, description ON tasks
+BEGIN
+    UPDATE task_search
+    SET title = new.title,
+    description = new.description
+    WHERE rowid = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_task_delete AFTER DELETE ON tasks
+BEGIN
+    DELETE FROM task_search WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_insert AFTER INSERT ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = new.task_id), '')
+    WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_update AFTER UPDATE OF message ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = new.task_id), '')
+    WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_delete AFTER DELETE ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = old.task_id), '')
+    WHERE rowid = old.task_id;
+END;
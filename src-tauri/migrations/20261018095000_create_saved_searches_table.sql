@@ -0,0 +1,6 @@
+-- A named task search which can be run again, stored as serialized `TaskSearchParams` JSON.
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    params TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- 0 (P0) is the most important and 4 (P4) the least, new tasks default to P2.
+ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 2;
+CREATE INDEX IF NOT EXISTS idx_tasks_priority ON tasks(priority);
@@ -0,0 +1,38 @@
+-- Deleting a task, tag or comment moves it to the trash by setting deleted_at, rows are only
+-- removed for good when purged.
+ALTER TABLE tasks ADD COLUMN deleted_at INTEGER;
+ALTER TABLE tags ADD COLUMN deleted_at INTEGER;
+ALTER TABLE comments ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_deleted_at ON tasks(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_tags_deleted_at ON tags(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_comments_deleted_at ON comments(deleted_at);
+
+-- How many days items stay in the trash before they are purged, 0 keeps them forever.
+ALTER TABLE user_settings ADD COLUMN trash_retention_days INTEGER NOT NULL DEFAULT 30;
+
+-- Comments in the trash are left out of the search index.
+DROP TRIGGER IF EXISTS task_search_after_comment_insert;
+DROP TRIGGER IF EXISTS task_search_after_comment_update;
+DROP TRIGGER IF EXISTS task_search_after_comment_delete;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_insert AFTER INSERT ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = new.task_id AND comments.deleted_at IS NULL), '')
+    WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_update AFTER UPDATE OF message, deleted_at ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = new.task_id AND comments.deleted_at IS NULL), '')
+    WHERE rowid = new.task_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_search_after_comment_delete AFTER DELETE ON comments
+BEGIN
+    UPDATE task_search
+    SET comments = COALESCE((SELECT group_concat(comments.message, ' ') FROM comments WHERE comments.task_id = old.task_id AND comments.deleted_at IS NULL), '')
+    WHERE rowid = old.task_id;
+END;
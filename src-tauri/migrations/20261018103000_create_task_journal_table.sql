@@ -0,0 +1,9 @@
+-- Every change made to tasks by a command, stored as the state of the affected tasks before and
+-- after it so that the change can be undone and redone.
+CREATE TABLE IF NOT EXISTS task_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    description TEXT NOT NULL,
+    changes TEXT NOT NULL,
+    created INTEGER NOT NULL,
+    undone BOOL NOT NULL DEFAULT 0
+);
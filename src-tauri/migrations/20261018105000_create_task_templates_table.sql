@@ -0,0 +1,21 @@
+-- Templates for tasks which are created over and over. The title and description may contain
+-- `{placeholders}` and the scheduled dates are ISO 8601 spans from the start of today.
+CREATE TABLE IF NOT EXISTS task_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    priority INTEGER NOT NULL DEFAULT 2,
+    estimated_duration INTEGER,
+    start_offset TEXT,
+    due_offset TEXT,
+    project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS task_template_tags (
+    template_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (template_id, tag_id),
+    FOREIGN KEY(template_id) REFERENCES task_templates(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
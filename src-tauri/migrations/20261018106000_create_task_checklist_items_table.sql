@@ -0,0 +1,13 @@
+-- Checklist items break a task into steps which are checked off as they are done.
+CREATE TABLE IF NOT EXISTS task_checklist_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    checked BOOL NOT NULL DEFAULT 0,
+    position INTEGER NOT NULL DEFAULT 0,
+    estimated_duration INTEGER,
+    checked_at INTEGER,
+    FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_checklist_items_task_id ON task_checklist_items(task_id, position);
@@ -0,0 +1,14 @@
+-- Lightweight links between tasks. Each link is stored once, from the task it was made on, and
+-- is shown on both tasks.
+CREATE TABLE IF NOT EXISTS task_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL,
+    linked_task_id INTEGER NOT NULL,
+    link_type TEXT NOT NULL,
+    created INTEGER NOT NULL,
+    UNIQUE(task_id, linked_task_id, link_type),
+    FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY(linked_task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_links_linked_task_id ON task_links(linked_task_id);
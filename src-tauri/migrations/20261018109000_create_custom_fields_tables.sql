@@ -0,0 +1,23 @@
+-- User defined fields which every task can have a value for.
+CREATE TABLE IF NOT EXISTS custom_fields (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    field_type TEXT NOT NULL,
+    -- The choices of a select field, as a JSON array of strings.
+    options TEXT NOT NULL DEFAULT '[]'
+);
+
+-- Only the column matching the type of the field is set, so that values sort and compare
+-- naturally.
+CREATE TABLE IF NOT EXISTS task_custom_field_values (
+    task_id INTEGER NOT NULL,
+    field_id INTEGER NOT NULL,
+    text_value TEXT,
+    number_value REAL,
+    date_value INTEGER,
+    PRIMARY KEY(task_id, field_id),
+    FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY(field_id) REFERENCES custom_fields(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_custom_field_values_field_id ON task_custom_field_values(field_id);
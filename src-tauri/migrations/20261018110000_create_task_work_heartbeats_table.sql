@@ -0,0 +1,10 @@
+-- The last time the app saw each running session, so that sessions left running while the app
+-- was closed or the machine was asleep can be found and closed where the work really stopped.
+--
+-- There is no foreign key, since undoing a change rewrites the work history of a task with the
+-- same ids and the heartbeats of its running sessions need to survive that. Heartbeats of
+-- sessions which are no longer running are removed with the next heartbeat instead.
+CREATE TABLE IF NOT EXISTS task_work_heartbeats (
+    history_id INTEGER PRIMARY KEY NOT NULL,
+    beat INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- How many seconds without input before the running tasks are paused, 0 turns idle detection off.
+ALTER TABLE user_settings ADD COLUMN idle_timeout INTEGER NOT NULL DEFAULT 300;
@@ -0,0 +1,18 @@
+-- The countdown timer is a single row so that it carries on after the app is restarted.
+CREATE TABLE IF NOT EXISTS countdown_timer (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    initial_duration INTEGER NOT NULL DEFAULT 0,
+    -- The time left when the timer was last started or paused, in seconds.
+    remaining_duration INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'Stopped',
+    -- When the running timer was last started.
+    started_at INTEGER NULL,
+    message TEXT NOT NULL DEFAULT 'Beep beep, time has run out.'
+);
+
+INSERT INTO countdown_timer (id, initial_duration, remaining_duration)
+SELECT 1, user_settings.default_timer, user_settings.default_timer
+FROM user_settings
+LIMIT 1;
+
+INSERT OR IGNORE INTO countdown_timer (id) VALUES (1);
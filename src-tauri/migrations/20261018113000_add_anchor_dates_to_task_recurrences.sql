@@ -0,0 +1,6 @@
+-- Fixed intervals are counted from where the series was anchored, so that clamped dates such as
+-- the 31st in a shorter month do not carry over into the following occurrences.
+ALTER TABLE task_recurrences ADD COLUMN anchor_start_date INTEGER;
+ALTER TABLE task_recurrences ADD COLUMN anchor_complete_date INTEGER;
+-- The occurrence the anchor dates belong to.
+ALTER TABLE task_recurrences ADD COLUMN anchor_occurrence INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::{QueryBuilder, Sqlite, SqliteExecutor, Transaction};
+use tauri::State;
+
+use crate::{
+    features::{journal::JournalChange, tasks::UnixTimestamp},
+    query_utils::add_in_expression,
+    Data,
+};
+
+use super::{TaskEvent, TaskEventDetails, TaskEventRead};
+
+/// Write the activity of the tasks touched by a change.
+///
+/// ### Args
+/// * source - What caused the change, shown with each event.
+pub async fn record_task_events(
+    changes: &Vec<JournalChange>,
+    source: &str,
+    created: UnixTimestamp,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let mut tag_ids: Vec<i64> = changes
+        .iter()
+        .flat_map(|change| change.before.iter().chain(change.after.iter()))
+        .flat_map(|snapshot| snapshot.tag_ids.iter().copied())
+        .collect();
+    tag_ids.sort();
+    tag_ids.dedup();
+
+    let mut tag_values = HashMap::new();
+    if !tag_ids.is_empty() {
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("SELECT tags.id, tags.value FROM tags WHERE tags.id");
+        add_in_expression(&mut builder, &tag_ids);
+
+        tag_values = builder
+            .build_query_as::<(i64, String)>()
+            .fetch_all(&mut **transaction)
+            .await
+            .into_ta_result()?
+            .into_iter()
+            .collect();
+    }
+
+    for change in changes.iter() {
+        let events =
+            TaskEventDetails::between(change.before.as_ref(), change.after.as_ref(), &tag_values);
+
+        for event in events {
+            record_task_event(change.task_id, &event, source, created, &mut **transaction).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a single event to the activity of a task, for changes which are not journaled.
+pub async fn record_task_event<'e>(
+    task_id: i64,
+    event: &TaskEventDetails,
+    source: &str,
+    created: UnixTimestamp,
+    executor: impl SqliteExecutor<'e>,
+) -> TAResult<()> {
+    let kind = event.kind();
+    let details = serde_json::to_string(event).into_ta_result()?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_events (task_id, kind, details, source, created)
+        VALUES (?, ?, ?, ?, ?)
+    "#,
+        task_id,
+        kind,
+        details,
+        source,
+        created
+    )
+    .execute(executor)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Get the activity of a task, oldest first.
+///
+/// ### Args
+/// * kinds - Only include these kinds of events, e.g. `statusChanged`. Everything when empty.
+#[tauri::command]
+pub async fn get_task_activity(
+    task_id: i64,
+    kinds: Option<Vec<String>>,
+    db: State<'_, Data>,
+) -> TAResult<Vec<TaskEventRead>> {
+    let kinds = kinds.unwrap_or_default();
+
+    let mut builder =
+        QueryBuilder::<Sqlite>::new("SELECT * FROM task_events WHERE task_events.task_id = ");
+    builder.push_bind(task_id);
+
+    if !kinds.is_empty() {
+        builder.push(" AND task_events.kind");
+        add_in_expression(&mut builder, &kinds);
+    }
+
+    builder.push(" ORDER BY task_events.created ASC, task_events.id ASC");
+
+    let events = builder
+        .build_query_as::<TaskEvent>()
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    events
+        .into_iter()
+        .map(TaskEventRead::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .into_ta_result()
+}
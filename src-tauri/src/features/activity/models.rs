@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::prelude::FromRow;
+
+use crate::features::{
+    journal::{TaskSnapshot, WorkHistorySnapshot},
+    tasks::{
+        ChecklistItem, Frequency, LinkType, OptionalUnixTimestamp, Status, Task, UnixTimestamp,
+    },
+};
+
+/// Something which happened to a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TaskEventDetails {
+    Created {
+        title: String,
+    },
+    /// A field of the task changed, with the values as they are sent to the frontend.
+    FieldChanged {
+        field: String,
+        old: Value,
+        new: Value,
+    },
+    StatusChanged {
+        old: Status,
+        new: Status,
+    },
+    Trashed,
+    Restored,
+    TagAdded {
+        tag_id: i64,
+        tag: Option<String>,
+    },
+    TagRemoved {
+        tag_id: i64,
+        tag: Option<String>,
+    },
+    TagRenamed {
+        tag_id: i64,
+        old_value: String,
+        new_value: String,
+    },
+    CommentAdded {
+        comment_id: i64,
+        message: String,
+    },
+    CommentEdited {
+        comment_id: i64,
+        old_message: String,
+        new_message: String,
+    },
+    CommentTrashed {
+        comment_id: i64,
+    },
+    CommentRestored {
+        comment_id: i64,
+    },
+    CommentDeleted {
+        comment_id: i64,
+    },
+    WorkHistoryAdded {
+        history_id: i64,
+        start_date: Timestamp,
+        end_date: Option<Timestamp>,
+    },
+    WorkHistoryEdited {
+        history_id: i64,
+        old_start_date: Timestamp,
+        old_end_date: Option<Timestamp>,
+        new_start_date: Timestamp,
+        new_end_date: Option<Timestamp>,
+    },
+    WorkHistoryDeleted {
+        history_id: i64,
+        start_date: Timestamp,
+        end_date: Option<Timestamp>,
+    },
+    DependencyAdded {
+        depends_on_task_id: i64,
+    },
+    DependencyRemoved {
+        depends_on_task_id: i64,
+    },
+    RecurrenceSet {
+        frequency: Frequency,
+        interval: i64,
+    },
+    RecurrenceRemoved,
+    ChecklistItemAdded {
+        item_id: i64,
+        title: String,
+    },
+    ChecklistItemEdited {
+        item_id: i64,
+        old_title: String,
+        new_title: String,
+    },
+    ChecklistItemChecked {
+        item_id: i64,
+        title: String,
+    },
+    ChecklistItemUnchecked {
+        item_id: i64,
+        title: String,
+    },
+    ChecklistItemRemoved {
+        item_id: i64,
+        title: String,
+    },
+    ChecklistReordered,
+    AttachmentAdded {
+        attachment_id: i64,
+        name: String,
+    },
+    AttachmentRemoved {
+        attachment_id: i64,
+        name: String,
+    },
+    /// A link to another task, `outgoing` when it was made on this task rather than the other.
+    LinkAdded {
+        link_type: LinkType,
+        linked_task_id: i64,
+        outgoing: bool,
+    },
+    LinkRemoved {
+        link_type: LinkType,
+        linked_task_id: i64,
+        outgoing: bool,
+    },
+    CustomFieldChanged {
+        field_id: i64,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl TaskEventDetails {
+    /// The name of the event, stored alongside it so that the activity can be filtered.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskEventDetails::Created { .. } => "created",
+            TaskEventDetails::FieldChanged { .. } => "fieldChanged",
+            TaskEventDetails::StatusChanged { .. } => "statusChanged",
+            TaskEventDetails::Trashed => "trashed",
+            TaskEventDetails::Restored => "restored",
+            TaskEventDetails::TagAdded { .. } => "tagAdded",
+            TaskEventDetails::TagRemoved { .. } => "tagRemoved",
+            TaskEventDetails::TagRenamed { .. } => "tagRenamed",
+            TaskEventDetails::CommentAdded { .. } => "commentAdded",
+            TaskEventDetails::CommentEdited { .. } => "commentEdited",
+            TaskEventDetails::CommentTrashed { .. } => "commentTrashed",
+            TaskEventDetails::CommentRestored { .. } => "commentRestored",
+            TaskEventDetails::CommentDeleted { .. } => "commentDeleted",
+            TaskEventDetails::WorkHistoryAdded { .. } => "workHistoryAdded",
+            TaskEventDetails::WorkHistoryEdited { .. } => "workHistoryEdited",
+            TaskEventDetails::WorkHistoryDeleted { .. } => "workHistoryDeleted",
+            TaskEventDetails::DependencyAdded { .. } => "dependencyAdded",
+            TaskEventDetails::DependencyRemoved { .. } => "dependencyRemoved",
+            TaskEventDetails::RecurrenceSet { .. } => "recurrenceSet",
+            TaskEventDetails::RecurrenceRemoved => "recurrenceRemoved",
+            TaskEventDetails::ChecklistItemAdded { .. } => "checklistItemAdded",
+            TaskEventDetails::ChecklistItemEdited { .. } => "checklistItemEdited",
+            TaskEventDetails::ChecklistItemChecked { .. } => "checklistItemChecked",
+            TaskEventDetails::ChecklistItemUnchecked { .. } => "checklistItemUnchecked",
+            TaskEventDetails::ChecklistItemRemoved { .. } => "checklistItemRemoved",
+            TaskEventDetails::ChecklistReordered => "checklistReordered",
+            TaskEventDetails::AttachmentAdded { .. } => "attachmentAdded",
+            TaskEventDetails::AttachmentRemoved { .. } => "attachmentRemoved",
+            TaskEventDetails::LinkAdded { .. } => "linkAdded",
+            TaskEventDetails::LinkRemoved { .. } => "linkRemoved",
+            TaskEventDetails::CustomFieldChanged { .. } => "customFieldChanged",
+        }
+    }
+
+    /// Work out what happened to a task from its state before and after a change.
+    ///
+    /// A task which no longer exists has no activity left to add to, so removing one produces
+    /// nothing. `tag_values` names the tags so that the feed still reads well once they are gone.
+    pub fn between(
+        before: Option<&TaskSnapshot>,
+        after: Option<&TaskSnapshot>,
+        tag_values: &HashMap<i64, String>,
+    ) -> Vec<TaskEventDetails> {
+        match (before, after) {
+            (None, Some(after)) => vec![TaskEventDetails::Created {
+                title: after.task.title.clone(),
+            }],
+            (Some(before), Some(after)) => {
+                let mut events = task_events(&before.task, &after.task);
+                events.extend(tag_events(before, after, tag_values));
+                events.extend(comment_events(before, after));
+                events.extend(work_history_events(before, after));
+                events.extend(dependency_events(before, after));
+                events.extend(recurrence_events(before, after));
+                events.extend(checklist_events(before, after));
+                events.extend(link_events(before, after));
+                events.extend(custom_field_events(before, after));
+                events
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn timestamp_value(value: OptionalUnixTimestamp) -> Value {
+    json!(Option::<UnixTimestamp>::from(value).map(Timestamp::from))
+}
+
+fn to_timestamp(seconds: i64) -> Timestamp {
+    UnixTimestamp::from(seconds).into()
+}
+
+fn field_changed(field: &str, old: Value, new: Value) -> Option<TaskEventDetails> {
+    match old == new {
+        true => None,
+        false => Some(TaskEventDetails::FieldChanged {
+            field: field.to_string(),
+            old,
+            new,
+        }),
+    }
+}
+
+fn task_events(before: &Task, after: &Task) -> Vec<TaskEventDetails> {
+    let mut events: Vec<TaskEventDetails> = [
+        field_changed("title", json!(before.title), json!(after.title)),
+        field_changed(
+            "description",
+            json!(before.description),
+            json!(after.description),
+        ),
+        field_changed(
+            "scheduledStartDate",
+            timestamp_value(before.scheduled_start_date),
+            timestamp_value(after.scheduled_start_date),
+        ),
+        field_changed(
+            "scheduledCompleteDate",
+            timestamp_value(before.scheduled_complete_date),
+            timestamp_value(after.scheduled_complete_date),
+        ),
+        field_changed(
+            "estimatedDuration",
+            json!(Option::<i64>::from(before.estimated_duration.clone())),
+            json!(Option::<i64>::from(after.estimated_duration.clone())),
+        ),
+        field_changed("priority", json!(before.priority), json!(after.priority)),
+        field_changed(
+            "projectId",
+            json!(before.project_id),
+            json!(after.project_id),
+        ),
+        field_changed(
+            "parentTaskId",
+            json!(before.parent_task_id),
+            json!(after.parent_task_id),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if before.status != after.status {
+        events.push(TaskEventDetails::StatusChanged {
+            old: before.status.clone(),
+            new: after.status.clone(),
+        });
+    }
+
+    match (
+        before.deleted_at == OptionalUnixTimestamp::none(),
+        after.deleted_at == OptionalUnixTimestamp::none(),
+    ) {
+        (true, false) => events.push(TaskEventDetails::Trashed),
+        (false, true) => events.push(TaskEventDetails::Restored),
+        _ => {}
+    }
+
+    events
+}
+
+fn tag_events(
+    before: &TaskSnapshot,
+    after: &TaskSnapshot,
+    tag_values: &HashMap<i64, String>,
+) -> Vec<TaskEventDetails> {
+    let removed = before
+        .tag_ids
+        .iter()
+        .filter(|tag_id| !after.tag_ids.contains(tag_id))
+        .map(|tag_id| TaskEventDetails::TagRemoved {
+            tag_id: *tag_id,
+            tag: tag_values.get(tag_id).cloned(),
+        });
+
+    let added = after
+        .tag_ids
+        .iter()
+        .filter(|tag_id| !before.tag_ids.contains(tag_id))
+        .map(|tag_id| TaskEventDetails::TagAdded {
+            tag_id: *tag_id,
+            tag: tag_values.get(tag_id).cloned(),
+        });
+
+    removed.chain(added).collect()
+}
+
+fn comment_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let mut events = Vec::new();
+
+    for old in before.comments.iter() {
+        let Some(new) = after.comments.iter().find(|comment| comment.id == old.id) else {
+            events.push(TaskEventDetails::CommentDeleted { comment_id: old.id });
+            continue;
+        };
+
+        if old.message != new.message {
+            events.push(TaskEventDetails::CommentEdited {
+                comment_id: old.id,
+                old_message: old.message.clone(),
+                new_message: new.message.clone(),
+            });
+        }
+
+        match (
+            old.deleted_at == OptionalUnixTimestamp::none(),
+            new.deleted_at == OptionalUnixTimestamp::none(),
+        ) {
+            (true, false) => events.push(TaskEventDetails::CommentTrashed { comment_id: old.id }),
+            (false, true) => events.push(TaskEventDetails::CommentRestored { comment_id: old.id }),
+            _ => {}
+        }
+    }
+
+    events.extend(
+        after
+            .comments
+            .iter()
+            .filter(|new| !before.comments.iter().any(|old| old.id == new.id))
+            .map(|new| TaskEventDetails::CommentAdded {
+                comment_id: new.id,
+                message: new.message.clone(),
+            }),
+    );
+
+    events
+}
+
+fn work_history_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let find = |history: &Vec<WorkHistorySnapshot>, id: i64| {
+        history.iter().find(|session| session.id == id).cloned()
+    };
+
+    let mut events = Vec::new();
+
+    for old in before.work_history.iter() {
+        match find(&after.work_history, old.id) {
+            None => events.push(TaskEventDetails::WorkHistoryDeleted {
+                history_id: old.id,
+                start_date: to_timestamp(old.start_date),
+                end_date: old.end_date.map(to_timestamp),
+            }),
+            Some(new) if new != *old => events.push(TaskEventDetails::WorkHistoryEdited {
+                history_id: old.id,
+                old_start_date: to_timestamp(old.start_date),
+                old_end_date: old.end_date.map(to_timestamp),
+                new_start_date: to_timestamp(new.start_date),
+                new_end_date: new.end_date.map(to_timestamp),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for new in after.work_history.iter() {
+        if find(&before.work_history, new.id).is_none() {
+            events.push(TaskEventDetails::WorkHistoryAdded {
+                history_id: new.id,
+                start_date: to_timestamp(new.start_date),
+                end_date: new.end_date.map(to_timestamp),
+            });
+        }
+    }
+
+    events
+}
+
+fn dependency_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let removed = before
+        .depends_on_task_ids
+        .iter()
+        .filter(|id| !after.depends_on_task_ids.contains(id))
+        .map(|id| TaskEventDetails::DependencyRemoved {
+            depends_on_task_id: *id,
+        });
+
+    let added = after
+        .depends_on_task_ids
+        .iter()
+        .filter(|id| !before.depends_on_task_ids.contains(id))
+        .map(|id| TaskEventDetails::DependencyAdded {
+            depends_on_task_id: *id,
+        });
+
+    removed.chain(added).collect()
+}
+
+fn link_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let removed = before
+        .links
+        .iter()
+        .filter(|old| !after.links.iter().any(|new| new.id == old.id))
+        .map(|link| TaskEventDetails::LinkRemoved {
+            link_type: link.link_type,
+            linked_task_id: link.linked_task_id,
+            outgoing: true,
+        });
+
+    let added = after
+        .links
+        .iter()
+        .filter(|new| !before.links.iter().any(|old| old.id == new.id))
+        .map(|link| TaskEventDetails::LinkAdded {
+            link_type: link.link_type,
+            linked_task_id: link.linked_task_id,
+            outgoing: true,
+        });
+
+    removed.chain(added).collect()
+}
+
+fn custom_field_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let mut field_ids: Vec<i64> = before
+        .custom_field_values
+        .iter()
+        .chain(after.custom_field_values.iter())
+        .map(|value| value.field_id)
+        .collect();
+    field_ids.sort();
+    field_ids.dedup();
+
+    let value_of = |snapshot: &TaskSnapshot, field_id: i64| {
+        json!(snapshot
+            .custom_field_values
+            .iter()
+            .find(|value| value.field_id == field_id)
+            .and_then(|value| value.value()))
+    };
+
+    field_ids
+        .into_iter()
+        .filter_map(|field_id| {
+            let old = value_of(before, field_id);
+            let new = value_of(after, field_id);
+
+            (old != new).then_some(TaskEventDetails::CustomFieldChanged { field_id, old, new })
+        })
+        .collect()
+}
+
+fn recurrence_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    match (&before.recurrence, &after.recurrence) {
+        (Some(_), None) => vec![TaskEventDetails::RecurrenceRemoved],
+        (old, Some(new))
+            if old
+                .as_ref()
+                .map(|old| (&old.frequency, old.interval, old.weekdays))
+                != Some((&new.frequency, new.interval, new.weekdays)) =>
+        {
+            vec![TaskEventDetails::RecurrenceSet {
+                frequency: new.frequency.clone(),
+                interval: new.interval,
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn checklist_events(before: &TaskSnapshot, after: &TaskSnapshot) -> Vec<TaskEventDetails> {
+    let mut events = Vec::new();
+
+    for old in before.checklist.iter() {
+        let Some(new) = after.checklist.iter().find(|item| item.id == old.id) else {
+            events.push(TaskEventDetails::ChecklistItemRemoved {
+                item_id: old.id,
+                title: old.title.clone(),
+            });
+            continue;
+        };
+
+        if old.title != new.title {
+            events.push(TaskEventDetails::ChecklistItemEdited {
+                item_id: old.id,
+                old_title: old.title.clone(),
+                new_title: new.title.clone(),
+            });
+        }
+
+        match (old.checked, new.checked) {
+            (false, true) => events.push(TaskEventDetails::ChecklistItemChecked {
+                item_id: new.id,
+                title: new.title.clone(),
+            }),
+            (true, false) => events.push(TaskEventDetails::ChecklistItemUnchecked {
+                item_id: new.id,
+                title: new.title.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    events.extend(
+        after
+            .checklist
+            .iter()
+            .filter(|new| !before.checklist.iter().any(|old| old.id == new.id))
+            .map(|new| TaskEventDetails::ChecklistItemAdded {
+                item_id: new.id,
+                title: new.title.clone(),
+            }),
+    );
+
+    // Only the items on both sides count, so that adding or removing one is not a reorder.
+    let order = |checklist: &Vec<ChecklistItem>, other: &Vec<ChecklistItem>| {
+        let mut items: Vec<(i64, i64)> = checklist
+            .iter()
+            .filter(|item| other.iter().any(|o| o.id == item.id))
+            .map(|item| (item.position, item.id))
+            .collect();
+        items.sort();
+        items.into_iter().map(|(_, id)| id).collect::<Vec<i64>>()
+    };
+
+    if order(&before.checklist, &after.checklist) != order(&after.checklist, &before.checklist) {
+        events.push(TaskEventDetails::ChecklistReordered);
+    }
+
+    events
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskEvent {
+    pub id: i64,
+    pub task_id: i64,
+    pub kind: String,
+    /// The details serialized as JSON.
+    pub details: String,
+    /// What caused the event, the name of the command or the undo or redo of one.
+    pub source: String,
+    pub created: UnixTimestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskEventRead {
+    pub id: i64,
+    pub task_id: i64,
+    pub details: TaskEventDetails,
+    pub source: String,
+    pub created: Timestamp,
+}
+
+impl TryFrom<TaskEvent> for TaskEventRead {
+    type Error = serde_json::Error;
+
+    fn try_from(value: TaskEvent) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            task_id: value.task_id,
+            details: serde_json::from_str(&value.details)?,
+            source: value.source,
+            created: value.created.into(),
+        })
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, Context};
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_shell::ShellExt;
+
+use crate::{
+    features::{
+        activity::{record_task_event, TaskEventDetails},
+        tasks::UnixTimestamp,
+    },
+    Data,
+};
+
+use super::{attachment_store_path, blob_path, guess_mime_type, Attachment, AttachmentRead};
+
+fn not_found_message(attachment_id: i64) -> String {
+    format!("Attachment with id '{}' not found.", attachment_id)
+}
+
+fn store_path(app: &AppHandle) -> TAResult<PathBuf> {
+    let app_data_dir = app.path().app_data_dir().into_ta_result()?;
+    Ok(attachment_store_path(&app_data_dir))
+}
+
+async fn find_attachment(attachment_id: i64, db: &Data) -> TAResult<Attachment> {
+    let attachment = sqlx::query_as!(
+        Attachment,
+        "SELECT * FROM attachments WHERE attachments.id = ?",
+        attachment_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match attachment {
+        Some(attachment) => Ok(attachment),
+        None => anyhow_tauri::bail!(not_found_message(attachment_id)),
+    }
+}
+
+/// Tells apart the files being copied into the store at the same time.
+static PARTIAL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Copy the file into the store while hashing it, unless a blob with the same hash is already
+/// there. The file is read in chunks, so that large files are never held in memory.
+///
+/// ### Returns
+/// The hash and the size of the contents.
+fn store_blob(store: &Path, source: &mut impl Read) -> anyhow::Result<(String, u64)> {
+    fs::create_dir_all(store)?;
+
+    // Write beside the blobs first, so that an interrupted copy never looks like a whole blob.
+    let partial_path = store.join(format!(
+        "{}-{}.partial",
+        std::process::id(),
+        PARTIAL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let (hash, size) = match copy_hashing(source, &partial_path) {
+        Ok(copied) => copied,
+        Err(e) => {
+            let _ = fs::remove_file(&partial_path);
+            return Err(e);
+        }
+    };
+
+    let path = blob_path(store, &hash);
+    if path.exists() {
+        fs::remove_file(&partial_path)?;
+    } else {
+        let directory = path
+            .parent()
+            .expect("a blob should always be inside a directory of the store");
+        fs::create_dir_all(directory)?;
+        fs::rename(&partial_path, &path)?;
+    }
+
+    Ok((hash, size))
+}
+
+/// Copy everything from the reader into a new file, hashing it on the way.
+fn copy_hashing(source: &mut impl Read, destination: &Path) -> anyhow::Result<(String, u64)> {
+    let mut writer = BufWriter::new(File::create(destination)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        size += read as u64;
+    }
+
+    writer.flush()?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// Delete every blob in the store which no attachment uses any more, e.g. once the tasks they
+/// were attached to have been purged from the trash.
+///
+/// Only run this when the application starts. A file being attached is in the store before its
+/// attachment is saved, so collecting while the app is running could delete it.
+///
+/// ### Returns
+/// How many blobs were deleted.
+pub async fn collect_orphaned_attachments(
+    store: &Path,
+    pool: &SqlitePool,
+) -> anyhow::Result<usize> {
+    if !store.exists() {
+        return Ok(0);
+    }
+
+    let referenced: HashSet<String> =
+        sqlx::query_scalar!("SELECT DISTINCT attachments.hash FROM attachments")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut removed = 0;
+    for directory in fs::read_dir(store)? {
+        let directory = directory?.path();
+        if !directory.is_dir() {
+            continue;
+        }
+
+        for blob in fs::read_dir(&directory)? {
+            let blob = blob?.path();
+            let name = blob
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+
+            if !referenced.contains(name) {
+                fs::remove_file(&blob)?;
+                removed += 1;
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Open a file in the application the operating system uses for it.
+///
+/// The shell plugin hands the path to the operating system as a single argument, so that
+/// nothing in the name of the file is run as a command.
+fn open_with_default_application(app: &AppHandle, path: &Path) -> anyhow::Result<()> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| anyhow!("'{}' is not a valid path.", path.display()))?;
+
+    // The opener plugin is meant to replace this, but the app is set up with the shell plugin.
+    #[allow(deprecated)]
+    let opened = app.shell().open(path, None);
+
+    opened.with_context(|| format!("Unable to open '{}'", path))
+}
+
+/// Attach a copy of a file to a task. The original file can be moved or deleted afterwards.
+///
+/// ### Args
+/// * path - The path of the file to attach.
+#[tauri::command]
+pub async fn attach_file(
+    task_id: i64,
+    path: String,
+    app: AppHandle,
+    db: State<'_, Data>,
+) -> TAResult<AttachmentRead> {
+    let task_count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!: i64" FROM tasks WHERE tasks.id = ? AND tasks.deleted_at IS NULL"#,
+        task_id
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    if task_count == 0 {
+        anyhow_tauri::bail!(format!("Task with id '{}' not found.", task_id));
+    }
+
+    let source = PathBuf::from(&path);
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| anyhow!("'{}' is not a file.", path))
+        .into_ta_result()?;
+    let store = store_path(&app)?;
+
+    // Copying a large file takes a while, so it is kept off the threads which run the commands.
+    let (hash, size) = tauri::async_runtime::spawn_blocking(move || {
+        let mut file = File::open(&source).with_context(|| format!("Unable to read '{}'", path))?;
+
+        store_blob(&store, &mut file).with_context(|| format!("Unable to copy '{}'", path))
+    })
+    .await
+    .into_ta_result()?
+    .into_ta_result()?;
+
+    let mime_type = guess_mime_type(&name);
+    let size = size as i64;
+    let created = UnixTimestamp::now();
+
+    let attachment = sqlx::query_as!(
+        Attachment,
+        r#"
+        INSERT INTO attachments (task_id, name, mime_type, size, hash, created)
+        VALUES (?, ?, ?, ?, ?, ?)
+        RETURNING *
+    "#,
+        task_id,
+        name,
+        mime_type,
+        size,
+        hash,
+        created
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let event = TaskEventDetails::AttachmentAdded {
+        attachment_id: attachment.id,
+        name: attachment.name.clone(),
+    };
+    record_task_event(task_id, &event, "Attach file", created, &db.pool).await?;
+
+    Ok(attachment.into())
+}
+
+#[tauri::command]
+pub async fn get_task_attachments(
+    task_id: i64,
+    db: State<'_, Data>,
+) -> TAResult<Vec<AttachmentRead>> {
+    let attachments = sqlx::query_as!(
+        Attachment,
+        r#"
+        SELECT *
+        FROM attachments
+        WHERE attachments.task_id = ?
+        ORDER BY attachments.created ASC, attachments.id ASC
+    "#,
+        task_id
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(attachments.into_iter().map(AttachmentRead::from).collect())
+}
+
+/// Open an attachment in the application the operating system uses for its kind of file.
+///
+/// The blob is copied out under its original name first, so that the right application is
+/// picked and nothing it does can change the stored contents.
+#[tauri::command]
+pub async fn open_attachment(
+    attachment_id: i64,
+    app: AppHandle,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let attachment = find_attachment(attachment_id, &db).await?;
+    let blob = blob_path(&store_path(&app)?, &attachment.hash);
+
+    if !blob.exists() {
+        anyhow_tauri::bail!(format!(
+            "The contents of the attachment '{}' are missing.",
+            attachment.name
+        ));
+    }
+
+    let directory = app
+        .path()
+        .app_cache_dir()
+        .into_ta_result()?
+        .join("attachments")
+        .join(attachment.id.to_string());
+    fs::create_dir_all(&directory).into_ta_result()?;
+
+    let copy = directory.join(&attachment.name);
+    fs::copy(&blob, &copy).into_ta_result()?;
+
+    open_with_default_application(&app, &copy).into_ta_result()
+}
+
+/// Remove an attachment from its task. Contents which nothing else uses are deleted the next
+/// time the application starts.
+#[tauri::command]
+pub async fn remove_attachment(attachment_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let attachment = find_attachment(attachment_id, &db).await?;
+
+    sqlx::query!(
+        "DELETE FROM attachments WHERE attachments.id = ?",
+        attachment_id
+    )
+    .execute(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let event = TaskEventDetails::AttachmentRemoved {
+        attachment_id,
+        name: attachment.name,
+    };
+    record_task_event(
+        attachment.task_id,
+        &event,
+        "Remove attachment",
+        UnixTimestamp::now(),
+        &db.pool,
+    )
+    .await
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::features::tasks::UnixTimestamp;
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct Attachment {
+    pub id: i64,
+    pub task_id: i64,
+    /// The name of the file when it was attached.
+    pub name: String,
+    pub mime_type: String,
+    /// The size of the file in bytes.
+    pub size: i64,
+    /// The SHA-256 hash of the contents as lowercase hex, which names the blob in the store.
+    pub hash: String,
+    pub created: UnixTimestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentRead {
+    pub id: i64,
+    pub task_id: i64,
+    pub name: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub hash: String,
+    pub created: Timestamp,
+}
+
+impl From<Attachment> for AttachmentRead {
+    fn from(value: Attachment) -> Self {
+        Self {
+            id: value.id,
+            task_id: value.task_id,
+            name: value.name,
+            mime_type: value.mime_type,
+            size: value.size,
+            hash: value.hash,
+            created: value.created.into(),
+        }
+    }
+}
+
+/// The directory holding the contents of every attachment, named by their hash.
+pub fn attachment_store_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("attachments")
+}
+
+/// Where the blob with the given hash is stored, fanned out by the first two characters so that
+/// no single directory grows too large.
+pub fn blob_path(store: &Path, hash: &str) -> PathBuf {
+    store.join(&hash[..2]).join(hash)
+}
+
+/// Guess the mime type of a file from its extension.
+pub fn guess_mime_type(name: &str) -> &'static str {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("txt") | Some("log") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("html") | Some("htm") => "text/html",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("zip") => "application/zip",
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::{Sqlite, Transaction};
+use tauri::State;
+
+use crate::Data;
+
+use super::{
+    CustomField, CustomFieldRead, CustomFieldType, EditCustomField, NewCustomField,
+    SetCustomFieldValue, TaskCustomFieldValue,
+};
+
+fn not_found_message(field_id: i64) -> String {
+    format!("Unable to find a custom field with the id {}", field_id)
+}
+
+fn validate_name(name: &str) -> TAResult<String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        anyhow_tauri::bail!("A custom field must have a name.");
+    }
+
+    Ok(name.to_string())
+}
+
+/// Trim the options of a select field, dropping blank and repeated ones.
+fn validate_options(field_type: CustomFieldType, options: &Vec<String>) -> TAResult<Vec<String>> {
+    if field_type != CustomFieldType::Select {
+        return Ok(Vec::new());
+    }
+
+    let mut valid_options: Vec<String> = Vec::new();
+    for option in options.iter().map(|option| option.trim()) {
+        if !option.is_empty() && !valid_options.iter().any(|valid| valid == option) {
+            valid_options.push(option.to_string());
+        }
+    }
+
+    if valid_options.is_empty() {
+        anyhow_tauri::bail!("A select field must have at least one option.");
+    }
+
+    Ok(valid_options)
+}
+
+async fn ensure_name_available(name: &str, field_id: Option<i64>, db: &Data) -> TAResult<()> {
+    let existing_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!: i64"
+        FROM custom_fields
+        WHERE custom_fields.name = ? COLLATE NOCASE
+        AND custom_fields.id IS NOT ?
+    "#,
+        name,
+        field_id
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    if existing_count > 0 {
+        anyhow_tauri::bail!(format!("A custom field named '{}' already exists.", name));
+    }
+
+    Ok(())
+}
+
+async fn find_custom_field(field_id: i64, db: &Data) -> TAResult<CustomField> {
+    let field = sqlx::query_as!(
+        CustomField,
+        "SELECT * FROM custom_fields WHERE custom_fields.id = ?",
+        field_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match field {
+        Some(field) => Ok(field),
+        None => anyhow_tauri::bail!(not_found_message(field_id)),
+    }
+}
+
+/// Write the values given for the custom fields of a task, leaving its other fields as they are.
+///
+/// Every value is checked against the type of its field first, so nothing is written when any of
+/// them is invalid.
+pub async fn set_task_custom_field_values(
+    task_id: i64,
+    values: &Vec<SetCustomFieldValue>,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let mut parsed_values = Vec::with_capacity(values.len());
+    for value in values.iter() {
+        let field = sqlx::query_as!(
+            CustomField,
+            "SELECT * FROM custom_fields WHERE custom_fields.id = ?",
+            value.field_id
+        )
+        .fetch_optional(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+        let Some(field) = field else {
+            anyhow_tauri::bail!(not_found_message(value.field_id));
+        };
+
+        parsed_values.push((field.id, field.parse_value(&value.value).into_ta_result()?));
+    }
+
+    for (field_id, value) in parsed_values {
+        let Some(value) = value else {
+            sqlx::query!(
+                r#"
+                DELETE FROM task_custom_field_values
+                WHERE task_custom_field_values.task_id = ?
+                AND task_custom_field_values.field_id = ?
+            "#,
+                task_id,
+                field_id
+            )
+            .execute(&mut **transaction)
+            .await
+            .into_ta_result()?;
+
+            continue;
+        };
+
+        let stored = TaskCustomFieldValue::new(task_id, field_id, value);
+        sqlx::query!(
+            r#"
+            INSERT INTO task_custom_field_values (task_id, field_id, text_value, number_value, date_value)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(task_id, field_id) DO UPDATE
+            SET text_value = excluded.text_value,
+            number_value = excluded.number_value,
+            date_value = excluded.date_value
+        "#,
+            stored.task_id,
+            stored.field_id,
+            stored.text_value,
+            stored.number_value,
+            stored.date_value
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_custom_fields(db: State<'_, Data>) -> TAResult<Vec<CustomFieldRead>> {
+    let fields = sqlx::query_as!(
+        CustomField,
+        "SELECT * FROM custom_fields ORDER BY LOWER(custom_fields.name) ASC"
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    fields
+        .into_iter()
+        .map(CustomFieldRead::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .into_ta_result()
+}
+
+#[tauri::command]
+pub async fn add_custom_field(
+    new_field: NewCustomField,
+    db: State<'_, Data>,
+) -> TAResult<CustomFieldRead> {
+    let name = validate_name(&new_field.name)?;
+    ensure_name_available(&name, None, &db).await?;
+    let options = validate_options(new_field.field_type, &new_field.options)?;
+    let options = serde_json::to_string(&options).into_ta_result()?;
+
+    let field = sqlx::query_as!(
+        CustomField,
+        r#"
+        INSERT INTO custom_fields (name, field_type, options)
+        VALUES (?, ?, ?)
+        RETURNING *
+    "#,
+        name,
+        new_field.field_type,
+        options
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    CustomFieldRead::try_from(field).into_ta_result()
+}
+
+/// Rename a custom field or change the options of a select field.
+///
+/// An option cannot be removed while a task still has it as its value.
+#[tauri::command]
+pub async fn edit_custom_field(edit_field: EditCustomField, db: State<'_, Data>) -> TAResult<()> {
+    let field = find_custom_field(edit_field.id, &db).await?;
+    let name = validate_name(&edit_field.name)?;
+    ensure_name_available(&name, Some(field.id), &db).await?;
+    let options = validate_options(field.field_type, &edit_field.options)?;
+
+    if field.field_type == CustomFieldType::Select {
+        let used_options = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT tcfv.text_value AS "text_value!: String"
+            FROM task_custom_field_values tcfv
+            WHERE tcfv.field_id = ?
+            AND tcfv.text_value IS NOT NULL
+        "#,
+            field.id
+        )
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?;
+
+        let removed: Vec<String> = used_options
+            .into_iter()
+            .filter(|option| !options.contains(option))
+            .collect();
+
+        if !removed.is_empty() {
+            anyhow_tauri::bail!(format!(
+                "Unable to remove the options {} of '{}' while tasks still use them.",
+                removed.join(", "),
+                field.name
+            ));
+        }
+    }
+
+    let options = serde_json::to_string(&options).into_ta_result()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE custom_fields
+        SET name = ?,
+        options = ?
+        WHERE custom_fields.id = ?
+    "#,
+        name,
+        options,
+        field.id
+    )
+    .execute(&db.pool)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Delete a custom field along with the value every task had for it.
+#[tauri::command]
+pub async fn delete_custom_field(field_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    sqlx::query!(
+        "DELETE FROM custom_fields WHERE custom_fields.id = ?",
+        field_id
+    )
+    .execute(&db.pool)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod models;
+
+pub use commands::*;
+pub use models::*;
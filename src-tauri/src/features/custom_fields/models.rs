@@ -0,0 +1,259 @@
+use anyhow::bail;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+use timely_macros::EnumFromString;
+
+use crate::features::tasks::OptionalUnixTimestamp;
+
+/// The kind of value a custom field holds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, sqlx::Type, EnumFromString)]
+#[sqlx(type_name = "TEXT")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Date,
+    /// One of a fixed list of options.
+    Select,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct CustomField {
+    pub id: i64,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    /// The options of a select field, as a JSON array.
+    pub options: String,
+}
+
+impl CustomField {
+    pub fn options(&self) -> serde_json::Result<Vec<String>> {
+        serde_json::from_str(&self.options)
+    }
+
+    /// Check a value given for the field against its type.
+    ///
+    /// ### Returns
+    /// The value to store, nothing when the value is `null` or blank and the field should be
+    /// cleared.
+    pub fn parse_value(&self, value: &Value) -> anyhow::Result<Option<CustomFieldValue>> {
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        let parsed = match (self.field_type, value) {
+            (CustomFieldType::Text, Value::String(text)) => match text.trim() {
+                "" => None,
+                text => Some(CustomFieldValue::Text(text.to_string())),
+            },
+            (CustomFieldType::Number, Value::Number(number)) => match number.as_f64() {
+                Some(number) if number.is_finite() => Some(CustomFieldValue::Number(number)),
+                _ => bail!("'{}' is not a valid number for '{}'.", number, self.name),
+            },
+            (CustomFieldType::Date, Value::String(text)) => match text.parse::<Timestamp>() {
+                Ok(date) => Some(CustomFieldValue::Date(date)),
+                Err(e) => bail!("'{}' is not a valid date for '{}': {}", text, self.name, e),
+            },
+            (CustomFieldType::Select, Value::String(option)) => {
+                if !self.options()?.contains(option) {
+                    bail!("'{}' is not one of the options of '{}'.", option, self.name);
+                }
+
+                Some(CustomFieldValue::Text(option.clone()))
+            }
+            (field_type, value) => bail!(
+                "'{}' expects a {} value, but was given {}.",
+                self.name,
+                format!("{:?}", field_type).to_lowercase(),
+                value
+            ),
+        };
+
+        Ok(parsed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldRead {
+    pub id: i64,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub options: Vec<String>,
+}
+
+impl TryFrom<CustomField> for CustomFieldRead {
+    type Error = serde_json::Error;
+
+    fn try_from(value: CustomField) -> Result<Self, Self::Error> {
+        Ok(Self {
+            options: value.options()?,
+            id: value.id,
+            name: value.name,
+            field_type: value.field_type,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewCustomField {
+    pub name: String,
+    pub field_type: CustomFieldType,
+    /// The options of a select field, ignored for every other type.
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// The type of a field cannot be changed, since the values stored for it would no longer fit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditCustomField {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// The value of a custom field, written as a plain number, date or string.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CustomFieldValue {
+    Number(f64),
+    Date(Timestamp),
+    Text(String),
+}
+
+/// A value of a custom field as it is stored, with only the column for its type set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TaskCustomFieldValue {
+    pub task_id: i64,
+    pub field_id: i64,
+    pub text_value: Option<String>,
+    pub number_value: Option<f64>,
+    pub date_value: OptionalUnixTimestamp,
+}
+
+impl TaskCustomFieldValue {
+    pub fn new(task_id: i64, field_id: i64, value: CustomFieldValue) -> Self {
+        let mut stored = Self {
+            task_id,
+            field_id,
+            text_value: None,
+            number_value: None,
+            date_value: OptionalUnixTimestamp::none(),
+        };
+
+        match value {
+            CustomFieldValue::Number(number) => stored.number_value = Some(number),
+            CustomFieldValue::Date(date) => stored.date_value = OptionalUnixTimestamp::some(date),
+            CustomFieldValue::Text(text) => stored.text_value = Some(text),
+        }
+
+        stored
+    }
+
+    pub fn value(&self) -> Option<CustomFieldValue> {
+        let date: Option<Timestamp> = self.date_value.into();
+
+        match (&self.text_value, self.number_value, date) {
+            (_, Some(number), _) => Some(CustomFieldValue::Number(number)),
+            (_, _, Some(date)) => Some(CustomFieldValue::Date(date)),
+            (Some(text), _, _) => Some(CustomFieldValue::Text(text.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// A value to give a custom field of a task, checked against the type of the field when it is
+/// written. A `null` value clears the field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCustomFieldValue {
+    pub field_id: i64,
+    pub value: Value,
+}
+
+/// A value of a custom field loaded along with its field, to show on a task.
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskCustomFieldRow {
+    pub task_id: i64,
+    pub field_id: i64,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub text_value: Option<String>,
+    pub number_value: Option<f64>,
+    pub date_value: OptionalUnixTimestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCustomFieldRead {
+    pub field_id: i64,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub value: CustomFieldValue,
+}
+
+impl TaskCustomFieldRow {
+    pub fn into_read(self) -> Option<TaskCustomFieldRead> {
+        let value = TaskCustomFieldValue {
+            task_id: self.task_id,
+            field_id: self.field_id,
+            text_value: self.text_value,
+            number_value: self.number_value,
+            date_value: self.date_value,
+        }
+        .value()?;
+
+        Some(TaskCustomFieldRead {
+            field_id: self.field_id,
+            name: self.name,
+            field_type: self.field_type,
+            value,
+        })
+    }
+}
+
+/// A condition on the value of a custom field when searching tasks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CustomFieldCondition {
+    /// The task has a value for the field.
+    IsSet,
+    /// The task has no value for the field.
+    IsNotSet,
+    /// A text or select value is one of these, ignoring case.
+    OneOf { values: Vec<String> },
+    /// A text value contains the text, ignoring case.
+    Contains { text: String },
+    /// A number value is in the inclusive range, either end may be left open.
+    NumberRange { min: Option<f64>, max: Option<f64> },
+    /// A date value is in the inclusive range, either end may be left open.
+    DateRange {
+        start: Option<Timestamp>,
+        end: Option<Timestamp>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldFilter {
+    pub field_id: i64,
+    pub condition: CustomFieldCondition,
+}
+
+/// Sorting by `custom_field:<id>` orders tasks by their value for that field.
+const CUSTOM_FIELD_ORDER_PREFIX: &str = "custom_field:";
+
+/// The custom field to sort by, when the order is for one.
+pub fn custom_field_order(order_by: &str) -> Option<i64> {
+    order_by
+        .strip_prefix(CUSTOM_FIELD_ORDER_PREFIX)
+        .and_then(|field_id| field_id.parse().ok())
+}
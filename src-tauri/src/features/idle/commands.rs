@@ -0,0 +1,454 @@
+use std::thread;
+
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::SqlitePool;
+use tauri::{async_runtime::block_on, AppHandle, Emitter, Manager, State};
+
+use crate::{
+    features::{
+        journal::JournalRecording,
+        sessions::pause_if_not_running,
+        tasks::{add_task_work_history, NewTaskWorkHistory, Status, UnixTimestamp},
+    },
+    Data,
+};
+
+use super::{
+    IdleChange, IdleResolution, IdleSession, IdleSource, IdleSpan, IdleSpanRead, IdleTracker,
+    IDLE_ENDED_EVENT, IDLE_POLL_INTERVAL, IDLE_STARTED_EVENT,
+};
+
+/// Pauses the running tasks once the user has been idle for longer than the idle timeout, and
+/// notices when they come back.
+pub struct IdleWatcher<S: IdleSource> {
+    source: S,
+    span: Option<IdleSpan>,
+}
+
+impl<S: IdleSource> IdleWatcher<S> {
+    pub fn new(source: S) -> Self {
+        Self { source, span: None }
+    }
+
+    /// Compare the idle time against the idle timeout, pausing the running tasks when the user
+    /// has just gone idle.
+    pub async fn check(&mut self, db: &Data) -> TAResult<Option<IdleChange>> {
+        let idle_seconds = self.source.idle_time().into_ta_result()?.as_secs() as i64;
+        let idle_timeout = sqlx::query_scalar!("SELECT idle_timeout FROM user_settings LIMIT 1")
+            .fetch_one(&db.pool)
+            .await
+            .into_ta_result()?;
+        let last_input = UnixTimestamp::from(UnixTimestamp::now().as_seconds() - idle_seconds);
+        let idle = idle_timeout > 0 && idle_seconds >= idle_timeout;
+
+        match self.span.take() {
+            Some(mut span) if !idle => {
+                span.end = Some(last_input);
+                Ok(Some(IdleChange::Ended(span)))
+            }
+            Some(span) => {
+                self.span = Some(span);
+                Ok(None)
+            }
+            None if idle => {
+                let sessions = pause_for_idle(last_input, db).await?;
+                if sessions.is_empty() {
+                    return Ok(None);
+                }
+
+                let span = IdleSpan {
+                    start: last_input,
+                    end: None,
+                    sessions,
+                };
+                self.span = Some(span.clone());
+                Ok(Some(IdleChange::Started(span)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// End the sessions of the tasks in progress when the user was last seen and pause the tasks.
+///
+/// ### Returns
+/// The sessions which were ended.
+async fn pause_for_idle(last_input: UnixTimestamp, db: &Data) -> TAResult<Vec<IdleSession>> {
+    let doing = Status::Doing;
+
+    let sessions = sqlx::query_as!(
+        IdleSession,
+        r#"
+        SELECT twh.id AS history_id,
+        twh.task_id,
+        tasks.title AS task_title
+        FROM task_work_history twh
+        INNER JOIN tasks ON tasks.id = twh.task_id
+        WHERE twh.end_date IS NULL
+        AND twh.start_date < ?
+        AND tasks.status = ?
+        AND tasks.deleted_at IS NULL
+        ORDER BY twh.start_date ASC
+    "#,
+        last_input,
+        doing
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    if sessions.is_empty() {
+        return Ok(sessions);
+    }
+
+    let task_ids = sessions.iter().map(|session| session.task_id).collect();
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Pause for inactivity", task_ids, &mut transaction).await?;
+
+        for session in sessions.iter() {
+            sqlx::query!(
+                r#"
+                UPDATE task_work_history
+                SET end_date = ?
+                WHERE task_work_history.id = ?
+                AND task_work_history.end_date IS NULL
+            "#,
+                last_input,
+                session.history_id
+            )
+            .execute(&mut *transaction)
+            .await
+            .into_ta_result()?;
+
+            pause_if_not_running(session.task_id, &mut transaction).await?;
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result()?,
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            return Err(e);
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Watch for inactivity in the background for as long as the app runs.
+///
+/// The frontend is told when the running tasks are paused and again when the user is back, at
+/// which point the idle span waits in the [IdleTracker] until it is resolved.
+pub fn start_idle_watcher(app: AppHandle, pool: SqlitePool, source: impl IdleSource + 'static) {
+    thread::spawn(move || {
+        let db = Data { pool };
+        let mut watcher = IdleWatcher::new(source);
+
+        loop {
+            match block_on(watcher.check(&db)) {
+                Ok(Some(IdleChange::Started(span))) => {
+                    println!("Paused {} session(s) for inactivity.", span.sessions.len());
+
+                    if let Err(e) = app.emit(IDLE_STARTED_EVENT, IdleSpanRead::from(span)) {
+                        println!("Unable to report the inactivity: {}", e);
+                    }
+                }
+                Ok(Some(IdleChange::Ended(span))) => {
+                    *app.state::<IdleTracker>().pending.lock().unwrap() = Some(span.clone());
+
+                    if let Err(e) = app.emit(IDLE_ENDED_EVENT, IdleSpanRead::from(span)) {
+                        println!("Unable to report the end of the inactivity: {}", e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => println!("Unable to check for inactivity: {:?}", e),
+            }
+
+            thread::sleep(IDLE_POLL_INTERVAL);
+        }
+    });
+}
+
+/// The idle span the user has not decided what to do with yet, only the latest one is kept.
+#[tauri::command]
+pub async fn get_idle_span(tracker: State<'_, IdleTracker>) -> TAResult<Option<IdleSpanRead>> {
+    let span = tracker.pending.lock().unwrap().clone();
+
+    Ok(span.map(IdleSpanRead::from))
+}
+
+/// Decide what to do with the time the user was away.
+#[tauri::command]
+pub async fn resolve_idle_span(
+    resolution: IdleResolution,
+    tracker: State<'_, IdleTracker>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let span = tracker.pending.lock().unwrap().clone();
+
+    let Some(span) = span else {
+        anyhow_tauri::bail!("There is no idle time waiting to be resolved.");
+    };
+    let end = span.end.unwrap_or(UnixTimestamp::now());
+
+    match resolution {
+        IdleResolution::Keep => keep_idle_span(&span, end, &db).await?,
+        IdleResolution::Discard => {}
+        IdleResolution::Reassign { task_id } => {
+            let new_task_work_history = NewTaskWorkHistory {
+                task_id,
+                start_date: span.start.into(),
+                end_date: end.into(),
+            };
+
+            add_task_work_history(new_task_work_history, None, db).await?;
+        }
+    }
+
+    let mut pending = tracker.pending.lock().unwrap();
+    if pending.as_ref() == Some(&span) {
+        *pending = None;
+    }
+
+    Ok(())
+}
+
+/// Count the idle time as work on the paused tasks.
+///
+/// A session is running again when nothing has touched it or its task since and no other timer
+/// is in the way, otherwise it is only stretched up to when the user came back.
+async fn keep_idle_span(span: &IdleSpan, end: UnixTimestamp, db: &Data) -> TAResult<()> {
+    let task_ids = span
+        .sessions
+        .iter()
+        .map(|session| session.task_id)
+        .collect();
+    let doing = Status::Doing;
+    let paused = Status::Paused;
+    let allow_concurrent_timers =
+        sqlx::query_scalar!("SELECT allow_concurrent_timers FROM user_settings LIMIT 1")
+            .fetch_one(&db.pool)
+            .await
+            .into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Keep idle time", task_ids, &mut transaction).await?;
+
+        for session in span.sessions.iter() {
+            let reopened = sqlx::query!(
+                r#"
+                UPDATE task_work_history
+                SET end_date = NULL
+                WHERE task_work_history.id = ?
+                AND task_work_history.end_date = ?
+                AND EXISTS (
+                    SELECT 1
+                    FROM tasks
+                    WHERE tasks.id = task_work_history.task_id
+                    AND tasks.status = ?)
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM task_work_history twh
+                    WHERE twh.end_date IS NULL
+                    AND (twh.task_id = task_work_history.task_id OR NOT ?))
+            "#,
+                session.history_id,
+                span.start,
+                paused,
+                allow_concurrent_timers
+            )
+            .execute(&mut *transaction)
+            .await
+            .into_ta_result()?
+            .rows_affected();
+
+            if reopened == 0 {
+                sqlx::query!(
+                    r#"
+                    UPDATE task_work_history
+                    SET end_date = ?
+                    WHERE task_work_history.id = ?
+                    AND task_work_history.end_date = ?
+                "#,
+                    end,
+                    session.history_id,
+                    span.start
+                )
+                .execute(&mut *transaction)
+                .await
+                .into_ta_result()?;
+
+                continue;
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE tasks
+                SET status = ?
+                WHERE tasks.id = ?
+                AND tasks.status = ?
+            "#,
+                doing,
+                session.task_id,
+                paused
+            )
+            .execute(&mut *transaction)
+            .await
+            .into_ta_result()?;
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::features::idle::FakeIdleSource;
+
+    use super::*;
+
+    async fn seed(running_tasks: i64) -> Data {
+        // An in-memory database only lives as long as its connection, so the pool keeps just one.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        for task in 1..=running_tasks {
+            let task_id =
+                sqlx::query("INSERT INTO tasks (title, description, status) VALUES (?, ?, ?)")
+                    .bind(format!("Task {}", task))
+                    .bind("Seeded for the idle watcher")
+                    .bind(Status::Doing)
+                    .execute(&pool)
+                    .await
+                    .unwrap()
+                    .last_insert_rowid();
+
+            sqlx::query("INSERT INTO task_work_history (task_id, start_date) VALUES (?, ?)")
+                .bind(task_id)
+                .bind(UnixTimestamp::now().as_seconds() - 3600)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        Data { pool }
+    }
+
+    async fn sessions(db: &Data) -> Vec<(Status, Option<i64>)> {
+        sqlx::query_as(
+            r#"
+            SELECT tasks.status, twh.end_date
+            FROM tasks
+            INNER JOIN task_work_history twh ON twh.task_id = tasks.id
+            ORDER BY tasks.id ASC
+        "#,
+        )
+        .fetch_all(&db.pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn check_pauses_the_running_tasks_until_the_user_returns() {
+        block_on(async {
+            let db = seed(2).await;
+            let source = FakeIdleSource::default();
+            let mut watcher = IdleWatcher::new(source.clone());
+
+            source.set_idle_time(Duration::from_secs(60));
+            assert_eq!(watcher.check(&db).await.unwrap(), None);
+
+            // Idle for longer than the default timeout of five minutes.
+            source.set_idle_time(Duration::from_secs(600));
+            let Some(IdleChange::Started(started)) = watcher.check(&db).await.unwrap() else {
+                panic!("The user should have gone idle.");
+            };
+            let start = started.start.as_seconds();
+            assert!((UnixTimestamp::now().as_seconds() - 600 - start).abs() <= 1);
+            assert_eq!(started.end, None);
+            assert_eq!(started.sessions.len(), 2);
+            assert_eq!(
+                sessions(&db).await,
+                vec![(Status::Paused, Some(start)), (Status::Paused, Some(start))]
+            );
+
+            source.set_idle_time(Duration::from_secs(900));
+            assert_eq!(watcher.check(&db).await.unwrap(), None);
+
+            source.set_idle_time(Duration::from_secs(5));
+            let Some(IdleChange::Ended(ended)) = watcher.check(&db).await.unwrap() else {
+                panic!("The user should have returned.");
+            };
+            let end = ended
+                .end
+                .expect("A returned user should end the span.")
+                .as_seconds();
+            assert!((UnixTimestamp::now().as_seconds() - 5 - end).abs() <= 1);
+            assert_eq!(ended.start, started.start);
+            assert_eq!(ended.sessions, started.sessions);
+
+            assert_eq!(watcher.check(&db).await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn check_ignores_idle_time_without_running_tasks() {
+        block_on(async {
+            let db = seed(0).await;
+            let source = FakeIdleSource::default();
+            let mut watcher = IdleWatcher::new(source.clone());
+
+            for idle_seconds in [600, 900, 5] {
+                source.set_idle_time(Duration::from_secs(idle_seconds));
+                assert_eq!(
+                    watcher.check(&db).await.unwrap(),
+                    None,
+                    "{:?}",
+                    idle_seconds
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn check_ignores_idle_time_when_the_timeout_is_off() {
+        block_on(async {
+            let db = seed(1).await;
+            sqlx::query("UPDATE user_settings SET idle_timeout = 0")
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            let source = FakeIdleSource::default();
+            let mut watcher = IdleWatcher::new(source.clone());
+
+            source.set_idle_time(Duration::from_secs(86_400));
+            assert_eq!(watcher.check(&db).await.unwrap(), None);
+            assert_eq!(sessions(&db).await, vec![(Status::Doing, None)]);
+        });
+    }
+}
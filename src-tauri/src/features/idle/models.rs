@@ -0,0 +1,143 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use user_idle::UserIdle;
+
+use crate::features::tasks::UnixTimestamp;
+
+/// How often the idle time is checked.
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Emitted with the idle span when the running tasks are paused for inactivity.
+pub const IDLE_STARTED_EVENT: &str = "idle-started";
+
+/// Emitted with the idle span once the user is back, so they can decide what to do with it.
+pub const IDLE_ENDED_EVENT: &str = "idle-ended";
+
+/// Tells how long the user has gone without any input.
+pub trait IdleSource: Send + Sync {
+    fn idle_time(&self) -> anyhow::Result<Duration>;
+}
+
+/// The idle time reported by the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemIdleSource;
+
+impl IdleSource for SystemIdleSource {
+    fn idle_time(&self) -> anyhow::Result<Duration> {
+        let idle =
+            UserIdle::get_time().map_err(|e| anyhow!("Unable to read the idle time: {:?}", e))?;
+
+        Ok(Duration::from_secs(idle.as_seconds()))
+    }
+}
+
+/// An idle time which is set by hand, to drive the idle watcher without a real user.
+#[derive(Debug, Clone, Default)]
+pub struct FakeIdleSource {
+    idle_time: Arc<Mutex<Duration>>,
+}
+
+impl FakeIdleSource {
+    pub fn set_idle_time(&self, idle_time: Duration) {
+        *self.idle_time.lock().unwrap() = idle_time;
+    }
+}
+
+impl IdleSource for FakeIdleSource {
+    fn idle_time(&self) -> anyhow::Result<Duration> {
+        Ok(*self.idle_time.lock().unwrap())
+    }
+}
+
+/// A session which was ended because the user went idle.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct IdleSession {
+    pub history_id: i64,
+    pub task_id: i64,
+    pub task_title: String,
+}
+
+/// A stretch of time without input during which tasks were running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleSpan {
+    /// When the last input happened, where the running sessions were ended.
+    pub start: UnixTimestamp,
+    /// When the user came back, nothing while they are still away.
+    pub end: Option<UnixTimestamp>,
+    pub sessions: Vec<IdleSession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleSessionRead {
+    pub history_id: i64,
+    pub task_id: i64,
+    pub task_title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleSpanRead {
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
+    /// How long the user has been away, in seconds.
+    pub duration: i64,
+    pub sessions: Vec<IdleSessionRead>,
+}
+
+impl From<IdleSpan> for IdleSpanRead {
+    fn from(value: IdleSpan) -> Self {
+        Self {
+            start: value.start.into(),
+            end: value.end.map(Timestamp::from),
+            duration: value.end.unwrap_or(UnixTimestamp::now()) - value.start,
+            sessions: value
+                .sessions
+                .into_iter()
+                .map(|session| IdleSessionRead {
+                    history_id: session.history_id,
+                    task_id: session.task_id,
+                    task_title: session.task_title,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What the idle watcher noticed when it last checked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdleChange {
+    /// The user went idle and the running tasks were paused.
+    Started(IdleSpan),
+    /// The user came back.
+    Ended(IdleSpan),
+}
+
+/// The idle span waiting for the user to decide what to do with it.
+#[derive(Debug, Default)]
+pub struct IdleTracker {
+    pub pending: Mutex<Option<IdleSpan>>,
+}
+
+/// What to do with the time the user was away.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum IdleResolution {
+    /// Count the time as work on the paused tasks and keep them running.
+    Keep,
+    /// Leave the time out, the paused tasks stay paused.
+    Discard,
+    /// Count the time as a finished session of another task.
+    Reassign { task_id: i64 },
+}
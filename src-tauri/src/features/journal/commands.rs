@@ -0,0 +1,640 @@
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::{Sqlite, SqliteConnection, Transaction};
+use tauri::State;
+
+use crate::{
+    features::{
+        activity::record_task_events,
+        custom_fields::TaskCustomFieldValue,
+        tasks::{ChecklistItem, Comment, Task, TaskLink, TaskRecurrence, UnixTimestamp},
+    },
+    Data,
+};
+
+use super::{JournalChange, JournalEntry, JournalEntryRead, TaskSnapshot, WorkHistorySnapshot};
+
+/// How many entries are kept to undo.
+const JOURNAL_LIMIT: i64 = 100;
+
+/// Records how a command changes tasks so that it can be undone.
+///
+/// The recording lives in the transaction the command makes its changes in. Start it before the
+/// command changes anything, naming every task it may change, note every task the command
+/// creates, and finish it before committing. That way the entry holds the changes of the command
+/// and nothing else, and it is only kept when they are. Dropping the recording without finishing
+/// it records nothing.
+///
+/// Attachments and the tags, projects and custom fields themselves are not journaled. A task
+/// which has been changed by them since an entry was recorded, or by anything else which is not
+/// journaled, makes undoing or redoing the entry fail rather than losing that change.
+pub struct JournalRecording {
+    description: String,
+    before: Vec<(i64, Option<TaskSnapshot>)>,
+}
+
+impl JournalRecording {
+    pub async fn start(
+        description: impl Into<String>,
+        mut task_ids: Vec<i64>,
+        transaction: &mut Transaction<'_, Sqlite>,
+    ) -> TAResult<Self> {
+        task_ids.sort();
+        task_ids.dedup();
+
+        let mut before = Vec::with_capacity(task_ids.len());
+        for task_id in task_ids {
+            before.push((
+                task_id,
+                capture_snapshot(task_id, &mut **transaction).await?,
+            ));
+        }
+
+        Ok(Self {
+            description: description.into(),
+            before,
+        })
+    }
+
+    /// Note a task the command has created, so that undoing the entry deletes it again.
+    pub fn created(&mut self, task_id: i64) {
+        if !self.before.iter().any(|(id, _)| *id == task_id) {
+            self.before.push((task_id, None));
+        }
+    }
+
+    /// Record the changes and the activity of the tasks, clearing anything that could have been
+    /// redone.
+    pub async fn finish(self, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
+        let mut changes = Vec::new();
+        for (task_id, before) in self.before {
+            let after = capture_snapshot(task_id, &mut **transaction).await?;
+
+            if before != after {
+                changes.push(JournalChange {
+                    task_id,
+                    before,
+                    after,
+                });
+            }
+        }
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let created = UnixTimestamp::now();
+        record_task_events(&changes, &self.description, created, transaction).await?;
+        let changes = serde_json::to_string(&changes).into_ta_result()?;
+
+        sqlx::query!("DELETE FROM task_journal WHERE task_journal.undone = 1")
+            .execute(&mut **transaction)
+            .await
+            .into_ta_result()?;
+
+        let id = sqlx::query!(
+            "INSERT INTO task_journal (description, changes, created) VALUES (?, ?, ?)",
+            self.description,
+            changes,
+            created
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?
+        .last_insert_rowid();
+
+        let oldest_kept = id - JOURNAL_LIMIT;
+        sqlx::query!(
+            "DELETE FROM task_journal WHERE task_journal.id <= ?",
+            oldest_kept
+        )
+        .execute(&mut **transaction)
+        .await
+        .map(|_| ())
+        .into_ta_result()
+    }
+}
+
+/// Read everything stored about a task, or `None` when it does not exist.
+async fn capture_snapshot(
+    task_id: i64,
+    connection: &mut SqliteConnection,
+) -> TAResult<Option<TaskSnapshot>> {
+    let task = sqlx::query_as!(Task, "SELECT * FROM tasks WHERE tasks.id = ?", task_id)
+        .fetch_optional(&mut *connection)
+        .await
+        .into_ta_result()?;
+
+    let Some(task) = task else {
+        return Ok(None);
+    };
+
+    let tag_ids = sqlx::query_scalar!(
+        "SELECT task_tags.tag_id FROM task_tags WHERE task_tags.task_id = ? ORDER BY task_tags.tag_id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let comments = sqlx::query_as!(
+        Comment,
+        "SELECT * FROM comments WHERE comments.task_id = ? ORDER BY comments.id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let work_history = sqlx::query_as!(
+        WorkHistorySnapshot,
+        r#"
+        SELECT twh.id, twh.start_date, twh.end_date
+        FROM task_work_history twh
+        WHERE twh.task_id = ?
+        ORDER BY twh.id
+    "#,
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let recurrence = sqlx::query_as!(
+        TaskRecurrence,
+        "SELECT * FROM task_recurrences WHERE task_recurrences.task_id = ?",
+        task_id
+    )
+    .fetch_optional(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let depends_on_task_ids = sqlx::query_scalar!(
+        r#"
+        SELECT td.depends_on_task_id
+        FROM task_dependencies td
+        WHERE td.task_id = ?
+        ORDER BY td.depends_on_task_id
+    "#,
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let checklist = sqlx::query_as!(
+        ChecklistItem,
+        "SELECT * FROM task_checklist_items tci WHERE tci.task_id = ? ORDER BY tci.id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let links = sqlx::query_as!(
+        TaskLink,
+        "SELECT * FROM task_links tl WHERE tl.task_id = ? ORDER BY tl.id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let custom_field_values = sqlx::query_as!(
+        TaskCustomFieldValue,
+        "SELECT * FROM task_custom_field_values tcfv WHERE tcfv.task_id = ? ORDER BY tcfv.field_id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    let attachment_ids = sqlx::query_scalar!(
+        "SELECT attachments.id FROM attachments WHERE attachments.task_id = ? ORDER BY attachments.id",
+        task_id
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    Ok(Some(TaskSnapshot {
+        task,
+        tag_ids,
+        comments,
+        work_history,
+        recurrence,
+        depends_on_task_ids,
+        checklist,
+        links,
+        custom_field_values,
+        attachment_ids,
+    }))
+}
+
+/// Put the tasks back into the given states.
+///
+/// Tasks without a state are removed first, then the task rows are written oldest first so that
+/// parents exist before their subtasks, and finally everything which belongs to them is replaced.
+/// Links to projects, tags and tasks which no longer exist are dropped.
+async fn apply_states(
+    states: Vec<(i64, Option<TaskSnapshot>)>,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    for (task_id, _) in states.iter().filter(|(_, state)| state.is_none()) {
+        sqlx::query!("DELETE FROM tasks WHERE tasks.id = ?", task_id)
+            .execute(&mut **transaction)
+            .await
+            .into_ta_result()?;
+    }
+
+    let mut snapshots: Vec<TaskSnapshot> =
+        states.into_iter().filter_map(|(_, state)| state).collect();
+    snapshots.sort_by_key(|snapshot| snapshot.task.id);
+
+    for TaskSnapshot { task, .. } in snapshots.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO tasks (id, title, description, status, scheduled_start_date, scheduled_complete_date, estimated_duration, parent_task_id, priority, project_id, deleted_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, (SELECT parent.id FROM tasks parent WHERE parent.id = ?), ?, (SELECT projects.id FROM projects WHERE projects.id = ?), ?)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                description = excluded.description,
+                status = excluded.status,
+                scheduled_start_date = excluded.scheduled_start_date,
+                scheduled_complete_date = excluded.scheduled_complete_date,
+                estimated_duration = excluded.estimated_duration,
+                parent_task_id = excluded.parent_task_id,
+                priority = excluded.priority,
+                project_id = excluded.project_id,
+                deleted_at = excluded.deleted_at
+        "#,
+            task.id,
+            task.title,
+            task.description,
+            task.status,
+            task.scheduled_start_date,
+            task.scheduled_complete_date,
+            task.estimated_duration,
+            task.parent_task_id,
+            task.priority,
+            task.project_id,
+            task.deleted_at
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    for snapshot in snapshots.iter() {
+        replace_task_details(snapshot, transaction).await?;
+    }
+
+    Ok(())
+}
+
+async fn replace_task_details(
+    snapshot: &TaskSnapshot,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let task_id = snapshot.task.id;
+
+    sqlx::query!("DELETE FROM task_tags WHERE task_tags.task_id = ?", task_id)
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+    for tag_id in snapshot.tag_ids.iter() {
+        sqlx::query!(
+            "INSERT INTO task_tags (task_id, tag_id) SELECT ?, tags.id FROM tags WHERE tags.id = ?",
+            task_id,
+            tag_id
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!("DELETE FROM comments WHERE comments.task_id = ?", task_id)
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+    for comment in snapshot.comments.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO comments (id, task_id, message, created, modified, deleted_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+            comment.id,
+            task_id,
+            comment.message,
+            comment.created,
+            comment.modified,
+            comment.deleted_at
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_work_history WHERE task_work_history.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for history in snapshot.work_history.iter() {
+        sqlx::query!(
+            "INSERT INTO task_work_history (id, task_id, start_date, end_date) VALUES (?, ?, ?, ?)",
+            history.id,
+            task_id,
+            history.start_date,
+            history.end_date
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_recurrences WHERE task_recurrences.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    if let Some(recurrence) = &snapshot.recurrence {
+        sqlx::query!(
+            r#"
+            INSERT INTO task_recurrences (id, task_id, frequency, interval, weekdays, end_after_occurrences, end_date, occurrence, anchor_start_date, anchor_complete_date, anchor_occurrence)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+            recurrence.id,
+            task_id,
+            recurrence.frequency,
+            recurrence.interval,
+            recurrence.weekdays,
+            recurrence.end_after_occurrences,
+            recurrence.end_date,
+            recurrence.occurrence,
+            recurrence.anchor_start_date,
+            recurrence.anchor_complete_date,
+            recurrence.anchor_occurrence
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_dependencies WHERE task_dependencies.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for depends_on_task_id in snapshot.depends_on_task_ids.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO task_dependencies (task_id, depends_on_task_id)
+            SELECT ?, tasks.id FROM tasks WHERE tasks.id = ?
+        "#,
+            task_id,
+            depends_on_task_id
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_checklist_items WHERE task_checklist_items.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for item in snapshot.checklist.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO task_checklist_items (id, task_id, title, checked, position, estimated_duration, checked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+            item.id,
+            task_id,
+            item.title,
+            item.checked,
+            item.position,
+            item.estimated_duration,
+            item.checked_at
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_links WHERE task_links.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for link in snapshot.links.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO task_links (id, task_id, linked_task_id, link_type, created)
+            SELECT ?, ?, tasks.id, ?, ? FROM tasks WHERE tasks.id = ?
+        "#,
+            link.id,
+            task_id,
+            link.link_type,
+            link.created,
+            link.linked_task_id
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_custom_field_values WHERE task_custom_field_values.task_id = ?",
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for value in snapshot.custom_field_values.iter() {
+        sqlx::query!(
+            r#"
+            INSERT INTO task_custom_field_values (task_id, field_id, text_value, number_value, date_value)
+            SELECT ?, custom_fields.id, ?, ?, ? FROM custom_fields WHERE custom_fields.id = ?
+        "#,
+            task_id,
+            value.text_value,
+            value.number_value,
+            value.date_value,
+            value.field_id
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    Ok(())
+}
+
+/// Replay an entry of the journal in one transaction, putting its tasks back into the state
+/// from before the change when undoing or after it when redoing.
+///
+/// Fails when a task is no longer in the state the entry left it in, since replacing it would
+/// lose whatever changed it since.
+async fn replay(entry: JournalEntry, undo: bool, db: &Data) -> TAResult<JournalEntryRead> {
+    // Undoing runs the change backwards, from the state after it to the state before it.
+    let changes: Vec<JournalChange> = entry
+        .changes()
+        .into_ta_result()?
+        .into_iter()
+        .map(|change| match undo {
+            true => JournalChange {
+                task_id: change.task_id,
+                before: change.after,
+                after: change.before,
+            },
+            false => change,
+        })
+        .collect();
+    let states = changes
+        .iter()
+        .map(|change| (change.task_id, change.after.clone()))
+        .collect();
+    let source = match undo {
+        true => format!("Undo {}", entry.description.to_lowercase()),
+        false => format!("Redo {}", entry.description.to_lowercase()),
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result = async {
+        for change in changes.iter() {
+            let current = capture_snapshot(change.task_id, &mut transaction).await?;
+
+            if current != change.before {
+                let task = current
+                    .as_ref()
+                    .or(change.before.as_ref())
+                    .map(|snapshot| format!("'{}'", snapshot.task.title))
+                    .unwrap_or_else(|| format!("with id '{}'", change.task_id));
+
+                anyhow_tauri::bail!(format!(
+                    "Unable to {} '{}' as task {} has since been changed in a way which cannot be undone.",
+                    match undo {
+                        true => "undo",
+                        false => "redo",
+                    },
+                    entry.description,
+                    task
+                ));
+            }
+        }
+
+        apply_states(states, &mut transaction).await?;
+        record_task_events(&changes, &source, UnixTimestamp::now(), &mut transaction).await?;
+
+        sqlx::query!(
+            "UPDATE task_journal SET undone = ? WHERE task_journal.id = ?",
+            undo,
+            entry.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map(|_| ())
+        .into_ta_result()
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result()?,
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            return Err(e);
+        }
+    }
+
+    Ok(JournalEntryRead {
+        undone: undo,
+        ..JournalEntryRead::from(entry)
+    })
+}
+
+/// Undo the most recent change which has not been undone yet.
+///
+/// ### Returns
+/// The change which was undone, or nothing when there is nothing left to undo.
+#[tauri::command]
+pub async fn undo_last(db: State<'_, Data>) -> TAResult<Option<JournalEntryRead>> {
+    let entry = sqlx::query_as!(
+        JournalEntry,
+        r#"
+        SELECT *
+        FROM task_journal
+        WHERE task_journal.undone = 0
+        ORDER BY task_journal.id DESC
+        LIMIT 1
+    "#
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match entry {
+        Some(entry) => Ok(Some(replay(entry, true, &db).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Redo the change which was undone most recently.
+///
+/// ### Returns
+/// The change which was redone, or nothing when there is nothing left to redo.
+#[tauri::command]
+pub async fn redo(db: State<'_, Data>) -> TAResult<Option<JournalEntryRead>> {
+    let entry = sqlx::query_as!(
+        JournalEntry,
+        r#"
+        SELECT *
+        FROM task_journal
+        WHERE task_journal.undone = 1
+        ORDER BY task_journal.id ASC
+        LIMIT 1
+    "#
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match entry {
+        Some(entry) => Ok(Some(replay(entry, false, &db).await?)),
+        None => Ok(None),
+    }
+}
+
+/// List the journal, newest first, so the user can see what undo and redo will do.
+#[tauri::command]
+pub async fn get_journal(db: State<'_, Data>) -> TAResult<Vec<JournalEntryRead>> {
+    let entries = sqlx::query_as!(
+        JournalEntry,
+        "SELECT * FROM task_journal ORDER BY task_journal.id DESC"
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(entries.into_iter().map(JournalEntryRead::from).collect())
+}
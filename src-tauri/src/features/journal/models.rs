@@ -0,0 +1,87 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::features::{
+    custom_fields::TaskCustomFieldValue,
+    tasks::{ChecklistItem, Comment, Task, TaskLink, TaskRecurrence, UnixTimestamp},
+};
+
+/// A session of work as it is stored, with no end date while it is still running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkHistorySnapshot {
+    pub id: i64,
+    pub start_date: i64,
+    pub end_date: Option<i64>,
+}
+
+/// Everything stored about a single task at one point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSnapshot {
+    pub task: Task,
+    pub tag_ids: Vec<i64>,
+    pub comments: Vec<Comment>,
+    pub work_history: Vec<WorkHistorySnapshot>,
+    pub recurrence: Option<TaskRecurrence>,
+    pub depends_on_task_ids: Vec<i64>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    /// The links made on this task. Links made on other tasks belong to their snapshots.
+    #[serde(default)]
+    pub links: Vec<TaskLink>,
+    #[serde(default)]
+    pub custom_field_values: Vec<TaskCustomFieldValue>,
+    /// The attachments of the task. They are only compared and never put back, as their files
+    /// are not kept for undoing.
+    #[serde(default)]
+    pub attachment_ids: Vec<i64>,
+}
+
+/// How a command changed one task. A missing snapshot means the task did not exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalChange {
+    pub task_id: i64,
+    pub before: Option<TaskSnapshot>,
+    pub after: Option<TaskSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub description: String,
+    /// The changes serialized as JSON.
+    pub changes: String,
+    pub created: UnixTimestamp,
+    pub undone: bool,
+}
+
+impl JournalEntry {
+    pub fn changes(&self) -> anyhow::Result<Vec<JournalChange>> {
+        serde_json::from_str(&self.changes).map_err(|e| {
+            anyhow::anyhow!(
+                "The journal entry '{}' could not be read: {}",
+                self.description,
+                e
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntryRead {
+    pub id: i64,
+    pub description: String,
+    pub created: Timestamp,
+    pub undone: bool,
+}
+
+impl From<JournalEntry> for JournalEntryRead {
+    fn from(value: JournalEntry) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            created: value.created.into(),
+            undone: value.undone,
+        }
+    }
+}
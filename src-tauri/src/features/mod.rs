@@ -1,4 +1,15 @@
+pub mod activity;
+pub mod attachments;
+pub mod custom_fields;
+pub mod idle;
+pub mod journal;
 pub mod metrics;
+pub mod projects;
+pub mod saved_searches;
+pub mod sessions;
 pub mod settings;
 pub mod tags;
 pub mod tasks;
+pub mod templates;
+pub mod timer;
+pub mod trash;
@@ -0,0 +1,242 @@
+use anyhow_tauri::{IntoTAResult, TAResult};
+use tauri::State;
+
+use crate::{
+    features::{
+        journal::JournalRecording,
+        tasks::{OptionalUnixTimestamp, Status},
+    },
+    Data,
+};
+
+use super::{
+    EditProject, NewProject, Project, ProjectRead, ProjectStatus, ProjectSummary, StatusCount,
+};
+
+fn not_found_message(project_id: i64) -> String {
+    format!("Unable to find a project with the id {}", project_id)
+}
+
+fn validate_name(name: &str) -> TAResult<String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        anyhow_tauri::bail!("A project must have a name.");
+    }
+
+    Ok(name.to_string())
+}
+
+pub async fn find_project(project_id: i64, db: &Data) -> TAResult<Project> {
+    let project = sqlx::query_as!(
+        Project,
+        "SELECT * FROM projects WHERE projects.id = ?",
+        project_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match project {
+        Some(project) => Ok(project),
+        None => anyhow_tauri::bail!(not_found_message(project_id)),
+    }
+}
+
+/// Get every project, optionally only those in one of the given statuses.
+#[tauri::command]
+pub async fn get_projects(
+    statuses: Option<Vec<ProjectStatus>>,
+    db: State<'_, Data>,
+) -> TAResult<Vec<ProjectRead>> {
+    let projects = sqlx::query_as!(
+        Project,
+        "SELECT * FROM projects ORDER BY LOWER(projects.name) ASC"
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(projects
+        .into_iter()
+        .filter(|project| match &statuses {
+            Some(statuses) if !statuses.is_empty() => statuses.contains(&project.status),
+            _ => true,
+        })
+        .map(ProjectRead::from)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_project(project_id: i64, db: State<'_, Data>) -> TAResult<ProjectRead> {
+    Ok(find_project(project_id, &db).await?.into())
+}
+
+#[tauri::command]
+pub async fn add_project(new_project: NewProject, db: State<'_, Data>) -> TAResult<ProjectRead> {
+    let name = validate_name(&new_project.name)?;
+    let target_date: OptionalUnixTimestamp = new_project.target_date.into();
+
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        INSERT INTO projects (name, description, status, client, target_date)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING *
+    "#,
+        name,
+        new_project.description,
+        new_project.status,
+        new_project.client,
+        target_date
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(project.into())
+}
+
+#[tauri::command]
+pub async fn edit_project(edit_project: EditProject, db: State<'_, Data>) -> TAResult<()> {
+    let name = validate_name(&edit_project.name)?;
+    let target_date: OptionalUnixTimestamp = edit_project.target_date.into();
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE projects
+        SET name = ?,
+        description = ?,
+        status = ?,
+        client = ?,
+        target_date = ?
+        WHERE projects.id = ?
+    "#,
+        name,
+        edit_project.description,
+        edit_project.status,
+        edit_project.client,
+        target_date,
+        edit_project.id
+    )
+    .execute(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    if result.rows_affected() == 0 {
+        anyhow_tauri::bail!(not_found_message(edit_project.id));
+    }
+
+    Ok(())
+}
+
+/// Delete a project, its tasks are kept without a project.
+#[tauri::command]
+pub async fn delete_project(project_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    sqlx::query!("DELETE FROM projects WHERE projects.id = ?", project_id)
+        .execute(&db.pool)
+        .await
+        .map(|_| ())
+        .into_ta_result()
+}
+
+/// Move a task into a project, or out of its project when no project is given.
+#[tauri::command]
+pub async fn set_task_project(
+    task_id: i64,
+    project_id: Option<i64>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    if let Some(project_id) = project_id {
+        find_project(project_id, &db).await?;
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Set project", vec![task_id], &mut transaction).await?;
+
+    let result = sqlx::query!(
+        "UPDATE tasks SET project_id = ? WHERE tasks.id = ?",
+        project_id,
+        task_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    if result.rows_affected() == 0 {
+        anyhow_tauri::bail!(format!("Unable to find a task with the id {}", task_id));
+    }
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Summarize the task statuses, time worked and estimates of a project.
+#[tauri::command]
+pub async fn get_project_summary(project_id: i64, db: State<'_, Data>) -> TAResult<ProjectSummary> {
+    let project = find_project(project_id, &db).await?;
+
+    let counts = sqlx::query!(
+        r#"
+        SELECT tasks.status AS "status!: Status", COUNT(*) AS "count!: i64"
+        FROM tasks
+        WHERE tasks.project_id = ?
+        AND tasks.deleted_at IS NULL
+        GROUP BY tasks.status
+    "#,
+        project_id
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let task_counts: Vec<StatusCount> = [
+        Status::Todo,
+        Status::Doing,
+        Status::Paused,
+        Status::Done,
+        Status::Cancelled,
+    ]
+    .into_iter()
+    .map(|status| StatusCount {
+        count: counts
+            .iter()
+            .find(|row| row.status == status)
+            .map(|row| row.count)
+            .unwrap_or(0),
+        status,
+    })
+    .collect();
+
+    let durations = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(CASE WHEN tasks.status <> 'Cancelled' THEN tasks.estimated_duration END), 0) AS "estimated_duration!: i64",
+            COALESCE(SUM(worked.elapsed), 0) AS "elapsed_duration!: i64",
+            COALESCE(SUM(CASE WHEN tasks.status = 'Done' THEN tasks.estimated_duration END), 0) AS "completed_estimated_duration!: i64",
+            COALESCE(SUM(CASE WHEN tasks.status = 'Done' AND tasks.estimated_duration IS NOT NULL THEN worked.elapsed END), 0) AS "completed_elapsed_duration!: i64"
+        FROM tasks
+        LEFT JOIN (
+            SELECT twh.task_id, SUM(COALESCE(twh.end_date, CAST(strftime('%s', 'now') as INTEGER)) - twh.start_date) AS elapsed
+            FROM task_work_history twh
+            GROUP BY twh.task_id
+        ) worked ON worked.task_id = tasks.id
+        WHERE tasks.project_id = ?
+        AND tasks.deleted_at IS NULL
+    "#,
+        project_id
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(ProjectSummary {
+        project: project.into(),
+        total_tasks: task_counts.iter().map(|count| count.count).sum(),
+        task_counts,
+        estimated_duration: durations.estimated_duration,
+        elapsed_duration: durations.elapsed_duration,
+        completed_estimated_duration: durations.completed_estimated_duration,
+        completed_elapsed_duration: durations.completed_elapsed_duration,
+    })
+}
@@ -0,0 +1,105 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use timely_macros::EnumFromString;
+
+use crate::features::tasks::{OptionalUnixTimestamp, Status};
+
+/// The state of a project as a whole.
+#[derive(
+    Default, Debug, PartialEq, Eq, Clone, Serialize, Deserialize, sqlx::Type, EnumFromString,
+)]
+#[sqlx(type_name = "TEXT")]
+pub enum ProjectStatus {
+    /// A project which is being worked on.
+    #[default]
+    Active,
+    /// A project which is waiting on something before work can continue.
+    OnHold,
+    /// A project where all of the work has been delivered.
+    Completed,
+    /// A project which is kept only for its history.
+    Archived,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub status: ProjectStatus,
+    pub client: Option<String>,
+    pub target_date: OptionalUnixTimestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRead {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub status: ProjectStatus,
+    pub client: Option<String>,
+    pub target_date: Option<Timestamp>,
+}
+
+impl From<Project> for ProjectRead {
+    fn from(value: Project) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            description: value.description,
+            status: value.status,
+            client: value.client,
+            target_date: value.target_date.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewProject {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub status: ProjectStatus,
+    pub client: Option<String>,
+    pub target_date: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditProject {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub status: ProjectStatus,
+    pub client: Option<String>,
+    pub target_date: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusCount {
+    pub status: Status,
+    pub count: i64,
+}
+
+/// The progress of a project, with durations in seconds.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSummary {
+    pub project: ProjectRead,
+    /// How many of the project's tasks are in each status, including statuses with none.
+    pub task_counts: Vec<StatusCount>,
+    pub total_tasks: i64,
+    /// The estimates of every task in the project which has not been cancelled.
+    pub estimated_duration: i64,
+    /// The time worked on every task in the project, with running sessions counted until now.
+    pub elapsed_duration: i64,
+    /// The estimates of the finished tasks, to compare against `completed_elapsed_duration`.
+    pub completed_estimated_duration: i64,
+    /// The time worked on the finished tasks which had an estimate.
+    pub completed_elapsed_duration: i64,
+}
@@ -0,0 +1,187 @@
+use anyhow_tauri::{IntoTAResult, TAResult};
+use tauri::State;
+
+use crate::{
+    features::tasks::{count_tasks, search_tasks, TaskRead, TaskSearchParams},
+    Data, PagedData, MAX_PAGE_SIZE,
+};
+
+use super::{EditSavedSearch, NewSavedSearch, SavedSearch, SavedSearchRead};
+
+fn not_found_message(saved_search_id: i64) -> String {
+    format!(
+        "Unable to find a saved search with the id {}",
+        saved_search_id
+    )
+}
+
+fn validate_name(name: &str) -> TAResult<String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        anyhow_tauri::bail!("A saved search must have a name.");
+    }
+
+    Ok(name.to_string())
+}
+
+/// Check that the search can be run before it is saved, so that it does not fail when listed.
+async fn validate_params(params: &TaskSearchParams, db: &Data) -> TAResult<String> {
+    if params.page < 1 {
+        anyhow_tauri::bail!("The page of a saved search must be at least 1.");
+    }
+
+    if !(1..=MAX_PAGE_SIZE as i64).contains(&params.page_size) {
+        anyhow_tauri::bail!(format!(
+            "The page size of a saved search must be between 1 and {}.",
+            MAX_PAGE_SIZE
+        ));
+    }
+
+    count_tasks(params, db).await?;
+    serde_json::to_string(params).into_ta_result()
+}
+
+async fn find_saved_search(saved_search_id: i64, db: &Data) -> TAResult<SavedSearch> {
+    let saved_search = sqlx::query_as!(
+        SavedSearch,
+        "SELECT * FROM saved_searches WHERE saved_searches.id = ?",
+        saved_search_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match saved_search {
+        Some(saved_search) => Ok(saved_search),
+        None => anyhow_tauri::bail!(not_found_message(saved_search_id)),
+    }
+}
+
+/// Read the saved search, reporting a search which cannot be read or run on the search itself.
+async fn to_saved_search_read(saved_search: SavedSearch, db: &Data) -> SavedSearchRead {
+    let (params, match_count, error) = match saved_search.search_params() {
+        Ok(params) => match count_tasks(&params, db).await {
+            Ok(match_count) => (Some(params), Some(match_count), None),
+            Err(e) => (Some(params), None, Some(e.to_string())),
+        },
+        Err(e) => (None, None, Some(e.to_string())),
+    };
+
+    SavedSearchRead {
+        id: saved_search.id,
+        name: saved_search.name,
+        params,
+        match_count,
+        error,
+    }
+}
+
+/// Get every saved search along with how many tasks currently match it.
+///
+/// A search which cannot be read or run is still listed, with the reason in its `error`.
+#[tauri::command]
+pub async fn get_saved_searches(db: State<'_, Data>) -> TAResult<Vec<SavedSearchRead>> {
+    let saved_searches = sqlx::query_as!(
+        SavedSearch,
+        "SELECT * FROM saved_searches ORDER BY LOWER(saved_searches.name) ASC"
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let mut saved_search_reads = Vec::with_capacity(saved_searches.len());
+    for saved_search in saved_searches {
+        saved_search_reads.push(to_saved_search_read(saved_search, &db).await);
+    }
+
+    Ok(saved_search_reads)
+}
+
+#[tauri::command]
+pub async fn get_saved_search(
+    saved_search_id: i64,
+    db: State<'_, Data>,
+) -> TAResult<SavedSearchRead> {
+    let saved_search = find_saved_search(saved_search_id, &db).await?;
+    Ok(to_saved_search_read(saved_search, &db).await)
+}
+
+#[tauri::command]
+pub async fn add_saved_search(
+    new_saved_search: NewSavedSearch,
+    db: State<'_, Data>,
+) -> TAResult<SavedSearchRead> {
+    let name = validate_name(&new_saved_search.name)?;
+    let params = validate_params(&new_saved_search.params, &db).await?;
+
+    let saved_search = sqlx::query_as!(
+        SavedSearch,
+        "INSERT INTO saved_searches (name, params) VALUES (?, ?) RETURNING *",
+        name,
+        params
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    Ok(to_saved_search_read(saved_search, &db).await)
+}
+
+#[tauri::command]
+pub async fn edit_saved_search(
+    edit_saved_search: EditSavedSearch,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let name = validate_name(&edit_saved_search.name)?;
+    let params = validate_params(&edit_saved_search.params, &db).await?;
+
+    let result = sqlx::query!(
+        "UPDATE saved_searches SET name = ?, params = ? WHERE id = ?",
+        name,
+        params,
+        edit_saved_search.id
+    )
+    .execute(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    if result.rows_affected() == 0 {
+        anyhow_tauri::bail!(not_found_message(edit_saved_search.id));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(saved_search_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    sqlx::query!(
+        "DELETE FROM saved_searches WHERE saved_searches.id = ?",
+        saved_search_id
+    )
+    .execute(&db.pool)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Run a saved search, optionally for a different page than the one it was saved with.
+#[tauri::command]
+pub async fn run_saved_search(
+    saved_search_id: i64,
+    page: Option<i64>,
+    page_size: Option<i64>,
+    db: State<'_, Data>,
+) -> TAResult<PagedData<TaskRead>> {
+    let saved_search = find_saved_search(saved_search_id, &db).await?;
+    let params = saved_search.search_params().into_ta_result()?;
+
+    // The page and page size given here are checked like any other search before it is run.
+    let params = TaskSearchParams {
+        page: page.unwrap_or(params.page).max(1),
+        page_size: page_size.unwrap_or(params.page_size),
+        ..params
+    };
+
+    search_tasks(params, &db).await
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::features::tasks::TaskSearchParams;
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    /// The search parameters serialized as JSON.
+    pub params: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearchRead {
+    pub id: i64,
+    pub name: String,
+    /// Missing when the saved parameters can no longer be read.
+    pub params: Option<TaskSearchParams>,
+    /// How many tasks currently match the search, for showing as a badge. Missing when the
+    /// search could not be run.
+    pub match_count: Option<i64>,
+    /// Why the search could not be read or run.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSavedSearch {
+    pub name: String,
+    pub params: TaskSearchParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditSavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub params: TaskSearchParams,
+}
+
+impl SavedSearch {
+    pub fn search_params(&self) -> anyhow::Result<TaskSearchParams> {
+        serde_json::from_str(&self.params).map_err(|e| {
+            anyhow::anyhow!("The saved search '{}' could not be read: {}", self.name, e)
+        })
+    }
+}
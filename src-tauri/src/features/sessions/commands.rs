@@ -0,0 +1,249 @@
+use std::thread;
+
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tauri::{async_runtime::block_on, AppHandle, Emitter, State};
+
+use crate::{
+    features::{
+        journal::JournalRecording,
+        tasks::{Status, UnixTimestamp},
+    },
+    Data,
+};
+
+use super::{
+    StaleSession, StaleSessionRead, StaleSessionResolution, HEARTBEAT_INTERVAL,
+    STALE_AFTER_SECONDS, STALE_SESSIONS_EVENT,
+};
+
+/// Mark every running session which is not stale as still running.
+///
+/// Stale sessions are left alone so that they stay stale until the user decides what to do
+/// with them.
+pub async fn record_heartbeat(pool: &SqlitePool) -> anyhow::Result<()> {
+    let now = UnixTimestamp::now();
+    let fresh_since = UnixTimestamp::from(now.as_seconds() - STALE_AFTER_SECONDS);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_work_heartbeats (history_id, beat)
+        SELECT twh.id, ?
+        FROM task_work_history twh
+        LEFT JOIN task_work_heartbeats twhb ON twhb.history_id = twh.id
+        WHERE twh.end_date IS NULL
+        AND COALESCE(twhb.beat, twh.start_date) >= ?
+        ON CONFLICT(history_id) DO UPDATE
+        SET beat = excluded.beat
+    "#,
+        now,
+        fresh_since
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM task_work_heartbeats
+        WHERE task_work_heartbeats.history_id NOT IN (
+            SELECT twh.id
+            FROM task_work_history twh
+            WHERE twh.end_date IS NULL)
+    "#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Find the running sessions which have gone without a heartbeat for too long.
+pub async fn find_stale_sessions(pool: &SqlitePool) -> anyhow::Result<Vec<StaleSession>> {
+    let fresh_since = UnixTimestamp::from(UnixTimestamp::now().as_seconds() - STALE_AFTER_SECONDS);
+
+    let sessions = sqlx::query_as!(
+        StaleSession,
+        r#"
+        SELECT twh.id AS history_id,
+        twh.task_id,
+        tasks.title AS task_title,
+        twh.start_date,
+        COALESCE(twhb.beat, twh.start_date) AS "last_heartbeat!: UnixTimestamp"
+        FROM task_work_history twh
+        INNER JOIN tasks ON tasks.id = twh.task_id
+        LEFT JOIN task_work_heartbeats twhb ON twhb.history_id = twh.id
+        WHERE twh.end_date IS NULL
+        AND tasks.deleted_at IS NULL
+        AND COALESCE(twhb.beat, twh.start_date) < ?
+        ORDER BY twh.start_date ASC
+    "#,
+        fresh_since
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Write heartbeats in the background for as long as the app runs.
+///
+/// Stale sessions are looked for before every heartbeat, which finds the sessions left running
+/// when the app was last closed as well as those running while the machine was asleep. The
+/// frontend is told whenever they change, and should also ask for them once it has loaded since
+/// the first check happens before it is listening.
+pub fn start_heartbeat(app: AppHandle, pool: SqlitePool) {
+    thread::spawn(move || {
+        let mut reported_ids: Vec<i64> = Vec::new();
+
+        loop {
+            match block_on(find_stale_sessions(&pool)) {
+                Ok(sessions) => {
+                    let ids: Vec<i64> = sessions.iter().map(|session| session.history_id).collect();
+
+                    if ids != reported_ids {
+                        if !sessions.is_empty() {
+                            println!("Found {} stale work session(s).", sessions.len());
+                        }
+
+                        let sessions: Vec<StaleSessionRead> =
+                            sessions.into_iter().map(StaleSessionRead::from).collect();
+                        if let Err(e) = app.emit(STALE_SESSIONS_EVENT, sessions) {
+                            println!("Unable to report stale work sessions: {}", e);
+                        }
+
+                        reported_ids = ids;
+                    }
+                }
+                Err(e) => println!("Unable to look for stale work sessions: {}", e),
+            }
+
+            if let Err(e) = block_on(record_heartbeat(&pool)) {
+                println!("Unable to record the work session heartbeat: {}", e);
+            }
+
+            thread::sleep(HEARTBEAT_INTERVAL);
+        }
+    });
+}
+
+/// Pause the task when it is in progress without a running session left.
+pub async fn pause_if_not_running(
+    task_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let doing = Status::Doing;
+    let paused = Status::Paused;
+
+    sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET status = ?
+        WHERE tasks.id = ?
+        AND tasks.status = ?
+        AND NOT EXISTS (
+            SELECT 1
+            FROM task_work_history twh
+            WHERE twh.task_id = tasks.id
+            AND twh.end_date IS NULL)
+    "#,
+        paused,
+        task_id,
+        doing
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+#[tauri::command]
+pub async fn get_stale_sessions(db: State<'_, Data>) -> TAResult<Vec<StaleSessionRead>> {
+    let sessions = find_stale_sessions(&db.pool).await.into_ta_result()?;
+
+    Ok(sessions.into_iter().map(StaleSessionRead::from).collect())
+}
+
+/// Decide what to do with a session which was left running while the app was not.
+#[tauri::command]
+pub async fn resolve_stale_session(
+    history_id: i64,
+    resolution: StaleSessionResolution,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let session = find_stale_sessions(&db.pool)
+        .await
+        .into_ta_result()?
+        .into_iter()
+        .find(|session| session.history_id == history_id);
+
+    let Some(session) = session else {
+        anyhow_tauri::bail!(format!(
+            "Work history with id '{}' is not a stale session.",
+            history_id
+        ));
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording = JournalRecording::start(
+            "Recover stale session",
+            vec![session.task_id],
+            &mut transaction,
+        )
+        .await?;
+
+        match resolution {
+            StaleSessionResolution::CloseAtLastHeartbeat => {
+                sqlx::query!(
+                    "UPDATE task_work_history SET end_date = ? WHERE task_work_history.id = ?",
+                    session.last_heartbeat,
+                    history_id
+                )
+                .execute(&mut *transaction)
+                .await
+                .into_ta_result()?;
+
+                pause_if_not_running(session.task_id, &mut transaction).await?;
+            }
+            StaleSessionResolution::Keep => {
+                let now = UnixTimestamp::now();
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO task_work_heartbeats (history_id, beat)
+                    VALUES (?, ?)
+                    ON CONFLICT(history_id) DO UPDATE
+                    SET beat = excluded.beat
+                "#,
+                    history_id,
+                    now
+                )
+                .execute(&mut *transaction)
+                .await
+                .into_ta_result()?;
+            }
+            StaleSessionResolution::Discard => {
+                sqlx::query!(
+                    "DELETE FROM task_work_history WHERE task_work_history.id = ?",
+                    history_id
+                )
+                .execute(&mut *transaction)
+                .await
+                .into_ta_result()?;
+
+                pause_if_not_running(session.task_id, &mut transaction).await?;
+            }
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::features::tasks::UnixTimestamp;
+
+/// How often the running sessions are marked as still running.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a running session can go without a heartbeat before it is considered stale, which
+/// happens when the app was closed or the machine was asleep.
+pub const STALE_AFTER_SECONDS: i64 = 5 * 60;
+
+/// Emitted with the stale sessions whenever a different set of them is found.
+pub const STALE_SESSIONS_EVENT: &str = "stale-sessions-detected";
+
+/// A running session which has not had a heartbeat for a while.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct StaleSession {
+    pub history_id: i64,
+    pub task_id: i64,
+    pub task_title: String,
+    pub start_date: UnixTimestamp,
+    /// The last time the session was seen running, its start when it never had a heartbeat.
+    pub last_heartbeat: UnixTimestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleSessionRead {
+    pub history_id: i64,
+    pub task_id: i64,
+    pub task_title: String,
+    pub start_date: Timestamp,
+    pub last_heartbeat: Timestamp,
+    /// How long the session has been running since its last heartbeat, in seconds.
+    pub unaccounted_duration: i64,
+}
+
+impl From<StaleSession> for StaleSessionRead {
+    fn from(value: StaleSession) -> Self {
+        Self {
+            history_id: value.history_id,
+            task_id: value.task_id,
+            task_title: value.task_title,
+            start_date: value.start_date.into(),
+            last_heartbeat: value.last_heartbeat.into(),
+            unaccounted_duration: UnixTimestamp::now() - value.last_heartbeat,
+        }
+    }
+}
+
+/// What to do with a stale session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StaleSessionResolution {
+    /// End the session at its last heartbeat and pause the task.
+    CloseAtLastHeartbeat,
+    /// Count the time since the last heartbeat and keep the session running.
+    Keep,
+    /// Delete the session and pause the task.
+    Discard,
+}
@@ -63,7 +63,10 @@ pub async fn update_user_settings(
             gradient_from = ?,
             gradient_degrees = ?,
             navbar_opened = ?,
-            default_timer = ?
+            default_timer = ?,
+            allow_concurrent_timers = ?,
+            trash_retention_days = ?,
+            idle_timeout = ?
             WHERE id = ?
         "#,
         found.page_size,
@@ -75,6 +78,9 @@ pub async fn update_user_settings(
         found.gradient_degrees,
         found.navbar_opened,
         found.default_timer,
+        found.allow_concurrent_timers,
+        found.trash_retention_days,
+        found.idle_timeout,
         found.id
     )
     .execute(&db.pool)
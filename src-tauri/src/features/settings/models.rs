@@ -14,6 +14,12 @@ pub struct UpdateUserSettings {
     pub navbar_opened: bool,
     pub notification_settings: Vec<NotificationSetting>,
     pub default_timer: i64,
+    /// Left unchanged when not provided.
+    pub allow_concurrent_timers: Option<bool>,
+    /// Left unchanged when not provided.
+    pub trash_retention_days: Option<i64>,
+    /// Left unchanged when not provided.
+    pub idle_timeout: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +35,11 @@ pub struct UserSetting {
     pub gradient_degrees: i64,
     pub navbar_opened: bool,
     pub default_timer: i64,
+    pub allow_concurrent_timers: bool,
+    /// How many days deleted items stay in the trash, 0 keeps them forever.
+    pub trash_retention_days: i64,
+    /// How many seconds without input before the running tasks are paused, 0 turns it off.
+    pub idle_timeout: i64,
 }
 
 impl UserSetting {
@@ -44,6 +55,17 @@ impl UserSetting {
             gradient_degrees: update.gradient_degrees,
             navbar_opened: update.navbar_opened,
             default_timer: update.default_timer,
+            allow_concurrent_timers: update
+                .allow_concurrent_timers
+                .unwrap_or(self.allow_concurrent_timers),
+            trash_retention_days: update
+                .trash_retention_days
+                .map(|days| days.max(0))
+                .unwrap_or(self.trash_retention_days),
+            idle_timeout: update
+                .idle_timeout
+                .map(|seconds| seconds.max(0))
+                .unwrap_or(self.idle_timeout),
         }
     }
 }
@@ -62,6 +84,9 @@ impl From<UserSetting> for UserSettingRead {
             notification_settings: Vec::new(),
             navbar_opened: value.navbar_opened,
             default_timer: value.default_timer,
+            allow_concurrent_timers: value.allow_concurrent_timers,
+            trash_retention_days: value.trash_retention_days,
+            idle_timeout: value.idle_timeout,
         }
     }
 }
@@ -79,6 +104,9 @@ impl From<UserSettingRead> for UserSetting {
             gradient_degrees: value.gradient_degrees,
             navbar_opened: value.navbar_opened,
             default_timer: value.default_timer,
+            allow_concurrent_timers: value.allow_concurrent_timers,
+            trash_retention_days: value.trash_retention_days,
+            idle_timeout: value.idle_timeout,
         }
     }
 }
@@ -97,6 +125,11 @@ pub struct UserSettingRead {
     pub navbar_opened: bool,
     pub notification_settings: Vec<NotificationSetting>,
     pub default_timer: i64,
+    pub allow_concurrent_timers: bool,
+    /// How many days deleted items stay in the trash, 0 keeps them forever.
+    pub trash_retention_days: i64,
+    /// How many seconds without input before the running tasks are paused, 0 turns it off.
+    pub idle_timeout: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
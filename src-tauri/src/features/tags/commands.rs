@@ -1,24 +1,28 @@
 use anyhow_tauri::{IntoTAResult, TAResult};
-use sqlx::QueryBuilder;
+use sqlx::{QueryBuilder, Sqlite, Transaction};
 use tauri::State;
 
 use crate::{
-    features::{tags::Tag, tasks::TaskTag},
-    query_utils::add_in_expression,
-    Data, PagedData, SortDirection,
+    features::{
+        activity::{record_task_event, TaskEventDetails},
+        journal::JournalRecording,
+        tags::Tag,
+        tasks::{OptionalUnixTimestamp, TaskTag, UnixTimestamp},
+    },
+    Data, PagedData, Query, SortDirection,
 };
 
-use super::TagSearchParams;
+use super::{TagSearchParams, TAG_FILTER_FIELDS};
 
 fn generate_search_query<'a>(
     mut builder: QueryBuilder<'a, sqlx::Sqlite>,
     params: &'a TagSearchParams,
 ) -> QueryBuilder<'a, sqlx::Sqlite> {
-    builder.push("SELECT * FROM tags");
+    builder.push("SELECT * FROM tags WHERE tags.deleted_at IS NULL");
 
     if let Some(query) = &params.query_string {
         builder
-            .push(" WHERE tags.value LIKE ")
+            .push(" AND tags.value LIKE ")
             .push_bind(format!("'%{}%'", query));
     }
 
@@ -74,6 +78,45 @@ pub async fn get_tags(params: TagSearchParams, db: State<'_, Data>) -> TAResult<
     ))
 }
 
+/// Search tags with a generic query of nested `and`/`or` groups over `TAG_FILTER_FIELDS`.
+#[tauri::command]
+pub async fn search_tags_advanced(query: Query, db: State<'_, Data>) -> TAResult<PagedData<Tag>> {
+    let mut count_builder =
+        QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM tags WHERE tags.deleted_at IS NULL AND ");
+    query
+        .push_conditions(&mut count_builder, TAG_FILTER_FIELDS)
+        .into_ta_result()?;
+
+    let count = count_builder
+        .build_query_scalar::<i64>()
+        .fetch_one(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    let mut tag_query =
+        QueryBuilder::<Sqlite>::new("SELECT * FROM tags WHERE tags.deleted_at IS NULL AND ");
+    query
+        .push_conditions(&mut tag_query, TAG_FILTER_FIELDS)
+        .into_ta_result()?;
+    query
+        .push_ordering(&mut tag_query, TAG_FILTER_FIELDS)
+        .into_ta_result()?;
+    query.push_paging(&mut tag_query).into_ta_result()?;
+
+    let all_tags = tag_query
+        .build_query_as::<Tag>()
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    Ok(PagedData::<Tag>::new(
+        query.page as i64,
+        query.page_size as i64,
+        count,
+        all_tags,
+    ))
+}
+
 #[tauri::command]
 pub async fn add_new_tag(new_tag: String, db: State<'_, Data>) -> TAResult<Tag> {
     // If a tag already exists, no need to add it again.
@@ -83,6 +126,7 @@ pub async fn add_new_tag(new_tag: String, db: State<'_, Data>) -> TAResult<Tag>
             SELECT *
             FROM tags
             WHERE tags.value = ?
+            ORDER BY tags.deleted_at IS NOT NULL ASC
             LIMIT 1
         "#,
         new_tag
@@ -92,7 +136,21 @@ pub async fn add_new_tag(new_tag: String, db: State<'_, Data>) -> TAResult<Tag>
     .into_ta_result()?;
 
     match maybe_tag {
-        Some(_) => anyhow_tauri::bail!("Tag already exists."),
+        Some(tag) if tag.deleted_at == OptionalUnixTimestamp::none() => {
+            anyhow_tauri::bail!("Tag already exists.")
+        }
+        // Adding a tag which is in the trash brings it back rather than creating a duplicate.
+        Some(tag) => {
+            sqlx::query!("UPDATE tags SET deleted_at = NULL WHERE id = ?", tag.id)
+                .execute(&db.pool)
+                .await
+                .into_ta_result()?;
+
+            Ok(Tag {
+                deleted_at: OptionalUnixTimestamp::none(),
+                ..tag
+            })
+        }
         None => {
             let result = sqlx::query!("INSERT INTO tags (value) VALUES (?)", new_tag)
                 .execute(&db.pool)
@@ -116,6 +174,7 @@ pub async fn get_all_tags(db: State<'_, Data>) -> TAResult<Vec<Tag>> {
         r#"
         SELECT *
         FROM tags
+        WHERE tags.deleted_at IS NULL
         ORDER BY tags.value ASC
     "#
     )
@@ -124,39 +183,117 @@ pub async fn get_all_tags(db: State<'_, Data>) -> TAResult<Vec<Tag>> {
     .into_ta_result()
 }
 
+/// Add an event to the activity of every task with the tag, as changing the tag changes what
+/// those tasks show.
+pub async fn record_tagged_task_events(
+    tag_id: i64,
+    event: &TaskEventDetails,
+    source: &str,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let task_ids = sqlx::query_scalar!(
+        "SELECT task_tags.task_id FROM task_tags WHERE task_tags.tag_id = ?",
+        tag_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let created = UnixTimestamp::now();
+    for task_id in task_ids {
+        record_task_event(task_id, event, source, created, &mut **transaction).await?;
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn edit_tag(tag: Tag, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let old_value = sqlx::query_scalar!("SELECT tags.value FROM tags WHERE tags.id = ?", tag.id)
+        .fetch_optional(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
     sqlx::query!("UPDATE tags SET value = ? WHERE id = ?", tag.value, tag.id)
-        .execute(&db.pool)
+        .execute(&mut *transaction)
         .await
-        .map(|_| ())
-        .into_ta_result()
+        .into_ta_result()?;
+
+    if let Some(old_value) = old_value.filter(|old_value| *old_value != tag.value) {
+        let event = TaskEventDetails::TagRenamed {
+            tag_id: tag.id,
+            old_value,
+            new_value: tag.value,
+        };
+        record_tagged_task_events(tag.id, &event, "Rename tag", &mut transaction).await?;
+    }
+
+    transaction.commit().await.into_ta_result()
 }
 
+/// Move a tag to the trash, noting it on the activity of the tasks which carry it.
+async fn trash_tag(
+    tag_id: i64,
+    deleted_at: UnixTimestamp,
+    source: &str,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let value = sqlx::query_scalar!(
+        "SELECT tags.value FROM tags WHERE tags.id = ? AND tags.deleted_at IS NULL",
+        tag_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let Some(value) = value else {
+        return Ok(());
+    };
+
+    sqlx::query!(
+        "UPDATE tags SET deleted_at = ? WHERE tags.id = ?",
+        deleted_at,
+        tag_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let event = TaskEventDetails::TagRemoved {
+        tag_id,
+        tag: Some(value),
+    };
+    record_tagged_task_events(tag_id, &event, source, transaction).await
+}
+
+/// Move a tag to the trash, the tasks keep their link to it so that restoring it re-tags them.
 #[tauri::command]
 pub async fn delete_tag(tag_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    sqlx::query!("DELETE FROM tags WHERE tags.id = ?", tag_id)
-        .execute(&db.pool)
-        .await
-        .map(|_| ())
-        .into_ta_result()
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    trash_tag(tag_id, UnixTimestamp::now(), "Delete tag", &mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
+/// Move the tags to the trash.
 #[tauri::command]
 pub async fn delete_many_tags(tag_ids: Vec<i64>, db: State<'_, Data>) -> TAResult<()> {
-    let mut builder = QueryBuilder::new("DELETE FROM tags WHERE tags.id");
-    add_in_expression(&mut builder, &tag_ids);
+    let deleted_at = UnixTimestamp::now();
 
-    builder
-        .build()
-        .execute(&db.pool)
-        .await
-        .map(|_| ())
-        .into_ta_result()
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    for tag_id in tag_ids {
+        trash_tag(tag_id, deleted_at, "Delete tags", &mut transaction).await?;
+    }
+
+    transaction.commit().await.into_ta_result()
 }
 
 #[tauri::command]
 pub async fn remove_tag_from_task(task_id: i64, tag_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Remove tag", vec![task_id], &mut transaction).await?;
+
     sqlx::query!(
         r#"
             DELETE FROM task_tags
@@ -166,10 +303,12 @@ pub async fn remove_tag_from_task(task_id: i64, tag_id: i64, db: State<'_, Data>
         task_id,
         tag_id
     )
-    .execute(&db.pool)
+    .execute(&mut *transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
 #[tauri::command]
@@ -188,16 +327,22 @@ pub async fn add_tag_to_task(tag_id: i64, task_id: i64, db: State<'_, Data>) ->
     .await
     .into_ta_result()?;
 
-    match maybe_exists {
-        Some(_) => Ok(()),
-        None => sqlx::query!(
-            "INSERT INTO task_tags (task_id, tag_id) VALUES (?, ?)",
-            task_id,
-            tag_id
-        )
-        .execute(&db.pool)
-        .await
-        .map(|_| ())
-        .into_ta_result(),
+    if maybe_exists.is_some() {
+        return Ok(());
     }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Add tag", vec![task_id], &mut transaction).await?;
+
+    sqlx::query!(
+        "INSERT INTO task_tags (task_id, tag_id) VALUES (?, ?)",
+        task_id,
+        tag_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
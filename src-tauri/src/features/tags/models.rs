@@ -1,12 +1,17 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
-use crate::Ordering;
+use crate::{
+    features::tasks::OptionalUnixTimestamp, FieldKind, FilterableField, Ordering,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, FromRow)]
 pub struct Tag {
     pub id: i64,
     pub value: String,
+    /// When the tag was moved to the trash, never sent to or accepted from the frontend.
+    #[serde(skip)]
+    pub deleted_at: OptionalUnixTimestamp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,3 +22,9 @@ pub struct TagSearchParams {
     pub query_string: Option<String>,
     pub ordering: Ordering,
 }
+
+/// The tag fields which advanced searches may filter and sort on.
+pub const TAG_FILTER_FIELDS: &[FilterableField] = &[
+    FilterableField::new("id", "tags.id", FieldKind::Integer),
+    FilterableField::new("value", "tags.value", FieldKind::Text),
+];
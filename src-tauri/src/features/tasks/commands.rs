@@ -1,33 +1,63 @@
 use super::models::Task;
 use anyhow_tauri::{IntoTAResult, TAResult};
-use sqlx::{QueryBuilder, Sqlite, Transaction};
+use jiff::{tz::TimeZone, Timestamp, Zoned};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, Transaction};
+use std::collections::HashMap;
 use tauri::State;
 
 use crate::{
-    features::tags::Tag, option_utils::has_contents, query_utils::add_in_expression, Data,
-    FilterOption, PagedData, SortDirection,
+    escape_like,
+    features::{
+        activity::{record_task_event, TaskEventDetails},
+        custom_fields::{
+            custom_field_order, set_task_custom_field_values, CustomFieldCondition,
+            CustomFieldFilter, TaskCustomFieldRead, TaskCustomFieldRow,
+        },
+        journal::JournalRecording,
+        projects::find_project,
+        tags::Tag,
+    },
+    option_utils::has_contents,
+    query_utils::{add_in_expression, to_fts_match_expression},
+    Data, FilterOption, PagedData, Query, SortDirection, MAX_PAGE_SIZE,
 };
 
 use super::*;
 
 #[tauri::command]
 pub async fn create_task(new_task: CreateTask, db: State<'_, Data>) -> TAResult<()> {
+    if let Some(parent_task_id) = new_task.parent_task_id {
+        if find_task(parent_task_id, &db).await?.is_none() {
+            anyhow_tauri::bail!(not_found_message(parent_task_id));
+        }
+    }
+
+    if let Some(project_id) = new_task.project_id {
+        find_project(project_id, &db).await?;
+    }
+
     let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let mut recording = JournalRecording::start("Create task", vec![], &mut transaction).await?;
     let tags = new_task.tags.clone();
+    let custom_fields = new_task.custom_fields.clone();
     let new_task = NewTask::from(new_task);
 
     let result = sqlx::query!(r#"
-        INSERT INTO tasks (title, description, status, scheduled_start_date, scheduled_complete_date, estimated_duration) 
-        VALUES (?, ?, ?, ?, ?, ?)"#,
+        INSERT INTO tasks (title, description, status, scheduled_start_date, scheduled_complete_date, estimated_duration, parent_task_id, priority, project_id) 
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
         new_task.title,
         new_task.description,
         new_task.status,
         new_task.scheduled_start_date,
         new_task.scheduled_complete_date,
         new_task.estimated_duration,
+        new_task.parent_task_id,
+        new_task.priority,
+        new_task.project_id,
     ).execute(&mut *transaction)
     .await
     .into_ta_result()?;
+    recording.created(result.last_insert_rowid());
 
     if has_contents(tags.as_ref()) {
         let tags = tags.unwrap();
@@ -44,6 +74,10 @@ pub async fn create_task(new_task: CreateTask, db: State<'_, Data>) -> TAResult<
             .into_ta_result()?;
     }
 
+    set_task_custom_field_values(result.last_insert_rowid(), &custom_fields, &mut transaction)
+        .await?;
+
+    recording.finish(&mut transaction).await?;
     transaction.commit().await.into_ta_result()
 }
 
@@ -56,52 +90,106 @@ fn generate_search_query<'a>(
         SELECT DISTINCT tasks.* 
         FROM tasks
         LEFT JOIN task_tags on tasks.id = task_tags.task_id
-        LEFT JOIN tags on tags.id = task_tags.tag_id
+        LEFT JOIN tags on tags.id = task_tags.tag_id AND tags.deleted_at IS NULL
     "#,
     );
 
-    builder.push(" WHERE 1=1 ");
+    let match_expression = params
+        .query_string
+        .as_deref()
+        .and_then(to_fts_match_expression);
+
+    if let Some(match_expression) = &match_expression {
+        builder
+            .push(
+                r#"
+                INNER JOIN (
+                    SELECT task_search.rowid, task_search.rank
+                    FROM task_search
+                    WHERE task_search MATCH "#,
+            )
+            .push_bind(match_expression.clone())
+            .push(") search ON search.rowid = tasks.id ");
+    }
+
+    builder.push(" WHERE tasks.deleted_at IS NULL ");
 
     builder.push(" AND tasks.status ");
     add_in_expression(&mut builder, &params.statuses);
 
-    if let Some(query) = &params.query_string {
-        builder
-            .push(" AND (tasks.title LIKE ")
-            .push(format!("'%{}%'", query))
-            .push(" OR tasks.description LIKE ")
-            .push(format!("'%{}%'", query))
-            .push(") ");
+    if params.as_tree {
+        builder.push(" AND tasks.parent_task_id IS NULL ");
     }
 
-    if let Some(DateFilter {
-        start: Some(start),
-        end: Some(end),
-    }) = &params.start_by_filter
-    {
-        builder
-            .push(" AND tasks.scheduled_start_date BETWEEN ")
-            .push_bind(UnixTimestamp::from(start))
-            .push(" AND ")
-            .push_bind(UnixTimestamp::from(end));
+    if let Some(filter) = &params.start_by_filter {
+        add_date_filter(&mut builder, "tasks.scheduled_start_date", filter);
     }
 
-    if let Some(DateFilter {
-        start: Some(start),
-        end: Some(end),
-    }) = &params.due_by_filter
-    {
-        builder
-            .push(" AND tasks.scheduled_complete_date BETWEEN ")
-            .push_bind(UnixTimestamp::from(start))
-            .push(" AND ")
-            .push_bind(UnixTimestamp::from(end));
+    if let Some(filter) = &params.due_by_filter {
+        add_date_filter(&mut builder, "tasks.scheduled_complete_date", filter);
+    }
+
+    if let Some(DurationFilter { min, max }) = &params.estimate_filter {
+        if let Some(min) = min {
+            builder.push(" AND tasks.estimated_duration >= ").push_bind(*min);
+        }
+
+        if let Some(max) = max {
+            builder.push(" AND tasks.estimated_duration <= ").push_bind(*max);
+        }
+    }
+
+    if !params.project_ids.is_empty() {
+        builder.push(" AND tasks.project_id");
+        add_in_expression(&mut builder, &params.project_ids);
+    }
+
+    for tag in params.required_tags.iter() {
+        builder.push(
+            r#"
+            AND tasks.id IN (
+                SELECT required.task_id
+                FROM task_tags required
+                INNER JOIN tags required_tags ON required_tags.id = required.tag_id
+                WHERE required_tags.deleted_at IS NULL
+                AND required_tags.value = "#,
+        );
+        builder.push_bind(tag.clone());
+        builder.push(") ");
+    }
+
+    if !params.excluded_tags.is_empty() {
+        builder.push(
+            r#"
+            AND tasks.id NOT IN (
+                SELECT excluded.task_id
+                FROM task_tags excluded
+                INNER JOIN tags excluded_tags ON excluded_tags.id = excluded.tag_id
+                WHERE excluded_tags.deleted_at IS NULL
+                AND excluded_tags.value "#,
+        );
+        add_in_expression(&mut builder, &params.excluded_tags);
+        builder.push(") ");
+    }
+
+    for filter in params.custom_field_filters.iter() {
+        add_custom_field_filter(&mut builder, filter);
     }
 
     if let Some(ref quick_filter) = &params.quick_filter {
         match quick_filter {
             QuickFilter::Untagged => {
-                builder.push(" AND task_tags.task_id IS NULL ");
+                // Tags in the trash are not shown, so a task with only those counts as untagged.
+                builder.push(
+                    r#"
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM task_tags tt
+                        INNER JOIN tags t ON t.id = tt.tag_id
+                        WHERE tt.task_id = tasks.id
+                        AND t.deleted_at IS NULL)
+                "#,
+                );
             }
             QuickFilter::Tagged(tag_filter) => {
                 builder.push(" AND tags.value ");
@@ -165,24 +253,79 @@ fn generate_search_query<'a>(
                 builder.push_bind(UnixTimestamp::now());
                 builder.push(" AND tasks.status <> 'Done') ");
             }
+            QuickFilter::OverdueChecklist => {
+                builder.push(
+                    r#"
+                    AND tasks.scheduled_complete_date IS NOT NULL
+                    AND tasks.scheduled_complete_date < "#,
+                );
+                builder.push_bind(UnixTimestamp::now());
+                builder.push(
+                    r#"
+                    AND tasks.status NOT IN ('Done', 'Cancelled')
+                    AND EXISTS (
+                        SELECT 1
+                        FROM task_checklist_items tci
+                        WHERE tci.task_id = tasks.id
+                        AND tci.checked = 0)
+                "#,
+                );
+            }
+            QuickFilter::Blocked => {
+                builder.push(
+                    r#"
+                    AND EXISTS (
+                        SELECT 1
+                        FROM task_dependencies td
+                        INNER JOIN tasks dependency ON dependency.id = td.depends_on_task_id
+                        WHERE td.task_id = tasks.id
+                        AND dependency.status NOT IN ('Done', 'Cancelled')
+                        AND dependency.deleted_at IS NULL)
+                "#,
+                );
+            }
+            QuickFilter::Ready => {
+                builder.push(
+                    r#"
+                    AND tasks.status NOT IN ('Done', 'Cancelled')
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM task_dependencies td
+                        INNER JOIN tasks dependency ON dependency.id = td.depends_on_task_id
+                        WHERE td.task_id = tasks.id
+                        AND dependency.status NOT IN ('Done', 'Cancelled')
+                        AND dependency.deleted_at IS NULL)
+                "#,
+                );
+            }
         }
     }
 
+    if let Some(field_id) = custom_field_order(&params.ordering.order_by) {
+        add_custom_field_order(&mut builder, field_id, &params.ordering.sort_direction);
+        return builder;
+    }
+
     match params.ordering.sort_direction {
         SortDirection::Ascending => match params.ordering.order_by.as_str() {
             "title" => builder.push(" ORDER BY LOWER(tasks.title) ASC"),
             "status" => builder.push(" ORDER BY LOWER(tasks.status) ASC"),
             "description" => builder.push(" ORDER BY LOWER(tasks.description) ASC"),
+            "priority" => builder.push(" ORDER BY tasks.priority ASC"),
             "scheduled_start_date" => builder.push(" ORDER BY tasks.scheduled_start_date IS NULL ASC, tasks.scheduled_start_date ASC"),
             "scheduled_complete_date" => builder.push(" ORDER BY tasks.scheduled_complete_date IS NULL ASC, tasks.scheduled_complete_date ASC"),
+            _ if match_expression.is_some() => builder.push(" ORDER BY search.rank ASC"),
             _ => &mut builder
         },
         SortDirection::Descending => match params.ordering.order_by.as_str() {
             "title" => builder.push(" ORDER BY LOWER(tasks.title) DESC"),
             "status" => builder.push(" ORDER BY LOWER(tasks.status) DESC"),
             "description" => builder.push(" ORDER BY LOWER(tasks.description) DESC"),
+            "priority" => builder.push(" ORDER BY tasks.priority DESC"),
             "scheduled_start_date" => builder.push(" ORDER BY tasks.scheduled_start_date IS NULL DESC, tasks.scheduled_start_date DESC"),
             "scheduled_complete_date" => builder.push(" ORDER BY tasks.scheduled_complete_date IS NULL DESC, tasks.scheduled_complete_date DESC"),
+            // Relevance is always best match first, FTS5 ranks better matches lower.
+            _ if match_expression.is_some() => builder.push(" ORDER BY search.rank ASC"),
             _ => &mut builder
         },
     };
@@ -190,6 +333,105 @@ fn generate_search_query<'a>(
     builder
 }
 
+/// Restrict the tasks to those whose value for a custom field matches the condition.
+fn add_custom_field_filter<'a>(
+    builder: &mut QueryBuilder<'a, Sqlite>,
+    filter: &'a CustomFieldFilter,
+) {
+    let exists = match filter.condition {
+        CustomFieldCondition::IsNotSet => " AND NOT EXISTS (",
+        _ => " AND EXISTS (",
+    };
+
+    builder
+        .push(exists)
+        .push(
+            r#"
+            SELECT 1
+            FROM task_custom_field_values tcfv
+            WHERE tcfv.task_id = tasks.id
+            AND tcfv.field_id = "#,
+        )
+        .push_bind(filter.field_id);
+
+    match &filter.condition {
+        CustomFieldCondition::IsSet | CustomFieldCondition::IsNotSet => {}
+        CustomFieldCondition::OneOf { values } => {
+            // An empty list matches nothing rather than leaving the condition incomplete.
+            match values.is_empty() {
+                true => builder.push(" AND 0"),
+                false => builder.push(" AND tcfv.text_value COLLATE NOCASE"),
+            };
+            add_in_expression(builder, values);
+        }
+        CustomFieldCondition::Contains { text } => {
+            builder
+                .push(" AND tcfv.text_value LIKE ")
+                .push_bind(format!("%{}%", escape_like(text)))
+                .push(" ESCAPE '\\'");
+        }
+        CustomFieldCondition::NumberRange { min, max } => {
+            if let Some(min) = min {
+                builder.push(" AND tcfv.number_value >= ").push_bind(*min);
+            }
+
+            if let Some(max) = max {
+                builder.push(" AND tcfv.number_value <= ").push_bind(*max);
+            }
+        }
+        CustomFieldCondition::DateRange { start, end } => {
+            add_date_filter(
+                builder,
+                "tcfv.date_value",
+                &DateFilter {
+                    start: *start,
+                    end: *end,
+                },
+            );
+        }
+    }
+
+    builder.push(") ");
+}
+
+/// Sort the tasks by their value for a custom field, with the tasks without one placed like
+/// those without a scheduled date.
+fn add_custom_field_order(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    field_id: i64,
+    direction: &SortDirection,
+) {
+    let direction = match direction {
+        SortDirection::Ascending => "ASC",
+        SortDirection::Descending => "DESC",
+    };
+    // Only the column for the type of the field is ever set.
+    let value = format!(
+        "(SELECT COALESCE(tcfv.number_value, tcfv.date_value, LOWER(tcfv.text_value)) FROM task_custom_field_values tcfv WHERE tcfv.task_id = tasks.id AND tcfv.field_id = {})",
+        field_id
+    );
+
+    builder.push(format!(
+        " ORDER BY {} IS NULL {}, {} {}",
+        value, direction, value, direction
+    ));
+}
+
+/// Restrict a date column to the filter, leaving out whichever ends of the range are open.
+fn add_date_filter(builder: &mut QueryBuilder<'_, Sqlite>, column: &str, filter: &DateFilter) {
+    if let Some(start) = &filter.start {
+        builder
+            .push(format!(" AND {} >= ", column))
+            .push_bind(UnixTimestamp::from(start));
+    }
+
+    if let Some(end) = &filter.end {
+        builder
+            .push(format!(" AND {} <= ", column))
+            .push_bind(UnixTimestamp::from(end));
+    }
+}
+
 fn elapsed_duration(history: &Vec<TaskWorkHistory>) -> i64 {
     history.iter().fold(0, |acc, el| {
         acc + (el.end_date - el.start_date)
@@ -208,456 +450,2518 @@ fn get_actual_complete(status: &Status, history: &Vec<TaskWorkHistory>) -> Optio
     history.iter().map(|hist| hist.end_date).max()
 }
 
-/// Search for all tasks which match the search parameters.
-///
-/// ### Args
-/// * state - The database state used to query a connection.
-/// * params - The search parameters used to filter/sort the results.
-#[tauri::command]
-pub async fn get_tasks(
-    params: TaskSearchParams,
-    db: State<'_, Data>,
-) -> TAResult<PagedData<TaskRead>> {
-    let mut count_builder = QueryBuilder::<sqlx::Sqlite>::new("SELECT COUNT(DISTINCT id) FROM (");
-
-    count_builder = generate_search_query(count_builder, &params);
-    count_builder.push(")");
+/// Group rows by the task they belong to.
+fn group_by_task<T>(rows: Vec<T>, task_id: impl Fn(&T) -> i64) -> HashMap<i64, Vec<T>> {
+    let mut grouped: HashMap<i64, Vec<T>> = HashMap::new();
+    for row in rows {
+        grouped.entry(task_id(&row)).or_default().push(row);
+    }
+    grouped
+}
 
-    let count_query = count_builder.build_query_scalar::<i64>();
-    let count = count_query.fetch_one(&db.pool).await.into_ta_result()?;
+/// Load the comments, tags, work history, recurrence, checklist, links and custom fields of the
+/// tasks with one query each.
+async fn load_task_reads(tasks: Vec<Task>, db: &Data) -> TAResult<Vec<TaskRead>> {
+    if tasks.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    let mut task_query = generate_search_query(QueryBuilder::new(""), &params);
+    let task_ids: Vec<i64> = tasks.iter().map(|task| task.id).collect();
 
-    task_query.push(format!(
-        " LIMIT {} OFFSET {}",
-        params.page_size,
-        (params.page - 1) * params.page_size
-    ));
+    let mut comment_query = QueryBuilder::<Sqlite>::new(
+        "SELECT * FROM comments WHERE comments.deleted_at IS NULL AND comments.task_id",
+    );
+    add_in_expression(&mut comment_query, &task_ids);
+    let mut comments_by_task = group_by_task(
+        comment_query
+            .build_query_as::<Comment>()
+            .fetch_all(&db.pool)
+            .await
+            .into_ta_result()?,
+        |comment| comment.task_id,
+    );
 
-    let all_tasks: Vec<Task> = task_query
-        .build_query_as::<Task>()
-        .fetch_all(&db.pool)
-        .await
-        .into_ta_result()?;
+    let mut tag_query = QueryBuilder::<Sqlite>::new(
+        "SELECT task_tags.task_id, tags.id, tags.value FROM tags INNER JOIN task_tags on task_tags.tag_id = tags.id WHERE tags.deleted_at IS NULL AND task_tags.task_id",
+    );
+    add_in_expression(&mut tag_query, &task_ids);
+    let mut tags_by_task = group_by_task(
+        tag_query
+            .build_query_as::<(i64, i64, String)>()
+            .fetch_all(&db.pool)
+            .await
+            .into_ta_result()?,
+        |(task_id, _, _)| *task_id,
+    );
 
-    let mut comments: Vec<Vec<Comment>> = Vec::new();
-    let mut tags: Vec<Vec<Tag>> = Vec::new();
-    let mut work_history: Vec<Vec<TaskWorkHistory>> = Vec::new();
+    let mut work_history_query = QueryBuilder::<Sqlite>::new(
+        "SELECT twh.id, twh.task_id, twh.start_date, COALESCE(twh.end_date, CAST(strftime('%s', 'now') as INTEGER)) AS end_date FROM task_work_history twh WHERE twh.task_id",
+    );
+    add_in_expression(&mut work_history_query, &task_ids);
+    work_history_query.push(" ORDER BY twh.start_date DESC");
+    let mut work_history_by_task = group_by_task(
+        work_history_query
+            .build_query_as::<TaskWorkHistory>()
+            .fetch_all(&db.pool)
+            .await
+            .into_ta_result()?,
+        |history| history.task_id,
+    );
 
-    for task in all_tasks.iter() {
-        let task_comments = sqlx::query_as!(
-            Comment,
-            r#"
-            SELECT *
-            FROM comments
-            WHERE comments.task_id = ?
-        "#,
-            task.id
-        )
+    let mut recurrence_query = QueryBuilder::<Sqlite>::new(
+        "SELECT * FROM task_recurrences WHERE task_recurrences.task_id",
+    );
+    add_in_expression(&mut recurrence_query, &task_ids);
+    let mut recurrence_by_task: HashMap<i64, TaskRecurrence> = recurrence_query
+        .build_query_as::<TaskRecurrence>()
         .fetch_all(&db.pool)
         .await
-        .into_ta_result()?;
+        .into_ta_result()?
+        .into_iter()
+        .map(|recurrence| (recurrence.task_id, recurrence))
+        .collect();
 
-        comments.push(task_comments);
+    let mut checklist_query =
+        QueryBuilder::<Sqlite>::new("SELECT * FROM task_checklist_items tci WHERE tci.task_id");
+    add_in_expression(&mut checklist_query, &task_ids);
+    checklist_query.push(" ORDER BY tci.position ASC, tci.id ASC");
+    let mut checklist_by_task = group_by_task(
+        checklist_query
+            .build_query_as::<ChecklistItem>()
+            .fetch_all(&db.pool)
+            .await
+            .into_ta_result()?,
+        |item| item.task_id,
+    );
 
-        let task_tags = sqlx::query_as!(
-            Tag,
-            r#"
-            SELECT tags.*
-            FROM tags
-            INNER JOIN task_tags on task_tags.tag_id = tags.id
-            WHERE task_tags.task_id = ?
-        "#,
-            task.id
-        )
+    // A link shows up on both of its tasks, so load those made on either side and leave out the
+    // ones to a task in the trash.
+    let mut link_query = QueryBuilder::<Sqlite>::new(
+        "SELECT tl.id, tl.task_id, tl.linked_task_id, tl.link_type, t.title AS task_title, t.status AS task_status, lt.title AS linked_task_title, lt.status AS linked_task_status FROM task_links tl INNER JOIN tasks t ON t.id = tl.task_id INNER JOIN tasks lt ON lt.id = tl.linked_task_id WHERE t.deleted_at IS NULL AND lt.deleted_at IS NULL AND (tl.task_id",
+    );
+    add_in_expression(&mut link_query, &task_ids);
+    link_query.push(" OR tl.linked_task_id");
+    add_in_expression(&mut link_query, &task_ids);
+    link_query.push(") ORDER BY tl.created ASC, tl.id ASC");
+    let mut links_by_task: HashMap<i64, Vec<TaskLinkRead>> = HashMap::new();
+    for link in link_query
+        .build_query_as::<TaskLinkRow>()
         .fetch_all(&db.pool)
         .await
-        .into_ta_result()?;
-
-        tags.push(task_tags);
+        .into_ta_result()?
+    {
+        for task_id in [link.task_id, link.linked_task_id] {
+            links_by_task
+                .entry(task_id)
+                .or_default()
+                .push(link.seen_from(task_id));
+        }
+    }
 
-        let task_work_history = sqlx::query_as!(
-            TaskWorkHistory,
-            r#"
-            SELECT twh.id, twh.task_id, twh.start_date, COALESCE(twh.end_date, CAST(strftime('%s', 'now') as INTEGER)) AS end_date
-            FROM task_work_history twh
-            WHERE twh.task_id = ?
-            ORDER BY twh.start_date DESC
-        "#,
-            task.id
-        )
-        .fetch_all(&db.pool)
-        .await
-        .into_ta_result()?;
+    let mut custom_field_query = QueryBuilder::<Sqlite>::new(
+        "SELECT tcfv.task_id, tcfv.field_id, cf.name, cf.field_type, tcfv.text_value, tcfv.number_value, tcfv.date_value FROM task_custom_field_values tcfv INNER JOIN custom_fields cf ON cf.id = tcfv.field_id WHERE tcfv.task_id",
+    );
+    add_in_expression(&mut custom_field_query, &task_ids);
+    custom_field_query.push(" ORDER BY LOWER(cf.name) ASC");
+    let mut custom_fields_by_task = group_by_task(
+        custom_field_query
+            .build_query_as::<TaskCustomFieldRow>()
+            .fetch_all(&db.pool)
+            .await
+            .into_ta_result()?,
+        |row| row.task_id,
+    );
 
-        work_history.push(task_work_history);
-    }
+    let comments: Vec<Vec<Comment>> = task_ids
+        .iter()
+        .map(|id| comments_by_task.remove(id).unwrap_or_default())
+        .collect();
+    let tags: Vec<Vec<Tag>> = task_ids
+        .iter()
+        .map(|id| {
+            tags_by_task
+                .remove(id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(_, id, value)| Tag {
+                    id,
+                    value,
+                    deleted_at: OptionalUnixTimestamp::none(),
+                })
+                .collect()
+        })
+        .collect();
+    let work_history: Vec<Vec<TaskWorkHistory>> = task_ids
+        .iter()
+        .map(|id| work_history_by_task.remove(id).unwrap_or_default())
+        .collect();
+    let recurrences: Vec<Option<TaskRecurrence>> = task_ids
+        .iter()
+        .map(|id| recurrence_by_task.remove(id))
+        .collect();
+    let checklists: Vec<Vec<ChecklistItem>> = task_ids
+        .iter()
+        .map(|id| checklist_by_task.remove(id).unwrap_or_default())
+        .collect();
+    let links: Vec<Vec<TaskLinkRead>> = task_ids
+        .iter()
+        .map(|id| links_by_task.remove(id).unwrap_or_default())
+        .collect();
+    let custom_fields: Vec<Vec<TaskCustomFieldRead>> = task_ids
+        .iter()
+        .map(|id| {
+            custom_fields_by_task
+                .remove(id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(TaskCustomFieldRow::into_read)
+                .collect()
+        })
+        .collect();
 
-    let task_read = all_tasks
+    let task_read = tasks
         .into_iter()
         .zip(comments)
         .zip(tags)
         .zip(work_history)
-        .map(|(((task, comments), tags), history)| {
-            let elapsed_duration: i64 = elapsed_duration(&history);
-            let actual_start = get_actual_start(&history);
-            let actual_complete = get_actual_complete(&task.status, &history);
-
-            TaskRead {
-                id: task.id,
-                title: task.title,
-                description: task.description,
-                status: task.status,
-                scheduled_start_date: task.scheduled_start_date.into(),
-                scheduled_complete_date: task.scheduled_complete_date.into(),
-                actual_start_date: actual_start.into(),
-                actual_complete_date: actual_complete.map(|value| value.into()),
-                estimated_duration: task.estimated_duration.into(),
-                elapsed_duration,
-                comments: comments
-                    .into_iter()
-                    .map(|c| c.into())
-                    .collect::<Vec<CommentRead>>(),
-                tags,
-                work_history: history
-                    .into_iter()
-                    .map(|hist| hist.into())
-                    .collect::<Vec<_>>(),
-            }
-        })
+        .zip(recurrences)
+        .zip(checklists)
+        .zip(links)
+        .zip(custom_fields)
+        .map(
+            |(
+                ((((((task, comments), tags), history), recurrence), checklist), links),
+                custom_fields,
+            )| {
+                let elapsed_duration: i64 = elapsed_duration(&history);
+                let actual_start = get_actual_start(&history);
+                let actual_complete = get_actual_complete(&task.status, &history);
+                let estimated_duration: Option<i64> = task.estimated_duration.into();
+
+                TaskRead {
+                    id: task.id,
+                    title: task.title,
+                    description: task.description,
+                    status: task.status.clone(),
+                    scheduled_start_date: task.scheduled_start_date.into(),
+                    scheduled_complete_date: task.scheduled_complete_date.into(),
+                    actual_start_date: actual_start.into(),
+                    actual_complete_date: actual_complete.map(|value| value.into()),
+                    estimated_duration,
+                    elapsed_duration,
+                    priority: task.priority,
+                    comments: comments
+                        .into_iter()
+                        .map(|c| c.into())
+                        .collect::<Vec<CommentRead>>(),
+                    tags,
+                    work_history: history
+                        .into_iter()
+                        .map(|hist| hist.into())
+                        .collect::<Vec<_>>(),
+                    parent_task_id: task.parent_task_id,
+                    project_id: task.project_id,
+                    rolled_up_estimated_duration: estimated_duration,
+                    rolled_up_status: task.status,
+                    children: Vec::new(),
+                    recurrence: recurrence.map(|recurrence| recurrence.into()),
+                    checklist_completion: checklist_completion(&checklist),
+                    checklist: checklist.into_iter().map(|item| item.into()).collect(),
+                    links,
+                    custom_fields,
+                    search_snippet: None,
+                }
+            },
+        )
         .collect();
 
-    Ok(PagedData::<TaskRead>::new(
-        params.page,
-        params.page_size,
-        count,
-        task_read,
-    ))
+    Ok(task_read)
 }
 
-#[tauri::command]
-pub async fn start_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_active(model, Status::Doing, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
-}
+/// Nest the subtasks found in `children_by_parent` beneath the task, rolling them up as it goes.
+fn attach_children(task: TaskRead, children_by_parent: &HashMap<i64, Vec<TaskRead>>) -> TaskRead {
+    let children = children_by_parent
+        .get(&task.id)
+        .map(|children| {
+            children
+                .iter()
+                .cloned()
+                .map(|child| attach_children(child, children_by_parent))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
 
-#[tauri::command]
-pub async fn pause_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_inactive(model, Status::Paused, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
+    task.with_children(children)
 }
 
-#[tauri::command]
-pub async fn resume_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_active(model, Status::Doing, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
+/// Find every task nested anywhere beneath the given tasks.
+async fn find_descendants(
+    task_ids: &Vec<i64>,
+    connection: &mut SqliteConnection,
+) -> TAResult<Vec<Task>> {
+    if task_ids.is_empty() {
+        return Ok(Vec::new());
     }
-}
 
-#[tauri::command]
-pub async fn finish_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_inactive(model, Status::Done, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
-}
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        r#"
+        WITH RECURSIVE descendants(id) AS (
+            SELECT tasks.id
+            FROM tasks
+            WHERE tasks.deleted_at IS NULL
+            AND tasks.parent_task_id"#,
+    );
 
-#[tauri::command]
-pub async fn cancel_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => {
-            delete_work_history_by_task_id(&model.id, &db).await?;
-            set_task_model_inactive(model, Status::Cancelled, &db).await
-        }
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
-}
+    add_in_expression(&mut builder, task_ids);
 
-#[tauri::command]
-pub async fn reopen_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_active(model, Status::Doing, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
-}
+    builder.push(
+        r#"
+            UNION
+            SELECT tasks.id
+            FROM tasks
+            INNER JOIN descendants ON tasks.parent_task_id = descendants.id
+            WHERE tasks.deleted_at IS NULL
+        )
+        SELECT tasks.*
+        FROM tasks
+        INNER JOIN descendants ON descendants.id = tasks.id
+        WHERE tasks.deleted_at IS NULL
+        ORDER BY tasks.id ASC
+    "#,
+    );
 
-#[tauri::command]
-pub async fn restore_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(model) => set_task_model_inactive(model, Status::Todo, &db).await,
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
-    }
+    builder
+        .build_query_as::<Task>()
+        .fetch_all(&mut *connection)
+        .await
+        .into_ta_result()
 }
 
-#[tauri::command]
-pub async fn delete_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task_id, &db).await? {
-        Some(_) => {
-            sqlx::query!("DELETE FROM tasks WHERE tasks.id = ?", task_id)
-                .execute(&db.pool)
-                .await
-                .map(|_| ())
-                .into_ta_result()?;
+/// Find the best matching fragment of each task for the search text, with the matches
+/// wrapped in `<mark>` tags.
+async fn find_search_snippets(
+    params: &TaskSearchParams,
+    task_ids: &Vec<i64>,
+    db: &Data,
+) -> TAResult<HashMap<i64, String>> {
+    let match_expression = params
+        .query_string
+        .as_deref()
+        .and_then(to_fts_match_expression);
+
+    let Some(match_expression) = match_expression else {
+        return Ok(HashMap::new());
+    };
 
-            delete_work_history_by_task_id(&task_id, &db).await
-        }
-        None => anyhow_tauri::bail!(not_found_message(task_id)),
+    if task_ids.is_empty() {
+        return Ok(HashMap::new());
     }
-}
 
-#[tauri::command]
-pub async fn delete_many_tasks(task_ids: Vec<i64>, db: State<'_, Data>) -> TAResult<()> {
-    let mut builder = QueryBuilder::new("DELETE FROM tasks WHERE tasks.id");
-    add_in_expression(&mut builder, &task_ids);
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT task_search.rowid, snippet(task_search, -1, '<mark>', '</mark>', '…', 16)
+        FROM task_search
+        WHERE task_search MATCH "#,
+    );
+
+    builder.push_bind(match_expression);
+    builder.push(" AND task_search.rowid ");
+    add_in_expression(&mut builder, task_ids);
 
     builder
-        .build()
-        .execute(&db.pool)
+        .build_query_as::<(i64, String)>()
+        .fetch_all(&db.pool)
         .await
-        .map(|_| ())
+        .map(|rows| rows.into_iter().collect())
         .into_ta_result()
 }
 
+/// Search for all tasks which match the search parameters.
+///
+/// ### Args
+/// * state - The database state used to query a connection.
+/// * params - The search parameters used to filter/sort the results.
 #[tauri::command]
-pub async fn edit_task(task: EditTask, db: State<'_, Data>) -> TAResult<()> {
-    match find_task(task.id, &db).await? {
-        Some(model) => {
-            let mut existing_task = model.clone();
-
-            existing_task.title = task.title;
-            existing_task.description = task.description;
-            existing_task.scheduled_start_date = task.scheduled_start_date.into();
-            existing_task.scheduled_complete_date = task.scheduled_complete_date.into();
-            existing_task.estimated_duration = task.estimated_duration.into();
-            let mut transaction = db.pool.begin().await.into_ta_result()?;
-            save_task(existing_task, &mut transaction).await?;
-
-            transaction.commit().await.into_ta_result()
-        }
-        None => anyhow_tauri::bail!(not_found_message(task.id)),
+pub async fn get_tasks(
+    params: TaskSearchParams,
+    db: State<'_, Data>,
+) -> TAResult<PagedData<TaskRead>> {
+    search_tasks(params, &db).await
+}
+
+/// Compile the task query language of the search, if there is one, into the other filters.
+fn resolve_search_query(params: TaskSearchParams) -> TAResult<TaskSearchParams> {
+    match params.search_query.clone() {
+        Some(search_query) => Ok(parse_task_query(&search_query, &Zoned::now())
+            .into_ta_result()?
+            .apply_to(params)),
+        None => Ok(params),
     }
 }
 
-#[tauri::command]
-pub async fn add_comment(comment: CreateComment, db: State<'_, Data>) -> TAResult<()> {
-    let model = NewComment::from(comment);
+/// Count every task matching the search, ignoring its paging.
+pub async fn count_tasks(params: &TaskSearchParams, db: &Data) -> TAResult<i64> {
+    let params = resolve_search_query(params.clone())?;
 
-    sqlx::query!(
-        r#"
+    let mut count_builder = QueryBuilder::<sqlx::Sqlite>::new("SELECT COUNT(DISTINCT id) FROM (");
+
+    count_builder = generate_search_query(count_builder, &params);
+    count_builder.push(")");
+
+    let count_query = count_builder.build_query_scalar::<i64>();
+    count_query.fetch_one(&db.pool).await.into_ta_result()
+}
+
+/// Append the `LIMIT` and `OFFSET` for the page of the search.
+///
+/// Fails when the page size is outside of `1..=MAX_PAGE_SIZE` or the page is too far out for its
+/// offset to be represented, as SQLite would otherwise read a negative limit as no limit at all.
+fn push_task_paging(
+    params: &TaskSearchParams,
+    builder: &mut QueryBuilder<'_, Sqlite>,
+) -> TAResult<()> {
+    if !(1..=MAX_PAGE_SIZE as i64).contains(&params.page_size) {
+        anyhow_tauri::bail!(format!(
+            "The page size must be between 1 and {}, not {}.",
+            MAX_PAGE_SIZE, params.page_size
+        ));
+    }
+
+    let Some(offset) = (params.page.max(1) - 1).checked_mul(params.page_size) else {
+        anyhow_tauri::bail!(format!("Page {} is out of range.", params.page));
+    };
+
+    builder.push(format!(" LIMIT {} OFFSET {}", params.page_size, offset));
+    Ok(())
+}
+
+/// Load the requested page of tasks matching the search.
+pub async fn search_tasks(params: TaskSearchParams, db: &Data) -> TAResult<PagedData<TaskRead>> {
+    let count = count_tasks(&params, db).await?;
+    let params = resolve_search_query(params)?;
+
+    let mut task_query = generate_search_query(QueryBuilder::new(""), &params);
+
+    push_task_paging(&params, &mut task_query)?;
+
+    let all_tasks: Vec<Task> = task_query
+        .build_query_as::<Task>()
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    let task_ids: Vec<i64> = all_tasks.iter().map(|task| task.id).collect();
+    let snippets = find_search_snippets(&params, &task_ids, db).await?;
+
+    let task_read = build_task_page(all_tasks, params.as_tree, db)
+        .await?
+        .into_iter()
+        .map(|task| TaskRead {
+            search_snippet: snippets.get(&task.id).cloned(),
+            ..task
+        })
+        .collect();
+
+    Ok(PagedData::<TaskRead>::new(
+        params.page,
+        params.page_size,
+        count,
+        task_read,
+    ))
+}
+
+/// Load a page of tasks with their subtasks rolled up, nesting the subtasks when `as_tree` is set.
+async fn build_task_page(
+    all_tasks: Vec<Task>,
+    as_tree: bool,
+    db: &Data,
+) -> TAResult<Vec<TaskRead>> {
+    let task_ids: Vec<i64> = all_tasks.iter().map(|task| task.id).collect();
+    let descendants =
+        find_descendants(&task_ids, &mut *db.pool.acquire().await.into_ta_result()?).await?;
+
+    let mut children_by_parent: HashMap<i64, Vec<TaskRead>> = HashMap::new();
+    for child in load_task_reads(descendants, db).await? {
+        if let Some(parent_task_id) = child.parent_task_id {
+            children_by_parent
+                .entry(parent_task_id)
+                .or_default()
+                .push(child);
+        }
+    }
+
+    Ok(load_task_reads(all_tasks, db)
+        .await?
+        .into_iter()
+        .map(|task| {
+            let task = attach_children(task, &children_by_parent);
+
+            match as_tree {
+                true => task,
+                false => TaskRead {
+                    children: Vec::new(),
+                    ..task
+                },
+            }
+        })
+        .collect())
+}
+
+/// Search tasks with a generic query of nested `and`/`or` groups.
+///
+/// Only the fields in `TASK_FILTER_FIELDS` can be filtered and sorted on, and every value is bound
+/// as a parameter.
+#[tauri::command]
+pub async fn search_tasks_advanced(
+    query: Query,
+    db: State<'_, Data>,
+) -> TAResult<PagedData<TaskRead>> {
+    let mut count_builder = QueryBuilder::<Sqlite>::new(
+        "SELECT COUNT(*) FROM tasks WHERE tasks.deleted_at IS NULL AND ",
+    );
+    query
+        .push_conditions(&mut count_builder, TASK_FILTER_FIELDS)
+        .into_ta_result()?;
+
+    let count = count_builder
+        .build_query_scalar::<i64>()
+        .fetch_one(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    let mut task_query = QueryBuilder::<Sqlite>::new(
+        "SELECT tasks.* FROM tasks WHERE tasks.deleted_at IS NULL AND ",
+    );
+    query
+        .push_conditions(&mut task_query, TASK_FILTER_FIELDS)
+        .into_ta_result()?;
+    query
+        .push_ordering(&mut task_query, TASK_FILTER_FIELDS)
+        .into_ta_result()?;
+    query.push_paging(&mut task_query).into_ta_result()?;
+
+    let all_tasks: Vec<Task> = task_query
+        .build_query_as::<Task>()
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    Ok(PagedData::<TaskRead>::new(
+        query.page as i64,
+        query.page_size as i64,
+        count,
+        build_task_page(all_tasks, false, &db).await?,
+    ))
+}
+
+/// Search work history with a generic query of nested `and`/`or` groups over
+/// `WORK_HISTORY_FILTER_FIELDS`.
+#[tauri::command]
+pub async fn search_task_work_history_advanced(
+    query: Query,
+    db: State<'_, Data>,
+) -> TAResult<PagedData<TaskWorkHistoryRead>> {
+    let mut count_builder =
+        QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM task_work_history twh INNER JOIN tasks ON tasks.id = twh.task_id WHERE tasks.deleted_at IS NULL AND ");
+    query
+        .push_conditions(&mut count_builder, WORK_HISTORY_FILTER_FIELDS)
+        .into_ta_result()?;
+
+    let count = count_builder
+        .build_query_scalar::<i64>()
+        .fetch_one(&db.pool)
+        .await
+        .into_ta_result()?;
+
+    let mut work_history_query = QueryBuilder::<Sqlite>::new(
+        "SELECT twh.id, twh.task_id, twh.start_date, COALESCE(twh.end_date, CAST(strftime('%s', 'now') AS INTEGER)) AS end_date FROM task_work_history twh INNER JOIN tasks ON tasks.id = twh.task_id WHERE tasks.deleted_at IS NULL AND ",
+    );
+    query
+        .push_conditions(&mut work_history_query, WORK_HISTORY_FILTER_FIELDS)
+        .into_ta_result()?;
+    query
+        .push_ordering(&mut work_history_query, WORK_HISTORY_FILTER_FIELDS)
+        .into_ta_result()?;
+    query.push_paging(&mut work_history_query).into_ta_result()?;
+
+    let work_history = work_history_query
+        .build_query_as::<TaskWorkHistory>()
+        .fetch_all(&db.pool)
+        .await
+        .into_ta_result()?
+        .into_iter()
+        .map(TaskWorkHistoryRead::from)
+        .collect();
+
+    Ok(PagedData::<TaskWorkHistoryRead>::new(
+        query.page as i64,
+        query.page_size as i64,
+        count,
+        work_history,
+    ))
+}
+
+/// Rank the open tasks that are not blocked by what should be worked on next, best first.
+///
+/// Each task explains its score, see `RankedTask::score` for how the points are given.
+#[tauri::command]
+pub async fn get_next_tasks(limit: Option<i64>, db: State<'_, Data>) -> TAResult<Vec<RankedTask>> {
+    let open_tasks = sqlx::query_as!(
+        Task,
+        r#"
+        SELECT tasks.*
+        FROM tasks
+        WHERE tasks.status IN ('Todo', 'Doing', 'Paused')
+        AND tasks.deleted_at IS NULL
+        AND NOT EXISTS (
+            SELECT 1
+            FROM task_dependencies td
+            INNER JOIN tasks dependency ON dependency.id = td.depends_on_task_id
+            WHERE td.task_id = tasks.id
+            AND dependency.status NOT IN ('Done', 'Cancelled')
+            AND dependency.deleted_at IS NULL
+        )
+    "#
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let now = Timestamp::now();
+    let mut ranked: Vec<RankedTask> = load_task_reads(open_tasks, &db)
+        .await?
+        .into_iter()
+        .map(|task| RankedTask::score(task, now))
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.task.priority.cmp(&b.task.priority))
+            .then(a.task.id.cmp(&b.task.id))
+    });
+    ranked.truncate(limit.unwrap_or(10).max(0) as usize);
+
+    Ok(ranked)
+}
+
+/// Start working a task.
+///
+/// ### Args
+/// * force - Start the task even when it is blocked by unfinished dependencies.
+#[tauri::command]
+pub async fn start_task(task_id: i64, force: Option<bool>, db: State<'_, Data>) -> TAResult<()> {
+    let Some(model) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+    ensure_not_blocked(model.id, force, &db).await?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let task_ids = with_active_task_ids(task_id, &mut transaction).await?;
+    let recording = JournalRecording::start("Start task", task_ids, &mut transaction).await?;
+
+    set_task_model_active(model, Status::Doing, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn pause_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let Some(model) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Pause task", vec![task_id], &mut transaction).await?;
+
+    set_task_model_inactive(model, Status::Paused, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Resume working a paused task.
+///
+/// ### Args
+/// * force - Resume the task even when it is blocked by unfinished dependencies.
+#[tauri::command]
+pub async fn resume_task(task_id: i64, force: Option<bool>, db: State<'_, Data>) -> TAResult<()> {
+    let Some(model) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+    ensure_not_blocked(model.id, force, &db).await?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let task_ids = with_active_task_ids(task_id, &mut transaction).await?;
+    let recording = JournalRecording::start("Resume task", task_ids, &mut transaction).await?;
+
+    set_task_model_active(model, Status::Doing, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Finish a task, handling any of its open subtasks according to the policy.
+///
+/// ### Args
+/// * open_children_policy - What to do with the open subtasks, leaving them open when not given.
+///
+/// ### Returns
+/// The ids of the subtasks left open, which is only ever non-empty when the policy is `Warn`.
+#[tauri::command]
+pub async fn finish_task(
+    task_id: i64,
+    open_children_policy: Option<OpenChildrenPolicy>,
+    db: State<'_, Data>,
+) -> TAResult<Vec<i64>> {
+    let model = match find_task(task_id, &db).await? {
+        Some(model) => model,
+        None => anyhow_tauri::bail!(not_found_message(task_id)),
+    };
+
+    let status = model.status.transition_to(Status::Done).into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let descendants = find_descendants(&vec![model.id], &mut transaction).await?;
+    let task_ids = std::iter::once(model.id)
+        .chain(descendants.iter().map(|child| child.id))
+        .collect();
+
+    let open_children: Vec<Task> = descendants
+        .into_iter()
+        .filter(|child| child.status != Status::Done && child.status != Status::Cancelled)
+        .collect();
+
+    let cascade = match open_children_policy.unwrap_or_default() {
+        OpenChildrenPolicy::Block if !open_children.is_empty() => anyhow_tauri::bail!(format!(
+            "Task with id '{}' has {} open subtask(s) which must be finished first.",
+            task_id,
+            open_children.len()
+        )),
+        OpenChildrenPolicy::Cascade => true,
+        _ => false,
+    };
+
+    let mut recording = JournalRecording::start("Finish task", task_ids, &mut transaction).await?;
+    let result = async {
+        if cascade {
+            for mut child in open_children.iter().cloned() {
+                // Subtasks which were never started cannot be done, so they are cancelled instead.
+                let next = match child.status {
+                    Status::Todo => Status::Cancelled,
+                    _ => Status::Done,
+                };
+                child.status = child.status.transition_to(next).into_ta_result()?;
+                deactivate_task(child.clone(), &mut transaction).await?;
+                // A recurring subtask carries on with its series however it was closed.
+                if let Some(next_task_id) =
+                    schedule_next_occurrence(&child, &mut transaction).await?
+                {
+                    recording.created(next_task_id);
+                }
+            }
+        }
+
+        let mut task = model;
+        task.status = status;
+        deactivate_task(task.clone(), &mut transaction).await?;
+        if let Some(next_task_id) = schedule_next_occurrence(&task, &mut transaction).await? {
+            recording.created(next_task_id);
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result()?,
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            return Err(e);
+        }
+    }
+
+    match cascade {
+        true => Ok(Vec::new()),
+        false => Ok(open_children.into_iter().map(|child| child.id).collect()),
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let mut task = match find_task(task_id, &db).await? {
+        Some(model) => model,
+        None => anyhow_tauri::bail!(not_found_message(task_id)),
+    };
+
+    task.status = task.status.transition_to(Status::Cancelled).into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Cancel task", vec![task_id], &mut transaction).await?;
+        deactivate_task(task, &mut transaction).await?;
+        delete_work_history_by_task_id(&task_id, &mut transaction).await?;
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn reopen_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let Some(model) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let task_ids = with_active_task_ids(task_id, &mut transaction).await?;
+    let recording = JournalRecording::start("Reopen task", task_ids, &mut transaction).await?;
+
+    set_task_model_active(model, Status::Doing, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn restore_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let Some(model) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Restore task", vec![task_id], &mut transaction).await?;
+
+    set_task_model_inactive(model, Status::Todo, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Move a task and its subtasks to the trash.
+#[tauri::command]
+pub async fn delete_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let task_ids = with_descendant_ids(vec![task_id], &mut transaction).await?;
+    let recording = JournalRecording::start("Delete task", task_ids, &mut transaction).await?;
+
+    trash_tasks(&vec![task_id], &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Move the tasks and their subtasks to the trash.
+#[tauri::command]
+pub async fn delete_many_tasks(task_ids: Vec<i64>, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let journal_task_ids = with_descendant_ids(task_ids.clone(), &mut transaction).await?;
+    let recording =
+        JournalRecording::start("Delete tasks", journal_task_ids, &mut transaction).await?;
+
+    trash_tasks(&task_ids, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// The task along with the tasks in progress, which starting it may pause.
+async fn with_active_task_ids(
+    task_id: i64,
+    connection: &mut SqliteConnection,
+) -> TAResult<Vec<i64>> {
+    let mut task_ids = sqlx::query_scalar!(
+        "SELECT tasks.id FROM tasks WHERE tasks.status = 'Doing' AND tasks.deleted_at IS NULL"
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    task_ids.push(task_id);
+    Ok(task_ids)
+}
+
+/// The tasks along with everything nested beneath them.
+async fn with_descendant_ids(
+    mut task_ids: Vec<i64>,
+    connection: &mut SqliteConnection,
+) -> TAResult<Vec<i64>> {
+    let descendants = find_descendants(&task_ids, connection).await?;
+    task_ids.extend(descendants.into_iter().map(|task| task.id));
+    Ok(task_ids)
+}
+
+/// Move the tasks and everything nested beneath them to the trash, all with the same
+/// `deleted_at` so that they are restored together.
+///
+/// Tasks in progress are paused and their running session is closed, so that no time is
+/// tracked against a task in the trash. Their work history is kept until the trash is purged.
+async fn trash_tasks(
+    task_ids: &Vec<i64>,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    if task_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        r#"
+        WITH RECURSIVE trashed(id) AS (
+            SELECT tasks.id
+            FROM tasks
+            WHERE tasks.deleted_at IS NULL
+            AND tasks.id"#,
+    );
+    add_in_expression(&mut builder, task_ids);
+    builder.push(
+        r#"
+            UNION
+            SELECT tasks.id
+            FROM tasks
+            INNER JOIN trashed ON tasks.parent_task_id = trashed.id
+            WHERE tasks.deleted_at IS NULL
+        )
+        SELECT trashed.id FROM trashed
+    "#,
+    );
+
+    let trashed_ids: Vec<i64> = builder
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+    if trashed_ids.is_empty() {
+        return Ok(());
+    }
+
+    let now = UnixTimestamp::now();
+
+    let mut builder = QueryBuilder::<Sqlite>::new("UPDATE task_work_history SET end_date = ");
+    builder.push_bind(now);
+    builder.push(" WHERE task_work_history.end_date IS NULL AND task_work_history.task_id");
+    add_in_expression(&mut builder, &trashed_ids);
+    builder
+        .build()
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "UPDATE tasks SET status = 'Paused' WHERE tasks.status = 'Doing' AND tasks.id",
+    );
+    add_in_expression(&mut builder, &trashed_ids);
+    builder
+        .build()
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new("UPDATE tasks SET deleted_at = ");
+    builder.push_bind(now);
+    builder.push(" WHERE tasks.id");
+    add_in_expression(&mut builder, &trashed_ids);
+    builder
+        .build()
+        .execute(&mut **transaction)
+        .await
+        .map(|_| ())
+        .into_ta_result()
+}
+
+#[tauri::command]
+pub async fn edit_task(task: EditTask, db: State<'_, Data>) -> TAResult<()> {
+    let Some(mut existing_task) = find_task(task.id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task.id));
+    };
+
+    existing_task.title = task.title;
+    existing_task.description = task.description;
+    existing_task.scheduled_start_date = task.scheduled_start_date.into();
+    existing_task.scheduled_complete_date = task.scheduled_complete_date.into();
+    existing_task.estimated_duration = task.estimated_duration.into();
+    existing_task.priority = task.priority.unwrap_or(existing_task.priority);
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Edit task", vec![task.id], &mut transaction).await?;
+
+    save_task(existing_task, &mut transaction).await?;
+    set_task_custom_field_values(task.id, &task.custom_fields, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn add_comment(comment: CreateComment, db: State<'_, Data>) -> TAResult<()> {
+    let model = NewComment::from(comment);
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Add comment", vec![model.task_id], &mut transaction).await?;
+
+    sqlx::query!(
+        r#"
         INSERT INTO comments (task_id, message, created, modified)
         VALUES (?, ?, ?, ?)
     "#,
-        model.task_id,
-        model.message,
-        model.created,
-        model.modified
+        model.task_id,
+        model.message,
+        model.created,
+        model.modified
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn update_comment(comment: EditComment, db: State<'_, Data>) -> TAResult<()> {
+    let mut found: Comment = sqlx::query_as!(
+        Comment,
+        r#"
+            SELECT *
+            FROM comments
+            WHERE comments.id = ?
+            AND comments.deleted_at IS NULL
+        "#,
+        comment.id
+    )
+    .fetch_one(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Edit comment", vec![found.task_id], &mut transaction).await?;
+
+    found.message = comment.message;
+    found.modified = OptionalUnixTimestamp::now();
+
+    sqlx::query!(
+        r#"
+        UPDATE comments 
+        SET message = ?, 
+        modified = ? 
+        WHERE id = ?"#,
+        found.message,
+        found.modified,
+        found.id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Move a comment to the trash.
+#[tauri::command]
+pub async fn delete_comment(id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let task_ids = sqlx::query_scalar!(
+        "SELECT comments.task_id FROM comments WHERE comments.id = ?",
+        id
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Delete comment", task_ids, &mut transaction).await?;
+    let deleted_at = UnixTimestamp::now();
+
+    sqlx::query!(
+        r#"
+            UPDATE comments
+            SET deleted_at = ?
+            WHERE comments.id = ?
+            AND comments.deleted_at IS NULL
+        "#,
+        deleted_at,
+        id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+async fn find_checklist_item(item_id: i64, db: &Data) -> TAResult<ChecklistItem> {
+    let item = sqlx::query_as!(
+        ChecklistItem,
+        "SELECT * FROM task_checklist_items tci WHERE tci.id = ?",
+        item_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match item {
+        Some(item) => Ok(item),
+        None => anyhow_tauri::bail!(format!("Checklist item with id '{}' not found.", item_id)),
+    }
+}
+
+fn validate_checklist_title(title: &str) -> TAResult<String> {
+    let title = title.trim();
+
+    if title.is_empty() {
+        anyhow_tauri::bail!("A checklist item must have a title.");
+    }
+
+    Ok(title.to_string())
+}
+
+/// Add an item to the end of the checklist of a task.
+#[tauri::command]
+pub async fn add_checklist_item(
+    item: CreateChecklistItem,
+    db: State<'_, Data>,
+) -> TAResult<ChecklistItemRead> {
+    if find_task(item.task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(item.task_id));
+    }
+
+    let title = validate_checklist_title(&item.title)?;
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Add checklist item", vec![item.task_id], &mut transaction).await?;
+
+    let created = sqlx::query_as!(
+        ChecklistItem,
+        r#"
+        INSERT INTO task_checklist_items (task_id, title, position, estimated_duration)
+        VALUES (?, ?, (
+            SELECT COALESCE(MAX(tci.position) + 1, 0)
+            FROM task_checklist_items tci
+            WHERE tci.task_id = ?), ?)
+        RETURNING *
+    "#,
+        item.task_id,
+        title,
+        item.task_id,
+        item.estimated_duration
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()?;
+    Ok(created.into())
+}
+
+#[tauri::command]
+pub async fn edit_checklist_item(item: EditChecklistItem, db: State<'_, Data>) -> TAResult<()> {
+    let found = find_checklist_item(item.id, &db).await?;
+    let title = validate_checklist_title(&item.title)?;
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Edit checklist item", vec![found.task_id], &mut transaction)
+            .await?;
+
+    sqlx::query!(
+        "UPDATE task_checklist_items SET title = ?, estimated_duration = ? WHERE id = ?",
+        title,
+        item.estimated_duration,
+        item.id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Check or uncheck an item, remembering when it was checked.
+#[tauri::command]
+pub async fn set_checklist_item_checked(
+    item_id: i64,
+    checked: bool,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let found = find_checklist_item(item_id, &db).await?;
+
+    if found.checked == checked {
+        return Ok(());
+    }
+
+    let description = match checked {
+        true => "Check checklist item",
+        false => "Uncheck checklist item",
+    };
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start(description, vec![found.task_id], &mut transaction).await?;
+
+    let checked_at = match checked {
+        true => OptionalUnixTimestamp::now(),
+        false => OptionalUnixTimestamp::none(),
+    };
+
+    sqlx::query!(
+        "UPDATE task_checklist_items SET checked = ?, checked_at = ? WHERE id = ?",
+        checked,
+        checked_at,
+        item_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn delete_checklist_item(item_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let found = find_checklist_item(item_id, &db).await?;
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start(
+        "Delete checklist item",
+        vec![found.task_id],
+        &mut transaction,
+    )
+    .await?;
+
+    sqlx::query!("DELETE FROM task_checklist_items WHERE id = ?", item_id)
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Put the checklist of a task into a new order.
+///
+/// ### Args
+/// * item_ids - Every item of the checklist, in the order they should appear.
+#[tauri::command]
+pub async fn reorder_checklist_items(
+    task_id: i64,
+    item_ids: Vec<i64>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let mut existing_ids = sqlx::query_scalar!(
+        "SELECT tci.id FROM task_checklist_items tci WHERE tci.task_id = ?",
+        task_id
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+    existing_ids.sort();
+
+    let mut given_ids = item_ids.clone();
+    given_ids.sort();
+
+    if existing_ids != given_ids {
+        anyhow_tauri::bail!(format!(
+            "The new order must include every item of the checklist of task '{}' exactly once.",
+            task_id
+        ));
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result = async {
+        let recording =
+            JournalRecording::start("Reorder checklist", vec![task_id], &mut transaction).await?;
+
+        for (position, item_id) in item_ids.iter().enumerate() {
+            let position = position as i64;
+
+            sqlx::query!(
+                "UPDATE task_checklist_items SET position = ? WHERE id = ?",
+                position,
+                item_id
+            )
+            .execute(&mut *transaction)
+            .await
+            .into_ta_result()?;
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+fn not_found_message(task_id: i64) -> String {
+    format!("Task with id '{}' not found.", task_id)
+}
+
+async fn delete_work_history_by_task_id(
+    task_id: &i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM task_work_history
+        WHERE task_work_history.task_id = ?
+    "#,
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+async fn save_task(task: Task, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET title = ?,
+        description = ?,
+        status = ?,
+        scheduled_start_date = ?,
+        scheduled_complete_date = ?,
+        estimated_duration = ?,
+        priority = ?
+        WHERE tasks.id = ?
+        "#,
+        task.title,
+        task.description,
+        task.status,
+        task.scheduled_start_date,
+        task.scheduled_complete_date,
+        task.estimated_duration,
+        task.priority,
+        task.id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Find a task by its id.
+async fn find_task(task_id: i64, db: &State<'_, Data>) -> TAResult<Option<Task>> {
+    sqlx::query_as!(
+        Task,
+        "SELECT * FROM tasks WHERE id = ? AND deleted_at IS NULL",
+        task_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()
+}
+
+/// Update the status when transitioning to an active state.
+async fn set_task_model_active(
+    mut task: Task,
+    status: Status,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    task.status = task.status.transition_to(status).into_ta_result()?;
+
+    let now = OptionalUnixTimestamp::now();
+
+    let allow_concurrent_timers =
+        sqlx::query_scalar!("SELECT allow_concurrent_timers FROM user_settings LIMIT 1")
+            .fetch_one(&mut **transaction)
+            .await
+            .into_ta_result()?;
+
+    if !allow_concurrent_timers {
+        pause_other_active_tasks(task.id, now, transaction).await?;
+    }
+
+    let duration = OptionalDurationInSeconds::none();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_work_history (task_id, start_date, end_date) 
+        VALUES (?, ?, ?);
+        "#,
+        task.id,
+        now,
+        duration,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()?;
+
+    save_task(task, transaction).await
+}
+
+/// Pause every other task being worked, closing their open work history at the given time.
+async fn pause_other_active_tasks(
+    task_id: i64,
+    now: OptionalUnixTimestamp,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE task_work_history
+        SET end_date = ?
+        WHERE end_date IS NULL
+        AND task_id <> ?
+        "#,
+        now,
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()?;
+
+    let paused = Status::Paused;
+    let doing = Status::Doing;
+
+    sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET status = ?
+        WHERE status = ?
+        AND id <> ?
+        "#,
+        paused,
+        doing,
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Update the status when transition to a paused or finished state.
+async fn set_task_model_inactive(
+    mut task: Task,
+    status: Status,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    task.status = task.status.transition_to(status).into_ta_result()?;
+
+    deactivate_task(task, transaction).await
+}
+
+/// Close the open work history for the task and save it with its new status.
+async fn deactivate_task(task: Task, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
+    let existing = sqlx::query_as!(
+        TaskWorkHistory, 
+        r#"
+        SELECT * from task_work_history twh
+        WHERE twh.task_id = ?
+        AND twh.end_date IS NULL
+        LIMIT 1
+        "#,
+        task.id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    if let Some(existing) = existing {
+        let now = OptionalUnixTimestamp::now();
+        
+        sqlx::query!(
+            r#"
+            UPDATE task_work_history 
+            SET end_date = ?
+            WHERE id = ?
+            "#,
+            now,
+            existing.id,
+        )
+        .execute(&mut **transaction)
+        .await
+        .map(|_| ())
+        .into_ta_result()?;
+    }
+
+    save_task(task, transaction).await
+}
+
+/// Nest a task beneath a parent task.
+#[tauri::command]
+pub async fn nest_task(task_id: i64, parent_task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    if find_task(parent_task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(parent_task_id));
+    }
+
+    if task_id == parent_task_id {
+        anyhow_tauri::bail!("A task cannot be nested beneath itself.");
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let creates_cycle = find_descendants(&vec![task_id], &mut transaction)
+        .await?
+        .iter()
+        .any(|descendant| descendant.id == parent_task_id);
+
+    if creates_cycle {
+        anyhow_tauri::bail!(format!(
+            "Task with id '{}' cannot be nested beneath its own subtask '{}'.",
+            task_id, parent_task_id
+        ));
+    }
+
+    let recording = JournalRecording::start("Nest task", vec![task_id], &mut transaction).await?;
+
+    sqlx::query!(
+        "UPDATE tasks SET parent_task_id = ? WHERE tasks.id = ?",
+        parent_task_id,
+        task_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Move a task out from beneath its parent so that it becomes a top level task.
+#[tauri::command]
+pub async fn unnest_task(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start("Unnest task", vec![task_id], &mut transaction).await?;
+
+    sqlx::query!(
+        "UPDATE tasks SET parent_task_id = NULL WHERE tasks.id = ?",
+        task_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Add a finished session of work to a task.
+///
+/// ### Args
+/// * overlap_policy - What to do when the session overlaps other sessions, rejecting it when
+///   not given.
+#[tauri::command]
+pub async fn add_task_work_history(
+    new_task_work_history: NewTaskWorkHistory,
+    overlap_policy: Option<OverlapPolicy>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let start_date: UnixTimestamp = UnixTimestamp::from(&new_task_work_history.start_date);
+    let end_date: UnixTimestamp = UnixTimestamp::from(&new_task_work_history.end_date);
+    let task_id = new_task_work_history.task_id;
+
+    let mut connection = db.pool.acquire().await.into_ta_result()?;
+    let overlapping = check_session(
+        task_id,
+        start_date,
+        end_date,
+        None,
+        overlap_policy.clone(),
+        &mut connection,
+    )
+    .await?;
+    drop(connection);
+
+    let task_ids = with_overlapping_task_ids(task_id, &overlapping);
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Add work history", task_ids.clone(), &mut transaction).await?;
+        let session = SessionCheck {
+            task_id,
+            start_date,
+            end_date,
+            excluded_history_id: None,
+        };
+        trim_overlaps(&session, overlap_policy, &task_ids, &mut transaction).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO task_work_history (task_id, start_date, end_date)
+            VALUES (?, ?, ?)
+        "#,
+            task_id,
+            start_date,
+            end_date
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn delete_task_work_history(history_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let task_ids = find_work_history_task_ids(history_id, &db).await?;
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Delete work history", task_ids, &mut transaction).await?;
+
+    sqlx::query!(
+        r#"
+            DELETE FROM task_work_history
+            WHERE task_work_history.id = ?
+        "#,
+        history_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Change when a session of work started and ended.
+///
+/// ### Args
+/// * overlap_policy - What to do when the session overlaps other sessions, rejecting the change
+///   when not given.
+#[tauri::command]
+pub async fn edit_task_work_history(
+    edit_task_work_history: EditTaskWorkHistory,
+    overlap_policy: Option<OverlapPolicy>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let start_date: UnixTimestamp = UnixTimestamp::from(&edit_task_work_history.start_date);
+    let end_date: UnixTimestamp = UnixTimestamp::from(&edit_task_work_history.end_date);
+    let history_id = edit_task_work_history.id;
+
+    let Some(task_id) = find_work_history_task_ids(history_id, &db).await?.pop() else {
+        anyhow_tauri::bail!(format!("Work history with id '{}' not found.", history_id));
+    };
+
+    let mut connection = db.pool.acquire().await.into_ta_result()?;
+    let overlapping = check_session(
+        task_id,
+        start_date,
+        end_date,
+        Some(history_id),
+        overlap_policy.clone(),
+        &mut connection,
+    )
+    .await?;
+    drop(connection);
+
+    let task_ids = with_overlapping_task_ids(task_id, &overlapping);
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Edit work history", task_ids.clone(), &mut transaction)
+                .await?;
+        let session = SessionCheck {
+            task_id,
+            start_date,
+            end_date,
+            excluded_history_id: Some(history_id),
+        };
+        trim_overlaps(&session, overlap_policy, &task_ids, &mut transaction).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE task_work_history
+            SET start_date = ?,
+            end_date = ?
+            WHERE task_work_history.id = ?
+        "#,
+            start_date,
+            end_date,
+            history_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+/// Validate a session which is about to be saved and find the sessions it overlaps.
+///
+/// Sessions of other tasks only count as overlapping when timers cannot run at the same time.
+///
+/// ### Returns
+/// The overlapping sessions, which are only ever returned when the policy allows trimming them.
+async fn check_session(
+    task_id: i64,
+    start_date: UnixTimestamp,
+    end_date: UnixTimestamp,
+    excluded_history_id: Option<i64>,
+    overlap_policy: Option<OverlapPolicy>,
+    connection: &mut SqliteConnection,
+) -> TAResult<Vec<OverlappingSession>> {
+    if end_date <= start_date {
+        anyhow_tauri::bail!("A session of work must end after it starts.");
+    }
+
+    let allow_concurrent_timers =
+        sqlx::query_scalar!("SELECT allow_concurrent_timers FROM user_settings LIMIT 1")
+            .fetch_one(&mut *connection)
+            .await
+            .into_ta_result()?;
+
+    let overlapping = sqlx::query_as!(
+        OverlappingSession,
+        r#"
+        SELECT twh.id, twh.task_id, tasks.title AS task_title, twh.start_date, twh.end_date
+        FROM task_work_history twh
+        INNER JOIN tasks ON tasks.id = twh.task_id
+        WHERE twh.id IS NOT ?
+        AND tasks.deleted_at IS NULL
+        AND (twh.task_id = ? OR NOT ?)
+        AND twh.start_date < ?
+        AND COALESCE(twh.end_date, CAST(strftime('%s', 'now') AS INTEGER)) > ?
+        ORDER BY twh.start_date ASC
+    "#,
+        excluded_history_id,
+        task_id,
+        allow_concurrent_timers,
+        end_date,
+        start_date
     )
-    .execute(&db.pool)
+    .fetch_all(&mut *connection)
+    .await
+    .into_ta_result()?;
+
+    if !overlapping.is_empty() && overlap_policy.unwrap_or_default() == OverlapPolicy::Reject {
+        let mut titles: Vec<&str> = overlapping
+            .iter()
+            .map(|session| session.task_title.as_str())
+            .collect();
+        titles.sort();
+        titles.dedup();
+
+        anyhow_tauri::bail!(format!(
+            "The session overlaps {} other session(s) of: {}.",
+            overlapping.len(),
+            titles.join(", ")
+        ));
+    }
+
+    Ok(overlapping)
+}
+
+/// A session of work which is about to be saved.
+struct SessionCheck {
+    task_id: i64,
+    start_date: UnixTimestamp,
+    end_date: UnixTimestamp,
+    /// The session itself when it is already saved.
+    excluded_history_id: Option<i64>,
+}
+
+/// Check the session again within the transaction which saves it and trim what it overlaps, so
+/// that no session added since the first check is left overlapping it.
+///
+/// Fails when the session now overlaps a task which the journal was not told about.
+async fn trim_overlaps(
+    session: &SessionCheck,
+    overlap_policy: Option<OverlapPolicy>,
+    recorded_task_ids: &[i64],
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let overlapping = check_session(
+        session.task_id,
+        session.start_date,
+        session.end_date,
+        session.excluded_history_id,
+        overlap_policy,
+        transaction,
+    )
+    .await?;
+
+    if overlapping
+        .iter()
+        .any(|overlap| !recorded_task_ids.contains(&overlap.task_id))
+    {
+        anyhow_tauri::bail!("The sessions of work changed while saving the session, try again.");
+    }
+
+    trim_overlapping_sessions(
+        session.start_date,
+        session.end_date,
+        &overlapping,
+        transaction,
+    )
+    .await
+}
+
+/// The task of a session along with the tasks of the sessions it overlaps.
+fn with_overlapping_task_ids(task_id: i64, overlapping: &Vec<OverlappingSession>) -> Vec<i64> {
+    let mut task_ids: Vec<i64> = std::iter::once(task_id)
+        .chain(overlapping.iter().map(|session| session.task_id))
+        .collect();
+    task_ids.sort();
+    task_ids.dedup();
+    task_ids
+}
+
+/// Trim the overlapping sessions so that they no longer overlap the period.
+async fn trim_overlapping_sessions(
+    start_date: UnixTimestamp,
+    end_date: UnixTimestamp,
+    overlapping: &Vec<OverlappingSession>,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    for session in overlapping.iter() {
+        let starts_before = session.start_date < start_date;
+        let ends_after = session.end_date.unwrap_or_now() > end_date;
+
+        match (starts_before, ends_after) {
+            (false, false) => {
+                sqlx::query!(
+                    "DELETE FROM task_work_history WHERE task_work_history.id = ?",
+                    session.id
+                )
+                .execute(&mut **transaction)
+                .await
+                .into_ta_result()?;
+            }
+            (true, false) => {
+                sqlx::query!(
+                    "UPDATE task_work_history SET end_date = ? WHERE task_work_history.id = ?",
+                    start_date,
+                    session.id
+                )
+                .execute(&mut **transaction)
+                .await
+                .into_ta_result()?;
+            }
+            (false, true) => {
+                sqlx::query!(
+                    "UPDATE task_work_history SET start_date = ? WHERE task_work_history.id = ?",
+                    end_date,
+                    session.id
+                )
+                .execute(&mut **transaction)
+                .await
+                .into_ta_result()?;
+            }
+            (true, true) => {
+                // The rest of the session keeps its end, so a running session carries on running.
+                sqlx::query!(
+                    "UPDATE task_work_history SET end_date = ? WHERE task_work_history.id = ?",
+                    start_date,
+                    session.id
+                )
+                .execute(&mut **transaction)
+                .await
+                .into_ta_result()?;
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO task_work_history (task_id, start_date, end_date)
+                    VALUES (?, ?, ?)
+                "#,
+                    session.task_id,
+                    end_date,
+                    session.end_date
+                )
+                .execute(&mut **transaction)
+                .await
+                .into_ta_result()?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Find every pair of sessions which overlap, so that they can be fixed before they are counted
+/// twice. Sessions of different tasks only conflict when timers cannot run at the same time.
+#[tauri::command]
+pub async fn find_work_history_conflicts(
+    db: State<'_, Data>,
+) -> TAResult<Vec<WorkHistoryConflict>> {
+    let allow_concurrent_timers =
+        sqlx::query_scalar!("SELECT allow_concurrent_timers FROM user_settings LIMIT 1")
+            .fetch_one(&db.pool)
+            .await
+            .into_ta_result()?;
+
+    let sessions: Vec<TaskWorkHistory> = sqlx::query_as(
+        r#"
+        SELECT twh.id, twh.task_id, twh.start_date, COALESCE(twh.end_date, CAST(strftime('%s', 'now') AS INTEGER)) AS end_date
+        FROM task_work_history twh
+        INNER JOIN tasks ON tasks.id = twh.task_id
+        WHERE tasks.deleted_at IS NULL
+        ORDER BY twh.start_date ASC, twh.id ASC
+    "#,
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    // Sorted by start, each session can only overlap those after it which start before it ends.
+    let mut conflicts = Vec::new();
+    for (i, earlier) in sessions.iter().enumerate() {
+        for later in sessions[i + 1..].iter() {
+            if later.start_date >= earlier.end_date {
+                break;
+            }
+
+            if allow_concurrent_timers && later.task_id != earlier.task_id {
+                continue;
+            }
+
+            conflicts.push(WorkHistoryConflict::new(earlier.clone(), later.clone()));
+        }
+    }
+
+    Ok(conflicts)
+}
+
+async fn find_session(history_id: i64, db: &Data) -> TAResult<WorkSession> {
+    let session = sqlx::query_as!(
+        WorkSession,
+        "SELECT * FROM task_work_history WHERE task_work_history.id = ?",
+        history_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match session {
+        Some(session) => Ok(session),
+        None => anyhow_tauri::bail!(format!("Work history with id '{}' not found.", history_id)),
+    }
+}
+
+/// Split a session in two at the timestamp, which must fall inside it.
+///
+/// ### Returns
+/// The second part, which keeps the end of the session so that a running session carries on
+/// running.
+async fn split_session(
+    session: &WorkSession,
+    at: UnixTimestamp,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<WorkSession> {
+    let (first, second) = session.split_at(at).into_ta_result()?;
+
+    sqlx::query!(
+        "UPDATE task_work_history SET end_date = ? WHERE task_work_history.id = ?",
+        first.end_date,
+        first.id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO task_work_history (task_id, start_date, end_date)
+        VALUES (?, ?, ?)
+    "#,
+        second.task_id,
+        second.start_date,
+        second.end_date
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    Ok(WorkSession {
+        id: result.last_insert_rowid(),
+        ..second
+    })
+}
+
+/// Split a session of work in two at a point in time.
+#[tauri::command]
+pub async fn split_task_work_history(
+    history_id: i64,
+    at: Timestamp,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let session = find_session(history_id, &db).await?;
+    let at = UnixTimestamp::from(&at);
+    session.split_at(at).into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start(
+        "Split work history",
+        vec![session.task_id],
+        &mut transaction,
+    )
+    .await?;
+
+    split_session(&session, at, &mut transaction).await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+/// Merge sessions of the same task which follow on from each other into one.
+///
+/// The sessions must leave no gaps between them, so that the merged session lasts exactly as
+/// long as they did together.
+#[tauri::command]
+pub async fn merge_task_work_history(history_ids: Vec<i64>, db: State<'_, Data>) -> TAResult<()> {
+    let mut sessions = Vec::with_capacity(history_ids.len());
+    for history_id in history_ids.iter() {
+        sessions.push(find_session(*history_id, &db).await?);
+    }
+
+    let (merged, replaced_ids) = merge_sessions(sessions).into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording =
+            JournalRecording::start("Merge work history", vec![merged.task_id], &mut transaction)
+                .await?;
+        for replaced_id in replaced_ids.iter() {
+            sqlx::query!(
+                "DELETE FROM task_work_history WHERE task_work_history.id = ?",
+                replaced_id
+            )
+            .execute(&mut *transaction)
+            .await
+            .into_ta_result()?;
+        }
+
+        sqlx::query!(
+            "UPDATE task_work_history SET end_date = ? WHERE task_work_history.id = ?",
+            merged.end_date,
+            merged.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+/// Move a session of work, or part of it, onto another task.
+///
+/// ### Args
+/// * start_date - Where the part to move starts, the start of the session when not given.
+/// * end_date - Where the part to move ends, the end of the session when not given. The part of
+///   a session which is still running cannot be moved, the timer has to be stopped first.
+#[tauri::command]
+pub async fn move_task_work_history(
+    history_id: i64,
+    task_id: i64,
+    start_date: Option<Timestamp>,
+    end_date: Option<Timestamp>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let session = find_session(history_id, &db).await?;
+
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    if session.task_id == task_id {
+        anyhow_tauri::bail!("The session already belongs to this task.");
+    }
+
+    let (start_date, end_date) = session
+        .part_between(
+            start_date.map(|date| UnixTimestamp::from(&date)),
+            end_date.map(|date| UnixTimestamp::from(&date)),
+        )
+        .into_ta_result()?;
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let result = async {
+        let recording = JournalRecording::start(
+            "Move work history",
+            vec![session.task_id, task_id],
+            &mut transaction,
+        )
+        .await?;
+        // The part has to fit in with the sessions of the task it moves to.
+        check_session(
+            task_id,
+            start_date,
+            end_date,
+            Some(session.id),
+            None,
+            &mut transaction,
+        )
+        .await?;
+
+        // Cut off whatever lies before and after the part, leaving the part in a session of its own.
+        let mut moved_session = session.clone();
+        if moved_session.contains(start_date) {
+            moved_session = split_session(&moved_session, start_date, &mut transaction).await?;
+        }
+
+        if moved_session.contains(end_date) {
+            split_session(&moved_session, end_date, &mut transaction).await?;
+        }
+
+        sqlx::query!(
+            "UPDATE task_work_history SET task_id = ? WHERE task_work_history.id = ?",
+            task_id,
+            moved_session.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+/// The task a session of work belongs to, or nothing when the session does not exist.
+async fn find_work_history_task_ids(history_id: i64, db: &Data) -> TAResult<Vec<i64>> {
+    sqlx::query_scalar!(
+        "SELECT task_work_history.task_id FROM task_work_history WHERE task_work_history.id = ?",
+        history_id
+    )
+    .fetch_all(&db.pool)
     .await
-    .map(|_| ())
     .into_ta_result()
 }
 
+/// Make a task depend on another task, so that it cannot be started until the other is done or
+/// cancelled.
 #[tauri::command]
-pub async fn update_comment(comment: EditComment, db: State<'_, Data>) -> TAResult<()> {
-    let mut found: Comment = sqlx::query_as!(
-        Comment,
+pub async fn add_task_dependency(
+    task_id: i64,
+    depends_on_task_id: i64,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    if find_task(depends_on_task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(depends_on_task_id));
+    }
+
+    if task_id == depends_on_task_id {
+        anyhow_tauri::bail!("A task cannot depend on itself.");
+    }
+
+    // Adding the dependency creates a cycle when the other task already depends on this one,
+    // the shortest chain of dependencies between them is kept to explain why.
+    let cycle_path: Option<String> = sqlx::query_scalar(
         r#"
-            SELECT *
-            FROM comments
-            WHERE comments.id = ?
-        "#,
-        comment.id
+        WITH RECURSIVE upstream(id, path) AS (
+            SELECT td.depends_on_task_id, td.task_id || ' -> ' || td.depends_on_task_id
+            FROM task_dependencies td
+            WHERE td.task_id = ?
+            UNION
+            SELECT td.depends_on_task_id, upstream.path || ' -> ' || td.depends_on_task_id
+            FROM task_dependencies td
+            INNER JOIN upstream ON td.task_id = upstream.id
+        )
+        SELECT upstream.path
+        FROM upstream
+        WHERE upstream.id = ?
+        ORDER BY LENGTH(upstream.path) ASC
+        LIMIT 1
+    "#,
     )
-    .fetch_one(&db.pool)
+    .bind(depends_on_task_id)
+    .bind(task_id)
+    .fetch_optional(&db.pool)
     .await
     .into_ta_result()?;
 
-    found.message = comment.message;
-    found.modified = OptionalUnixTimestamp::now();
+    if let Some(cycle_path) = cycle_path {
+        anyhow_tauri::bail!(format!(
+            "Task with id '{}' already depends on task with id '{}' through {}, so this would create a cycle.",
+            depends_on_task_id, task_id, cycle_path
+        ));
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Add dependency", vec![task_id], &mut transaction).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_task_id)
+        VALUES (?, ?)
+    "#,
+        task_id,
+        depends_on_task_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
+}
+
+#[tauri::command]
+pub async fn remove_task_dependency(
+    task_id: i64,
+    depends_on_task_id: i64,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Remove dependency", vec![task_id], &mut transaction).await?;
 
     sqlx::query!(
         r#"
-        UPDATE comments 
-        SET message = ?, 
-        modified = ? 
-        WHERE id = ?"#,
-        found.message,
-        found.modified,
-        found.id
+        DELETE FROM task_dependencies
+        WHERE task_dependencies.task_id = ?
+        AND task_dependencies.depends_on_task_id = ?
+    "#,
+        task_id,
+        depends_on_task_id
     )
-    .execute(&db.pool)
+    .execute(&mut *transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
-#[tauri::command]
-pub async fn delete_comment(id: i64, db: State<'_, Data>) -> TAResult<()> {
-    sqlx::query!(
+/// Get the dependencies of a task which are neither done nor cancelled.
+async fn find_blockers(task_id: i64, db: &State<'_, Data>) -> TAResult<Vec<Task>> {
+    sqlx::query_as!(
+        Task,
         r#"
-            DELETE FROM comments
-            WHERE comments.id = ?
-        "#,
-        id
+        SELECT tasks.*
+        FROM tasks
+        INNER JOIN task_dependencies td ON td.depends_on_task_id = tasks.id
+        WHERE td.task_id = ?
+        AND tasks.status NOT IN ('Done', 'Cancelled')
+        AND tasks.deleted_at IS NULL
+    "#,
+        task_id
     )
-    .execute(&db.pool)
+    .fetch_all(&db.pool)
     .await
-    .map(|_| ())
     .into_ta_result()
 }
 
-fn not_found_message(task_id: i64) -> String {
-    format!("Task with id '{}' not found.", task_id)
+/// Fail when the task is blocked by unfinished dependencies, unless forced.
+async fn ensure_not_blocked(task_id: i64, force: Option<bool>, db: &State<'_, Data>) -> TAResult<()> {
+    if force.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let blockers = find_blockers(task_id, db).await?;
+
+    if !blockers.is_empty() {
+        let titles = blockers
+            .iter()
+            .map(|blocker| format!("'{}'", blocker.title))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        anyhow_tauri::bail!(format!(
+            "Task with id '{}' is blocked by unfinished tasks: {}.",
+            task_id, titles
+        ));
+    }
+
+    Ok(())
 }
 
-async fn delete_work_history_by_task_id(task_id: &i64, db: &State<'_, Data>) -> TAResult<()> {
-    sqlx::query!(
+/// Link a task to another one. The link shows up on both tasks.
+///
+/// ### Args
+/// * link_type - How the task relates to the linked task, e.g. `FollowUpOf` for a follow-up.
+#[tauri::command]
+pub async fn link_tasks(
+    task_id: i64,
+    linked_task_id: i64,
+    link_type: LinkType,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    if find_task(task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    }
+
+    if find_task(linked_task_id, &db).await?.is_none() {
+        anyhow_tauri::bail!(not_found_message(linked_task_id));
+    }
+
+    if task_id == linked_task_id {
+        anyhow_tauri::bail!("A task cannot be linked to itself.");
+    }
+
+    // The same kind of link the other way round would say the same thing, or contradict it.
+    let existing_count = sqlx::query_scalar!(
         r#"
-        DELETE FROM task_work_history
-        WHERE task_work_history.task_id = ?
+        SELECT COUNT(*) AS "count!: i64"
+        FROM task_links tl
+        WHERE tl.link_type = ?
+        AND ((tl.task_id = ? AND tl.linked_task_id = ?) OR (tl.task_id = ? AND tl.linked_task_id = ?))
     "#,
+        link_type,
+        task_id,
+        linked_task_id,
+        linked_task_id,
         task_id
     )
-    .execute(&db.pool)
+    .fetch_one(&db.pool)
     .await
-    .map(|_| ())
-    .into_ta_result()
-}
+    .into_ta_result()?;
+
+    if existing_count > 0 {
+        anyhow_tauri::bail!(format!(
+            "Tasks with ids '{}' and '{}' are already linked this way.",
+            task_id, linked_task_id
+        ));
+    }
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start(
+        "Link tasks",
+        vec![task_id, linked_task_id],
+        &mut transaction,
+    )
+    .await?;
+    let created = UnixTimestamp::now();
 
-async fn save_task(task: Task, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
     sqlx::query!(
         r#"
-        UPDATE tasks
-        SET title = ?,
-        description = ?,
-        status = ?,
-        scheduled_start_date = ?,
-        scheduled_complete_date = ?,
-        estimated_duration = ?
-        WHERE tasks.id = ?
-        "#,
-        task.title,
-        task.description,
-        task.status,
-        task.scheduled_start_date,
-        task.scheduled_complete_date,
-        task.estimated_duration,
-        task.id
+        INSERT INTO task_links (task_id, linked_task_id, link_type, created)
+        VALUES (?, ?, ?, ?)
+    "#,
+        task_id,
+        linked_task_id,
+        link_type,
+        created
     )
-    .execute(&mut **transaction)
+    .execute(&mut *transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    // The link is only stored on the task it was made on, so the other task is told directly.
+    let event = TaskEventDetails::LinkAdded {
+        link_type,
+        linked_task_id: task_id,
+        outgoing: false,
+    };
+    record_task_event(
+        linked_task_id,
+        &event,
+        "Link tasks",
+        created,
+        &mut *transaction,
+    )
+    .await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
-/// Find a task by its id.
-async fn find_task(task_id: i64, db: &State<'_, Data>) -> TAResult<Option<Task>> {
-    sqlx::query_as!(Task, "SELECT * FROM tasks WHERE id = ?", task_id)
-        .fetch_optional(&db.pool)
+#[tauri::command]
+pub async fn unlink_tasks(link_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let link = sqlx::query_as!(
+        TaskLink,
+        "SELECT * FROM task_links WHERE task_links.id = ?",
+        link_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let Some(link) = link else {
+        anyhow_tauri::bail!(format!("Task link with id '{}' not found.", link_id));
+    };
+
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording = JournalRecording::start(
+        "Unlink tasks",
+        vec![link.task_id, link.linked_task_id],
+        &mut transaction,
+    )
+    .await?;
+
+    sqlx::query!("DELETE FROM task_links WHERE task_links.id = ?", link_id)
+        .execute(&mut *transaction)
         .await
-        .into_ta_result()
+        .into_ta_result()?;
+
+    let event = TaskEventDetails::LinkRemoved {
+        link_type: link.link_type,
+        linked_task_id: link.task_id,
+        outgoing: false,
+    };
+    let created = UnixTimestamp::now();
+    record_task_event(
+        link.linked_task_id,
+        &event,
+        "Unlink tasks",
+        created,
+        &mut *transaction,
+    )
+    .await?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
-/// Update the status when transitioning to an active state.
-async fn set_task_model_active(
-    mut task: Task,
-    status: Status,
-    db: &State<'_, Data>,
-) -> TAResult<()> {
-    task.status = status;
+/// Create a task which follows up on another one, linked back to it.
+///
+/// The follow-up copies the tags, priority and project of the original task.
+///
+/// ### Args
+/// * title - The title of the follow-up, `Follow up: ` and the original title when not given.
+///
+/// ### Returns
+/// The id of the follow-up task.
+#[tauri::command]
+pub async fn create_follow_up_task(
+    task_id: i64,
+    title: Option<String>,
+    db: State<'_, Data>,
+) -> TAResult<i64> {
+    let Some(task) = find_task(task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(task_id));
+    };
+
+    let title = title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| format!("Follow up: {}", task.title));
 
     let mut transaction = db.pool.begin().await.into_ta_result()?;
-    let result = async {
 
-        sqlx::query!("DELETE FROM task_work_history WHERE end_date IS NULL")
+    let result: TAResult<i64> = async {
+        let mut recording =
+            JournalRecording::start("Create follow-up task", vec![], &mut transaction).await?;
+
+        let status = Status::Todo;
+        let follow_up_task_id = sqlx::query!(
+            r#"
+            INSERT INTO tasks (title, description, status, priority, project_id)
+            VALUES (?, '', ?, ?, ?)
+        "#,
+            title,
+            status,
+            task.priority,
+            task.project_id
+        )
         .execute(&mut *transaction)
         .await
-        .map(|_|())
-        .into_ta_result()?;
-    
-        let now = OptionalUnixTimestamp::now();
-        let duration = OptionalDurationInSeconds::none();
-        
+        .into_ta_result()?
+        .last_insert_rowid();
+        recording.created(follow_up_task_id);
+
         sqlx::query!(
             r#"
-            INSERT INTO task_work_history (task_id, start_date, end_date) 
-            VALUES (?, ?, ?);
-            "#,
-            task.id,
-            now,
-            duration,
+            INSERT INTO task_tags (task_id, tag_id)
+            SELECT ?, task_tags.tag_id
+            FROM task_tags
+            WHERE task_tags.task_id = ?
+        "#,
+            follow_up_task_id,
+            task.id
         )
         .execute(&mut *transaction)
         .await
-        .map(|_|())
         .into_ta_result()?;
 
-        save_task(task, &mut transaction).await
-    }.await;
-
-    match result {
-        Ok(()) => transaction.commit().await.into_ta_result(),
-        Err(e) => {
-            transaction.rollback().await.into_ta_result()?;
-            Err(e)
-        }
-    }
-}
-
-/// Update the status when transition to a paused or finished state.
-async fn set_task_model_inactive(
-    mut task: Task,
-    status: Status,
-    db: &State<'_, Data>,
-) -> TAResult<()> {
-    task.status = status;
-
-    let mut transaction = db.pool.begin().await.into_ta_result()?;
-    let result = async {
-        let existing = sqlx::query_as!(
-            TaskWorkHistory, 
+        let link_type = LinkType::FollowUpOf;
+        let created = UnixTimestamp::now();
+        sqlx::query!(
             r#"
-            SELECT * from task_work_history twh
-            WHERE twh.task_id = ?
-            AND twh.end_date IS NULL
-            LIMIT 1
-            "#,
-            task.id
+            INSERT INTO task_links (task_id, linked_task_id, link_type, created)
+            VALUES (?, ?, ?, ?)
+        "#,
+            follow_up_task_id,
+            task.id,
+            link_type,
+            created
         )
-        .fetch_optional(&mut *transaction)
+        .execute(&mut *transaction)
         .await
         .into_ta_result()?;
-    
-    
-        if let Some(existing) = existing {
-            let now = OptionalUnixTimestamp::now();
-            
-            sqlx::query!(
-                r#"
-                UPDATE task_work_history 
-                SET end_date = ?
-                WHERE id = ?
-                "#,
-                now,
-                existing.id,
-            )
-            .execute(&mut *transaction)
-            .await
-            .map(|_| ())
-            .into_ta_result()?;
-        }
 
-        save_task(task, &mut transaction).await
-    }.await;
+        let event = TaskEventDetails::LinkAdded {
+            link_type,
+            linked_task_id: follow_up_task_id,
+            outgoing: false,
+        };
+        let source = "Create follow-up task";
+        record_task_event(task.id, &event, source, created, &mut *transaction).await?;
+
+        recording.finish(&mut transaction).await?;
+        Ok(follow_up_task_id)
+    }
+    .await;
 
     match result {
-        Ok(()) => transaction.commit().await.into_ta_result(),
+        Ok(follow_up_task_id) => {
+            transaction.commit().await.into_ta_result()?;
+            Ok(follow_up_task_id)
+        }
         Err(e) => {
             transaction.rollback().await.into_ta_result()?;
             Err(e)
@@ -665,64 +2969,162 @@ async fn set_task_model_inactive(
     }
 }
 
+/// Make a task recur, replacing any rule it already has.
+///
+/// The series is anchored on the current scheduled dates of the task, later occurrences are
+/// counted from there.
 #[tauri::command]
-pub async fn add_task_work_history(
-    new_task_work_history: NewTaskWorkHistory,
-    db: State<'_, Data>,
-) -> TAResult<()> {
-    let start_date: UnixTimestamp = UnixTimestamp::from(&new_task_work_history.start_date);
-    let end_date: OptionalUnixTimestamp = OptionalUnixTimestamp::some(new_task_work_history.end_date);
+pub async fn set_task_recurrence(recurrence: SetTaskRecurrence, db: State<'_, Data>) -> TAResult<()> {
+    let Some(task) = find_task(recurrence.task_id, &db).await? else {
+        anyhow_tauri::bail!(not_found_message(recurrence.task_id));
+    };
+
+    recurrence.validate().into_ta_result()?;
+
+    let weekdays = recurrence.weekday_mask();
+    let end_date: OptionalUnixTimestamp = recurrence.end_date.into();
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Set recurrence", vec![recurrence.task_id], &mut transaction)
+            .await?;
+
     sqlx::query!(
         r#"
-            INSERT INTO task_work_history (task_id, start_date, end_date)
-            VALUES (?, ?, ?)
-        "#,
-        new_task_work_history.task_id,
-        start_date,
-        end_date
+        INSERT INTO task_recurrences (task_id, frequency, interval, weekdays, end_after_occurrences, end_date, anchor_start_date, anchor_complete_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(task_id) DO UPDATE
+        SET frequency = excluded.frequency,
+        interval = excluded.interval,
+        weekdays = excluded.weekdays,
+        end_after_occurrences = excluded.end_after_occurrences,
+        end_date = excluded.end_date,
+        anchor_start_date = excluded.anchor_start_date,
+        anchor_complete_date = excluded.anchor_complete_date,
+        anchor_occurrence = task_recurrences.occurrence
+    "#,
+        recurrence.task_id,
+        recurrence.frequency,
+        recurrence.interval,
+        weekdays,
+        recurrence.end_after_occurrences,
+        end_date,
+        task.scheduled_start_date,
+        task.scheduled_complete_date
     )
-    .execute(&db.pool)
+    .execute(&mut *transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
 #[tauri::command]
-pub async fn delete_task_work_history(history_id: i64, db: State<'_, Data>) -> TAResult<()> {
+pub async fn remove_task_recurrence(task_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+    let recording =
+        JournalRecording::start("Remove recurrence", vec![task_id], &mut transaction).await?;
+
     sqlx::query!(
-        r#"
-            DELETE FROM task_work_history
-            WHERE task_work_history.id = ?
-        "#,
-        history_id
+        "DELETE FROM task_recurrences WHERE task_recurrences.task_id = ?",
+        task_id
     )
-    .execute(&db.pool)
+    .execute(&mut *transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    recording.finish(&mut transaction).await?;
+    transaction.commit().await.into_ta_result()
 }
 
-#[tauri::command]
-pub async fn edit_task_work_history(
-    edit_task_work_history: EditTaskWorkHistory,
-    db: State<'_, Data>,
-) -> TAResult<()> {
-    let start_date: UnixTimestamp = UnixTimestamp::from(&edit_task_work_history.start_date);
-    let end_date: OptionalUnixTimestamp = OptionalUnixTimestamp::some(edit_task_work_history.end_date);
+/// Create the next occurrence of a recurring task which has just been finished.
+///
+/// The new task copies the description, estimate and tags of the finished one, and the
+/// recurrence rule moves onto it so that the series continues from there.
+///
+/// ### Returns
+/// The id of the next occurrence, or nothing when the series has ended.
+async fn schedule_next_occurrence(
+    task: &Task,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<Option<i64>> {
+    let recurrence = sqlx::query_as!(
+        TaskRecurrence,
+        "SELECT * FROM task_recurrences WHERE task_recurrences.task_id = ?",
+        task.id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let Some(recurrence) = recurrence else {
+        return Ok(None);
+    };
+
+    let next = recurrence
+        .next_occurrence(
+            task.scheduled_start_date.into(),
+            task.scheduled_complete_date.into(),
+            &TimeZone::system(),
+        )
+        .into_ta_result()?;
+
+    let Some(next) = next else {
+        return Ok(None);
+    };
+
+    let scheduled_start_date: OptionalUnixTimestamp = next.scheduled_start_date.into();
+    let scheduled_complete_date: OptionalUnixTimestamp = next.scheduled_complete_date.into();
+    let status = Status::Todo;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO tasks (title, description, status, scheduled_start_date, scheduled_complete_date, estimated_duration, parent_task_id, priority, project_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#,
+        task.title,
+        task.description,
+        status,
+        scheduled_start_date,
+        scheduled_complete_date,
+        task.estimated_duration,
+        task.parent_task_id,
+        task.priority,
+        task.project_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let next_task_id = result.last_insert_rowid();
 
     sqlx::query!(
         r#"
-        UPDATE task_work_history
-        SET start_date = ?,
-        end_date = ?
-        WHERE task_work_history.id = ?
+        INSERT INTO task_tags (task_id, tag_id)
+        SELECT ?, task_tags.tag_id
+        FROM task_tags
+        WHERE task_tags.task_id = ?
     "#,
-        start_date,
-        end_date,
-        edit_task_work_history.id
+        next_task_id,
+        task.id
     )
-    .execute(&db.pool)
+    .execute(&mut **transaction)
     .await
-    .map(|_| ())
-    .into_ta_result()
+    .into_ta_result()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE task_recurrences
+        SET task_id = ?,
+        occurrence = occurrence + 1
+        WHERE id = ?
+    "#,
+        next_task_id,
+        recurrence.id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    Ok(Some(next_task_id))
 }
@@ -1,5 +1,7 @@
 pub mod commands;
 pub mod models;
+pub mod search_query;
 
 pub use commands::*;
-pub use models::*;
\ No newline at end of file
+pub use models::*;
+pub use search_query::*;
\ No newline at end of file
@@ -0,0 +1,71 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use super::OptionalUnixTimestamp;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct ChecklistItem {
+    pub id: i64,
+    pub task_id: i64,
+    pub title: String,
+    pub checked: bool,
+    /// Where the item appears in the checklist, starting from 0.
+    pub position: i64,
+    pub estimated_duration: Option<i64>,
+    pub checked_at: OptionalUnixTimestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItemRead {
+    pub id: i64,
+    pub task_id: i64,
+    pub title: String,
+    pub checked: bool,
+    pub position: i64,
+    pub estimated_duration: Option<i64>,
+    pub checked_at: Option<Timestamp>,
+}
+
+impl From<ChecklistItem> for ChecklistItemRead {
+    fn from(value: ChecklistItem) -> Self {
+        Self {
+            id: value.id,
+            task_id: value.task_id,
+            title: value.title,
+            checked: value.checked,
+            position: value.position,
+            estimated_duration: value.estimated_duration,
+            checked_at: value.checked_at.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateChecklistItem {
+    pub task_id: i64,
+    pub title: String,
+    pub estimated_duration: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditChecklistItem {
+    pub id: i64,
+    pub title: String,
+    pub estimated_duration: Option<i64>,
+}
+
+/// The share of the checklist which is checked, as a whole percentage.
+///
+/// Nothing when the task has no checklist, so that it is not shown as 0% done.
+pub fn checklist_completion(items: &[ChecklistItem]) -> Option<i64> {
+    if items.is_empty() {
+        return None;
+    }
+
+    let checked = items.iter().filter(|item| item.checked).count() as i64;
+    Some(checked * 100 / items.len() as i64)
+}
@@ -1,15 +1,18 @@
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
 
 use super::{OptionalUnixTimestamp, UnixTimestamp};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct Comment {
     pub id: i64,
     pub task_id: i64,
     pub message: String,
     pub created: UnixTimestamp,
     pub modified: OptionalUnixTimestamp,
+    /// When the comment was moved to the trash.
+    pub deleted_at: OptionalUnixTimestamp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,7 +1,9 @@
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 
-use crate::{FilterOption, Ordering};
+use crate::{
+    features::custom_fields::CustomFieldFilter, FieldKind, FilterOption, FilterableField, Ordering,
+};
 
 use super::Status;
 
@@ -16,22 +18,47 @@ pub struct TaskSearchParams {
     pub start_by_filter: Option<DateFilter>,
     pub due_by_filter: Option<DateFilter>,
     pub ordering: Ordering,
+    /// When set, only top level tasks are matched and their subtasks are nested beneath them.
+    #[serde(default)]
+    pub as_tree: bool,
+    /// Only tasks with all of these tags are matched, on top of any quick filter.
+    #[serde(default)]
+    pub required_tags: Vec<String>,
+    /// Tasks with any of these tags are left out.
+    #[serde(default)]
+    pub excluded_tags: Vec<String>,
+    pub estimate_filter: Option<DurationFilter>,
+    /// Only tasks in one of these projects are matched.
+    #[serde(default)]
+    pub project_ids: Vec<i64>,
+    /// A search written in the task query language, which is compiled into the other filters.
+    pub search_query: Option<String>,
+    /// Conditions on custom fields, all of which must match.
+    #[serde(default)]
+    pub custom_field_filters: Vec<CustomFieldFilter>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DateFilter {
     pub start: Option<Timestamp>,
     pub end: Option<Timestamp>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An inclusive range of durations in seconds, either end may be left open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DurationFilter {
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TagFilter {
     pub tags: Vec<String>,
     pub tag_filter: FilterOption,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum QuickFilter {
     Untagged,
@@ -40,4 +67,44 @@ pub enum QuickFilter {
     Unplanned,
     Overdue,
     LateStart,
+    /// Open tasks past their due date which still have unchecked checklist items.
+    OverdueChecklist,
+    /// Tasks waiting on at least one dependency which is neither done nor cancelled.
+    Blocked,
+    /// Open tasks whose dependencies are all done.
+    Ready,
 }
+
+/// The task fields which advanced searches may filter and sort on.
+///
+/// Statuses are compared by their stored names, e.g. `Todo` rather than `To Do`.
+pub const TASK_FILTER_FIELDS: &[FilterableField] = &[
+    FilterableField::new("id", "tasks.id", FieldKind::Integer),
+    FilterableField::new("title", "tasks.title", FieldKind::Text),
+    FilterableField::new("description", "tasks.description", FieldKind::Text),
+    FilterableField::new("status", "tasks.status", FieldKind::Text),
+    FilterableField::new(
+        "scheduled_start_date",
+        "tasks.scheduled_start_date",
+        FieldKind::Timestamp,
+    ),
+    FilterableField::new(
+        "scheduled_complete_date",
+        "tasks.scheduled_complete_date",
+        FieldKind::Timestamp,
+    ),
+    FilterableField::new(
+        "estimated_duration",
+        "tasks.estimated_duration",
+        FieldKind::Integer,
+    ),
+    FilterableField::new("parent_task_id", "tasks.parent_task_id", FieldKind::Integer),
+    FilterableField::new("priority", "tasks.priority", FieldKind::Integer),
+    FilterableField::new("project_id", "tasks.project_id", FieldKind::Integer),
+    FilterableField::many(
+        "tag",
+        "tags.value",
+        FieldKind::Text,
+        "SELECT 1 FROM task_tags INNER JOIN tags ON tags.id = task_tags.tag_id WHERE tags.deleted_at IS NULL AND task_tags.task_id = tasks.id AND",
+    ),
+];
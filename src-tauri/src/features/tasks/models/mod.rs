@@ -6,6 +6,11 @@ pub mod duration_in_seconds;
 pub mod task_work_history;
 pub mod task_tag;
 pub mod filters;
+pub mod recurrence;
+pub mod priority;
+pub mod ranking;
+pub mod checklist;
+pub mod task_link;
 
 pub use status::*;
 pub use unix_timestamp::*;
@@ -15,3 +20,8 @@ pub use duration_in_seconds::*;
 pub use task_work_history::*;
 pub use task_tag::*;
 pub use filters::*;
+pub use recurrence::*;
+pub use priority::*;
+pub use ranking::*;
+pub use checklist::*;
+pub use task_link::*;
@@ -0,0 +1,55 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// How important a task is, from `P0` (critical) to `P4` (whenever there is time).
+///
+/// Stored as its number so that sorting by priority puts the most important tasks first.
+#[derive(
+    Default, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize, sqlx::Type,
+)]
+#[repr(i64)]
+pub enum Priority {
+    P0 = 0,
+    P1 = 1,
+    #[default]
+    P2 = 2,
+    P3 = 3,
+    P4 = 4,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Priority::P0 => "P0",
+            Priority::P1 => "P1",
+            Priority::P2 => "P2",
+            Priority::P3 => "P3",
+            Priority::P4 => "P4",
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<i64> for Priority {
+    fn from(value: i64) -> Self {
+        match value {
+            i64::MIN..=0 => Priority::P0,
+            1 => Priority::P1,
+            2 => Priority::P2,
+            3 => Priority::P3,
+            _ => Priority::P4,
+        }
+    }
+}
+
+impl From<Priority> for i64 {
+    fn from(value: Priority) -> Self {
+        value as i64
+    }
+}
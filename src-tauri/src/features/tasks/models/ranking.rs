@@ -0,0 +1,130 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use super::{Priority, Status, TaskRead};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Slack beyond this many days no longer adds urgency.
+const SLACK_HORIZON_DAYS: i64 = 14;
+
+/// One part of the score of a ranked task, and why it was given.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingReason {
+    pub points: i64,
+    pub explanation: String,
+}
+
+/// A task suggested to work on next, with the reasons it was ranked where it is.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedTask {
+    pub task: TaskRead,
+    pub score: i64,
+    pub reasons: Vec<RankingReason>,
+}
+
+impl RankedTask {
+    /// Score an open task at the given time.
+    ///
+    /// | Reason                                  | Points                                   |
+    /// |-----------------------------------------|------------------------------------------|
+    /// | Priority                                | 40 for P0 down to 0 for P4               |
+    /// | Past its due date                       | 30, plus 1 per day overdue up to 20 more |
+    /// | Remaining estimate does not fit         | 25                                       |
+    /// | Little slack before the due date        | Up to 25, less as slack nears 14 days    |
+    /// | Should have been started already        | 10                                       |
+    /// | Already in progress                     | 5                                        |
+    pub fn score(task: TaskRead, now: Timestamp) -> Self {
+        let mut reasons = vec![priority_reason(task.priority)];
+
+        if let Some(due) = task.scheduled_complete_date {
+            let until_due = due.as_second() - now.as_second();
+
+            if until_due < 0 {
+                let days_overdue = -until_due / SECONDS_PER_DAY;
+                reasons.push(RankingReason {
+                    points: 30 + days_overdue.min(20),
+                    explanation: format!("Overdue by {}.", format_duration(-until_due)),
+                });
+            } else {
+                let remaining =
+                    (task.estimated_duration.unwrap_or(0) - task.elapsed_duration).max(0);
+                let slack = until_due - remaining;
+
+                if slack < 0 {
+                    reasons.push(RankingReason {
+                        points: 25,
+                        explanation: format!(
+                            "Due in {} but about {} of estimated work is left.",
+                            format_duration(until_due),
+                            format_duration(remaining)
+                        ),
+                    });
+                } else {
+                    let slack_days = slack / SECONDS_PER_DAY;
+                    let points = 25 * (SLACK_HORIZON_DAYS - slack_days.min(SLACK_HORIZON_DAYS))
+                        / SLACK_HORIZON_DAYS;
+
+                    if points > 0 {
+                        reasons.push(RankingReason {
+                            points,
+                            explanation: format!(
+                                "Only {} of slack before it is due.",
+                                format_duration(slack)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(start) = task.scheduled_start_date {
+            if task.status == Status::Todo && start < now {
+                reasons.push(RankingReason {
+                    points: 10,
+                    explanation: format!(
+                        "Was scheduled to start {} ago.",
+                        format_duration(now.as_second() - start.as_second())
+                    ),
+                });
+            }
+        }
+
+        if task.status == Status::Doing || task.status == Status::Paused {
+            reasons.push(RankingReason {
+                points: 5,
+                explanation: "Already in progress.".to_string(),
+            });
+        }
+
+        Self {
+            score: reasons.iter().map(|reason| reason.points).sum(),
+            task,
+            reasons,
+        }
+    }
+}
+
+fn priority_reason(priority: Priority) -> RankingReason {
+    let points = 10 * (4 - i64::from(priority));
+
+    RankingReason {
+        points,
+        explanation: format!("Priority {}.", priority),
+    }
+}
+
+/// Format a number of seconds as the two largest of days, hours and minutes, e.g. `2d 3h`.
+fn format_duration(seconds: i64) -> String {
+    let days = seconds / SECONDS_PER_DAY;
+    let hours = (seconds % SECONDS_PER_DAY) / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
@@ -0,0 +1,479 @@
+use jiff::{
+    civil::Weekday,
+    tz::TimeZone,
+    Span, Timestamp, Zoned,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use timely_macros::EnumFromString;
+
+use super::OptionalUnixTimestamp;
+
+/// How often a recurring task repeats.
+#[derive(Default, Debug, PartialEq, Eq, Clone, Serialize, Deserialize, sqlx::Type, EnumFromString)]
+#[sqlx(type_name = "TEXT")]
+pub enum Frequency {
+    /// Repeats every `interval` days.
+    Daily,
+    /// Repeats every `interval` weeks, optionally on a set of weekdays.
+    #[default]
+    Weekly,
+    /// Repeats every `interval` months.
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, FromRow)]
+pub struct TaskRecurrence {
+    pub id: i64,
+    pub task_id: i64,
+    pub frequency: Frequency,
+    pub interval: i64,
+    /// A bit mask of the weekdays, where bit 0 is Monday and bit 6 is Sunday.
+    pub weekdays: i64,
+    pub end_after_occurrences: Option<i64>,
+    pub end_date: OptionalUnixTimestamp,
+    /// Which occurrence of the series this is, starting from 1.
+    pub occurrence: i64,
+    /// The scheduled start date of the occurrence the series is anchored on.
+    #[serde(default)]
+    pub anchor_start_date: OptionalUnixTimestamp,
+    /// The scheduled complete date of the occurrence the series is anchored on.
+    #[serde(default)]
+    pub anchor_complete_date: OptionalUnixTimestamp,
+    /// The occurrence the anchor dates belong to.
+    #[serde(default = "first_occurrence")]
+    pub anchor_occurrence: i64,
+}
+
+fn first_occurrence() -> i64 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRecurrenceRead {
+    pub frequency: Frequency,
+    pub interval: i64,
+    /// The weekdays the task repeats on, where 1 is Monday and 7 is Sunday.
+    pub weekdays: Vec<i8>,
+    pub end_after_occurrences: Option<i64>,
+    pub end_date: Option<Timestamp>,
+    pub occurrence: i64,
+}
+
+impl From<TaskRecurrence> for TaskRecurrenceRead {
+    fn from(value: TaskRecurrence) -> Self {
+        Self {
+            frequency: value.frequency,
+            interval: value.interval,
+            weekdays: mask_to_weekdays(value.weekdays),
+            end_after_occurrences: value.end_after_occurrences,
+            end_date: value.end_date.into(),
+            occurrence: value.occurrence,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetTaskRecurrence {
+    pub task_id: i64,
+    pub frequency: Frequency,
+    pub interval: i64,
+    /// The weekdays the task repeats on, where 1 is Monday and 7 is Sunday.
+    pub weekdays: Vec<i8>,
+    pub end_after_occurrences: Option<i64>,
+    pub end_date: Option<Timestamp>,
+}
+
+impl SetTaskRecurrence {
+    /// Check that the rule describes a schedule which can actually be generated.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=1000).contains(&self.interval) {
+            anyhow::bail!("The recurrence interval must be between 1 and 1000.");
+        }
+
+        if self.weekdays.iter().any(|day| !(1..=7).contains(day)) {
+            anyhow::bail!("Recurrence weekdays must be between 1 (Monday) and 7 (Sunday).");
+        }
+
+        if !self.weekdays.is_empty() && self.frequency != Frequency::Weekly {
+            anyhow::bail!("Recurrence weekdays can only be used with a weekly frequency.");
+        }
+
+        if let Some(end_after_occurrences) = self.end_after_occurrences {
+            if end_after_occurrences < 1 {
+                anyhow::bail!("A recurrence must end after at least one occurrence.");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn weekday_mask(&self) -> i64 {
+        weekdays_to_mask(&self.weekdays)
+    }
+}
+
+/// The scheduled dates of the next occurrence of a recurring task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub scheduled_start_date: Option<Timestamp>,
+    pub scheduled_complete_date: Option<Timestamp>,
+}
+
+impl TaskRecurrence {
+    /// Compute the next occurrence after the one scheduled with the given dates.
+    ///
+    /// The dates are shifted in the given time zone so that wall clock times are kept across
+    /// daylight saving changes. Returns `None` when the series has ended.
+    pub fn next_occurrence(
+        &self,
+        scheduled_start_date: Option<Timestamp>,
+        scheduled_complete_date: Option<Timestamp>,
+        time_zone: &TimeZone,
+    ) -> anyhow::Result<Option<Occurrence>> {
+        if let Some(end_after_occurrences) = self.end_after_occurrences {
+            if self.occurrence >= end_after_occurrences {
+                return Ok(None);
+            }
+        }
+
+        let (anchor, series_anchor) = match (scheduled_start_date, scheduled_complete_date) {
+            (Some(start), _) => (start, self.anchor_start_date.into()),
+            (None, Some(complete)) => (complete, self.anchor_complete_date.into()),
+            (None, None) => (Timestamp::now(), None),
+        };
+
+        // Both dates move by the same number of days to reach the next weekday, so that the
+        // time between them is kept.
+        let weekday_shift = self.weekday_shift(&anchor.to_zoned(time_zone.clone()))?;
+
+        let next_date = |date: Timestamp, series_anchor: Option<Timestamp>| {
+            let date = date.to_zoned(time_zone.clone());
+            let next = match weekday_shift {
+                Some(shift) => date.checked_add(shift)?,
+                None => self.next_fixed_date(&date, series_anchor)?,
+            };
+
+            anyhow::Ok(next.timestamp())
+        };
+
+        let next_anchor = next_date(anchor, series_anchor)?;
+
+        let end_date: Option<Timestamp> = self.end_date.into();
+        if let Some(end_date) = end_date {
+            if next_anchor > end_date {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(Occurrence {
+            scheduled_start_date: scheduled_start_date
+                .map(|date| next_date(date, self.anchor_start_date.into()))
+                .transpose()?,
+            scheduled_complete_date: scheduled_complete_date
+                .map(|date| next_date(date, self.anchor_complete_date.into()))
+                .transpose()?,
+        }))
+    }
+
+    /// The span covering the given number of intervals of a rule without weekdays.
+    fn fixed_step(&self, count: i64) -> Span {
+        let intervals = self.interval.max(1) * count;
+
+        match self.frequency {
+            Frequency::Daily => Span::new().days(intervals),
+            Frequency::Weekly => Span::new().weeks(intervals),
+            Frequency::Monthly => Span::new().months(intervals),
+        }
+    }
+
+    /// The date of the next occurrence after one on the given date, for a rule without weekdays.
+    ///
+    /// The next date is counted from the anchor of the series while the given date is still
+    /// where the series put it, so that a monthly task on the 31st goes back to the 31st after
+    /// a shorter month. A date which was moved by hand starts counting from itself instead.
+    fn next_fixed_date(
+        &self,
+        date: &Zoned,
+        series_anchor: Option<Timestamp>,
+    ) -> anyhow::Result<Zoned> {
+        if let Some(series_anchor) = series_anchor {
+            let series_anchor = series_anchor.to_zoned(date.time_zone().clone());
+            let elapsed = (self.occurrence - self.anchor_occurrence).max(0);
+
+            let expected = series_anchor.checked_add(self.fixed_step(elapsed))?;
+            if expected.timestamp() == date.timestamp() {
+                return Ok(series_anchor.checked_add(self.fixed_step(elapsed + 1))?);
+            }
+        }
+
+        Ok(date.checked_add(self.fixed_step(1))?)
+    }
+
+    /// How many days the occurrence anchored at the given time moves to land on the next of the
+    /// chosen weekdays, nothing when the rule has no weekdays.
+    fn weekday_shift(&self, anchor: &Zoned) -> anyhow::Result<Option<Span>> {
+        let interval = self.interval.max(1);
+        let weekdays = mask_to_weekdays(self.weekdays);
+
+        if self.frequency != Frequency::Weekly || weekdays.is_empty() {
+            return Ok(None);
+        }
+
+        let mut date = anchor.date();
+        let mut days: i64 = 0;
+
+        loop {
+            date = date.tomorrow()?;
+            days += 1;
+
+            // Crossing into a new week skips the weeks which are not part of the interval.
+            if date.weekday() == Weekday::Monday && interval > 1 {
+                date = date.checked_add(Span::new().weeks(interval - 1))?;
+                days += 7 * (interval - 1);
+            }
+
+            if weekdays.contains(&date.weekday().to_monday_one_offset()) {
+                return Ok(Some(Span::new().days(days)));
+            }
+        }
+    }
+}
+
+fn weekdays_to_mask(weekdays: &[i8]) -> i64 {
+    weekdays
+        .iter()
+        .fold(0, |mask, day| mask | (1 << (day - 1)))
+}
+
+fn mask_to_weekdays(mask: i64) -> Vec<i8> {
+    (1..=7)
+        .filter(|day| mask & (1 << (day - 1)) != 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoned(date: &str) -> Zoned {
+        date.parse().unwrap()
+    }
+
+    fn recurrence(frequency: Frequency, anchor: &Zoned) -> TaskRecurrence {
+        TaskRecurrence {
+            id: 1,
+            task_id: 1,
+            frequency,
+            interval: 1,
+            weekdays: 0,
+            end_after_occurrences: None,
+            end_date: OptionalUnixTimestamp::none(),
+            occurrence: 1,
+            anchor_start_date: Some(anchor.timestamp()).into(),
+            anchor_complete_date: OptionalUnixTimestamp::none(),
+            anchor_occurrence: 1,
+        }
+    }
+
+    /// Follow the series from its anchor, returning the start dates of the next occurrences.
+    fn follow(recurrence: &mut TaskRecurrence, anchor: &Zoned, count: usize) -> Vec<Zoned> {
+        let time_zone = anchor.time_zone().clone();
+        let mut date = anchor.timestamp();
+        let mut dates = Vec::new();
+
+        for _ in 0..count {
+            let next = recurrence
+                .next_occurrence(Some(date), None, &time_zone)
+                .unwrap()
+                .unwrap();
+
+            date = next.scheduled_start_date.unwrap();
+            dates.push(date.to_zoned(time_zone.clone()));
+            recurrence.occurrence += 1;
+        }
+
+        dates
+    }
+
+    #[test]
+    fn monthly_recurrence_returns_to_the_end_of_the_month() {
+        let anchor = zoned("2026-01-31T09:00[Europe/Amsterdam]");
+        let mut recurrence = recurrence(Frequency::Monthly, &anchor);
+
+        let dates = follow(&mut recurrence, &anchor, 4);
+
+        assert_eq!(
+            dates,
+            vec![
+                zoned("2026-02-28T09:00[Europe/Amsterdam]"),
+                zoned("2026-03-31T09:00[Europe/Amsterdam]"),
+                zoned("2026-04-30T09:00[Europe/Amsterdam]"),
+                zoned("2026-05-31T09:00[Europe/Amsterdam]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_recurrence_counts_from_where_it_was_anchored_again() {
+        let anchor = zoned("2026-03-31T09:00[Europe/Amsterdam]");
+        let mut recurrence = recurrence(Frequency::Monthly, &anchor);
+        recurrence.occurrence = 3;
+        recurrence.anchor_occurrence = 3;
+
+        let dates = follow(&mut recurrence, &anchor, 2);
+
+        assert_eq!(
+            dates,
+            vec![
+                zoned("2026-04-30T09:00[Europe/Amsterdam]"),
+                zoned("2026-05-31T09:00[Europe/Amsterdam]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recurrence_counts_from_a_date_moved_by_hand() {
+        let anchor = zoned("2026-01-31T09:00[Europe/Amsterdam]");
+        let mut recurrence = recurrence(Frequency::Monthly, &anchor);
+        recurrence.occurrence = 2;
+
+        let moved = zoned("2026-02-20T09:00[Europe/Amsterdam]");
+        let next = recurrence
+            .next_occurrence(Some(moved.timestamp()), None, moved.time_zone())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            next.scheduled_start_date,
+            Some(zoned("2026-03-20T09:00[Europe/Amsterdam]").timestamp())
+        );
+    }
+
+    #[test]
+    fn recurrence_without_an_anchor_shifts_from_the_date() {
+        let date = zoned("2026-01-31T09:00[Europe/Amsterdam]");
+        let mut recurrence = recurrence(Frequency::Monthly, &date);
+        recurrence.anchor_start_date = OptionalUnixTimestamp::none();
+
+        let next = recurrence
+            .next_occurrence(Some(date.timestamp()), None, date.time_zone())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            next.scheduled_start_date,
+            Some(zoned("2026-02-28T09:00[Europe/Amsterdam]").timestamp())
+        );
+    }
+
+    #[test]
+    fn recurrence_keeps_the_wall_clock_time_across_daylight_saving_changes() {
+        let cases = [
+            (
+                Frequency::Daily,
+                "2026-03-07T09:00[America/New_York]",
+                "2026-03-08T09:00[America/New_York]",
+            ),
+            (
+                Frequency::Weekly,
+                "2026-03-02T09:00[America/New_York]",
+                "2026-03-09T09:00[America/New_York]",
+            ),
+            (
+                Frequency::Monthly,
+                "2026-10-15T09:00[America/New_York]",
+                "2026-11-15T09:00[America/New_York]",
+            ),
+        ];
+
+        for (frequency, date, expected) in cases {
+            let date = zoned(date);
+            let mut recurrence = recurrence(frequency.clone(), &date);
+
+            assert_eq!(
+                follow(&mut recurrence, &date, 1),
+                vec![zoned(expected)],
+                "{:?}",
+                frequency
+            );
+        }
+    }
+
+    #[test]
+    fn weekday_recurrence_moves_both_dates_by_the_same_days() {
+        // Friday, the weekend before daylight saving time starts.
+        let start = zoned("2026-03-06T09:00[America/New_York]");
+        let complete = zoned("2026-03-06T17:00[America/New_York]");
+        let mut recurrence = recurrence(Frequency::Weekly, &start);
+        recurrence.weekdays = weekdays_to_mask(&[1, 3]);
+
+        let next = recurrence
+            .next_occurrence(
+                Some(start.timestamp()),
+                Some(complete.timestamp()),
+                start.time_zone(),
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            next,
+            Occurrence {
+                scheduled_start_date: Some(zoned("2026-03-09T09:00[America/New_York]").timestamp()),
+                scheduled_complete_date: Some(
+                    zoned("2026-03-09T17:00[America/New_York]").timestamp()
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn weekday_recurrence_skips_weeks_outside_the_interval() {
+        // Wednesday.
+        let date = zoned("2026-03-04T09:00[Europe/Amsterdam]");
+        let mut recurrence = recurrence(Frequency::Weekly, &date);
+        recurrence.interval = 2;
+        recurrence.weekdays = weekdays_to_mask(&[1, 5]);
+
+        assert_eq!(
+            follow(&mut recurrence, &date, 3),
+            vec![
+                zoned("2026-03-06T09:00[Europe/Amsterdam]"),
+                zoned("2026-03-16T09:00[Europe/Amsterdam]"),
+                zoned("2026-03-20T09:00[Europe/Amsterdam]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recurrence_ends_after_its_last_occurrence_or_end_date() {
+        let date = zoned("2026-01-31T09:00[Europe/Amsterdam]");
+
+        let mut ended_by_count = recurrence(Frequency::Daily, &date);
+        ended_by_count.end_after_occurrences = Some(2);
+        ended_by_count.occurrence = 2;
+
+        let mut ended_by_date = recurrence(Frequency::Daily, &date);
+        ended_by_date.end_date =
+            Some(zoned("2026-02-01T08:00[Europe/Amsterdam]").timestamp()).into();
+
+        for recurrence in [ended_by_count, ended_by_date] {
+            let next = recurrence
+                .next_occurrence(Some(date.timestamp()), None, date.time_zone())
+                .unwrap();
+
+            assert_eq!(next, None, "{:?}", recurrence);
+        }
+    }
+
+    #[test]
+    fn weekdays_round_trip_through_the_mask() {
+        let weekdays = vec![1, 3, 7];
+
+        assert_eq!(weekdays_to_mask(&weekdays), 0b1000101);
+        assert_eq!(mask_to_weekdays(weekdays_to_mask(&weekdays)), weekdays);
+    }
+}
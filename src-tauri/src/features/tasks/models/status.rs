@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 use timely_macros::EnumFromString;
 
@@ -18,20 +20,86 @@ pub enum Status {
     Todo,
 }
 
-impl Serialize for Status {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let value = match *self {
+impl Status {
+    /// The name of the status as it is shown to the user.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
             Status::Todo => "To Do",
             Status::Doing => "Doing",
             Status::Done => "Done",
             Status::Cancelled => "Cancelled",
             Status::Paused => "Paused",
-        };
+        }
+    }
+
+    /// Check whether a task in this status is allowed to move to the next status.
+    ///
+    /// | From      | To                      |
+    /// |-----------|-------------------------|
+    /// | To Do     | Doing, Cancelled        |
+    /// | Doing     | Paused, Done, Cancelled |
+    /// | Paused    | Doing, Done, Cancelled  |
+    /// | Done      | Doing                   |
+    /// | Cancelled | To Do                   |
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        matches!(
+            (self, next),
+            (Status::Todo, Status::Doing)
+                | (Status::Todo, Status::Cancelled)
+                | (Status::Doing, Status::Paused)
+                | (Status::Doing, Status::Done)
+                | (Status::Doing, Status::Cancelled)
+                | (Status::Paused, Status::Doing)
+                | (Status::Paused, Status::Done)
+                | (Status::Paused, Status::Cancelled)
+                | (Status::Done, Status::Doing)
+                | (Status::Cancelled, Status::Todo)
+        )
+    }
 
-        serializer.serialize_str(value)
+    /// Move to the next status, failing when the transition table does not allow it.
+    pub fn transition_to(&self, next: Status) -> Result<Status, StatusTransitionError> {
+        match self.can_transition_to(&next) {
+            true => Ok(next),
+            false => Err(StatusTransitionError {
+                from: self.clone(),
+                to: next,
+            }),
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A task was asked to move between two statuses which the transition table does not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusTransitionError {
+    pub from: Status,
+    pub to: Status,
+}
+
+impl Display for StatusTransitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "A task cannot move from '{}' to '{}'.",
+            self.from, self.to
+        )
+    }
+}
+
+impl std::error::Error for StatusTransitionError {}
+
+impl Serialize for Status {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -54,4 +122,59 @@ impl<'de> Deserialize<'de> for Status {
             )),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUSES: [Status; 5] = [
+        Status::Todo,
+        Status::Doing,
+        Status::Paused,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    #[test]
+    fn can_transition_to_follows_the_transition_table() {
+        let allowed = [
+            (Status::Todo, Status::Doing),
+            (Status::Todo, Status::Cancelled),
+            (Status::Doing, Status::Paused),
+            (Status::Doing, Status::Done),
+            (Status::Doing, Status::Cancelled),
+            (Status::Paused, Status::Doing),
+            (Status::Paused, Status::Done),
+            (Status::Paused, Status::Cancelled),
+            (Status::Done, Status::Doing),
+            (Status::Cancelled, Status::Todo),
+        ];
+
+        for from in &STATUSES {
+            for to in &STATUSES {
+                let expected = allowed.contains(&(from.clone(), to.clone()));
+
+                assert_eq!(
+                    from.can_transition_to(to),
+                    expected,
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transition_to_reports_the_statuses_it_cannot_move_between() {
+        assert_eq!(Status::Todo.transition_to(Status::Doing), Ok(Status::Doing));
+        assert_eq!(
+            Status::Done.transition_to(Status::Cancelled),
+            Err(StatusTransitionError {
+                from: Status::Done,
+                to: Status::Cancelled,
+            })
+        );
+    }
+}
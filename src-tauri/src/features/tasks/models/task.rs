@@ -2,9 +2,15 @@ use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
-use crate::features::tags::Tag;
+use crate::features::{
+    custom_fields::{SetCustomFieldValue, TaskCustomFieldRead},
+    tags::Tag,
+};
 
-use super::{CommentRead, OptionalDurationInSeconds, OptionalUnixTimestamp, Status, TaskWorkHistoryRead};
+use super::{
+    ChecklistItemRead, CommentRead, OptionalDurationInSeconds, OptionalUnixTimestamp, Priority,
+    Status, TaskLinkRead, TaskRecurrenceRead, TaskWorkHistoryRead,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, FromRow)]
 pub struct Task {
@@ -15,6 +21,11 @@ pub struct Task {
     pub scheduled_start_date: OptionalUnixTimestamp,
     pub scheduled_complete_date: OptionalUnixTimestamp,
     pub estimated_duration: OptionalDurationInSeconds,
+    pub parent_task_id: Option<i64>,
+    pub priority: Priority,
+    pub project_id: Option<i64>,
+    /// When the task was moved to the trash.
+    pub deleted_at: OptionalUnixTimestamp,
 }
 
 /// Model for the database which requires NaiveDateTime
@@ -27,6 +38,9 @@ pub struct NewTask {
     pub scheduled_start_date: OptionalUnixTimestamp,
     pub scheduled_complete_date: OptionalUnixTimestamp,
     pub estimated_duration: Option<i64>,
+    pub parent_task_id: Option<i64>,
+    pub priority: Priority,
+    pub project_id: Option<i64>,
 }
 
 /// Required to serialize the datetime as Local
@@ -40,6 +54,13 @@ pub struct CreateTask {
     pub scheduled_complete_date: Option<Timestamp>,
     pub estimated_duration: Option<i64>,
     pub tags: Option<Vec<Tag>>,
+    pub parent_task_id: Option<i64>,
+    #[serde(default)]
+    pub priority: Priority,
+    pub project_id: Option<i64>,
+    /// The values of custom fields, checked against the type of each field.
+    #[serde(default)]
+    pub custom_fields: Vec<SetCustomFieldValue>,
 }
 
 impl From<CreateTask> for NewTask {
@@ -51,6 +72,9 @@ impl From<CreateTask> for NewTask {
             scheduled_start_date: value.scheduled_start_date.into(),
             scheduled_complete_date: value.scheduled_complete_date.into(),
             estimated_duration: value.estimated_duration,
+            parent_task_id: value.parent_task_id,
+            priority: value.priority,
+            project_id: value.project_id,
         }
     }
 }
@@ -68,9 +92,109 @@ pub struct TaskRead {
     pub actual_complete_date: Option<Timestamp>,
     pub estimated_duration: Option<i64>,
     pub elapsed_duration: i64,
+    pub priority: Priority,
     pub comments: Vec<CommentRead>,
     pub tags: Vec<Tag>,
     pub work_history: Vec<TaskWorkHistoryRead>,
+    pub parent_task_id: Option<i64>,
+    pub project_id: Option<i64>,
+    /// The estimate of this task plus the estimates of all of its subtasks.
+    pub rolled_up_estimated_duration: Option<i64>,
+    /// The combined status of this task and all of its subtasks.
+    pub rolled_up_status: Status,
+    /// The subtasks of this task, only populated when searching as a tree.
+    pub children: Vec<TaskRead>,
+    pub recurrence: Option<TaskRecurrenceRead>,
+    /// The checklist of the task, in order.
+    pub checklist: Vec<ChecklistItemRead>,
+    /// The percentage of the checklist which is checked, nothing when there is no checklist.
+    pub checklist_completion: Option<i64>,
+    /// The links to other tasks, whichever task they were made on.
+    pub links: Vec<TaskLinkRead>,
+    /// The custom fields the task has a value for.
+    pub custom_fields: Vec<TaskCustomFieldRead>,
+    /// The best matching fragment of the task for the search text, with matches in `<mark>` tags.
+    pub search_snippet: Option<String>,
+}
+
+impl TaskRead {
+    /// Attach the subtasks to this task, rolling their time, estimates and status up into it.
+    ///
+    /// The children are expected to have already had their own subtasks rolled up.
+    pub fn with_children(mut self, children: Vec<TaskRead>) -> Self {
+        self.elapsed_duration += children
+            .iter()
+            .map(|child| child.elapsed_duration)
+            .sum::<i64>();
+
+        self.rolled_up_estimated_duration = children
+            .iter()
+            .map(|child| child.rolled_up_estimated_duration)
+            .fold(self.estimated_duration, |acc, estimate| {
+                match (acc, estimate) {
+                    (Some(acc), Some(estimate)) => Some(acc + estimate),
+                    (acc, None) => acc,
+                    (None, estimate) => estimate,
+                }
+            });
+
+        let statuses = children
+            .iter()
+            .map(|child| &child.rolled_up_status)
+            .chain(std::iter::once(&self.status));
+
+        self.rolled_up_status = roll_up_status(statuses);
+        self.children = children;
+        self
+    }
+}
+
+/// Combine the statuses of a task and its subtasks into a single status.
+///
+/// * Any work in progress makes the whole tree `Doing`.
+/// * A tree where everything is finished is `Done`, or `Cancelled` when nothing was completed.
+/// * A tree with some finished or paused work is `Paused`.
+/// * Otherwise nothing has been started and the tree is `Todo`.
+fn roll_up_status<'a>(statuses: impl Iterator<Item = &'a Status>) -> Status {
+    let statuses: Vec<&Status> = statuses.collect();
+
+    if statuses.iter().any(|status| **status == Status::Doing) {
+        return Status::Doing;
+    }
+
+    let all_closed = statuses
+        .iter()
+        .all(|status| **status == Status::Done || **status == Status::Cancelled);
+
+    if all_closed {
+        return match statuses.iter().any(|status| **status == Status::Done) {
+            true => Status::Done,
+            false => Status::Cancelled,
+        };
+    }
+
+    if statuses
+        .iter()
+        .any(|status| **status == Status::Paused || **status == Status::Done)
+    {
+        return Status::Paused;
+    }
+
+    Status::Todo
+}
+
+/// What to do with open subtasks when their parent task is finished.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenChildrenPolicy {
+    /// Refuse to finish the parent while any subtask is still open.
+    Block,
+    /// Finish every open subtask along with the parent, cancelling those never started.
+    Cascade,
+    /// Finish the parent and report the subtasks that are still open, as tasks were always
+    /// finished before they could have subtasks.
+    #[default]
+    Warn,
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -83,4 +207,118 @@ pub struct EditTask {
     pub scheduled_start_date: Option<Timestamp>,
     pub scheduled_complete_date: Option<Timestamp>,
     pub estimated_duration: Option<i64>,
-}
\ No newline at end of file
+    /// The priority is left unchanged when not given.
+    pub priority: Option<Priority>,
+    /// The custom fields to change, any others are left as they are. A `null` value clears one.
+    #[serde(default)]
+    pub custom_fields: Vec<SetCustomFieldValue>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_read(id: i64, status: Status, elapsed: i64, estimate: Option<i64>) -> TaskRead {
+        TaskRead {
+            id,
+            title: format!("Task {}", id),
+            description: String::new(),
+            status: status.clone(),
+            scheduled_start_date: None,
+            scheduled_complete_date: None,
+            actual_start_date: None,
+            actual_complete_date: None,
+            estimated_duration: estimate,
+            elapsed_duration: elapsed,
+            priority: Priority::default(),
+            comments: Vec::new(),
+            tags: Vec::new(),
+            work_history: Vec::new(),
+            parent_task_id: None,
+            project_id: None,
+            rolled_up_estimated_duration: estimate,
+            rolled_up_status: status,
+            children: Vec::new(),
+            recurrence: None,
+            checklist: Vec::new(),
+            checklist_completion: None,
+            links: Vec::new(),
+            custom_fields: Vec::new(),
+            search_snippet: None,
+        }
+    }
+
+    #[test]
+    fn with_children_adds_up_time_and_estimates() {
+        let child = task_read(2, Status::Done, 60, Some(100));
+        let grandchild = task_read(4, Status::Todo, 15, Some(50));
+        let other_child = task_read(3, Status::Paused, 30, None).with_children(vec![grandchild]);
+
+        let parent =
+            task_read(1, Status::Doing, 10, Some(200)).with_children(vec![child, other_child]);
+
+        assert_eq!(parent.elapsed_duration, 115);
+        assert_eq!(parent.rolled_up_estimated_duration, Some(350));
+        assert_eq!(parent.estimated_duration, Some(200));
+        assert_eq!(parent.children.len(), 2);
+    }
+
+    #[test]
+    fn with_children_keeps_no_estimate_when_nothing_is_estimated() {
+        let parent = task_read(1, Status::Todo, 0, None).with_children(vec![task_read(
+            2,
+            Status::Todo,
+            0,
+            None,
+        )]);
+
+        assert_eq!(parent.rolled_up_estimated_duration, None);
+    }
+
+    #[test]
+    fn with_children_uses_child_estimates_when_the_parent_has_none() {
+        let parent = task_read(1, Status::Todo, 0, None).with_children(vec![task_read(
+            2,
+            Status::Todo,
+            0,
+            Some(40),
+        )]);
+
+        assert_eq!(parent.rolled_up_estimated_duration, Some(40));
+    }
+
+    #[test]
+    fn roll_up_status_combines_statuses() {
+        let cases = [
+            (vec![Status::Todo, Status::Doing], Status::Doing),
+            (
+                vec![Status::Done, Status::Paused, Status::Doing],
+                Status::Doing,
+            ),
+            (vec![Status::Done, Status::Done], Status::Done),
+            (vec![Status::Done, Status::Cancelled], Status::Done),
+            (
+                vec![Status::Cancelled, Status::Cancelled],
+                Status::Cancelled,
+            ),
+            (vec![Status::Todo, Status::Done], Status::Paused),
+            (vec![Status::Todo, Status::Paused], Status::Paused),
+            (vec![Status::Todo, Status::Cancelled], Status::Todo),
+            (vec![Status::Todo], Status::Todo),
+        ];
+
+        for (statuses, expected) in cases {
+            assert_eq!(roll_up_status(statuses.iter()), expected, "{:?}", statuses);
+        }
+    }
+
+    #[test]
+    fn with_children_rolls_up_status_from_grandchildren() {
+        let grandchild = task_read(3, Status::Doing, 0, None);
+        let child = task_read(2, Status::Todo, 0, None).with_children(vec![grandchild]);
+        let parent = task_read(1, Status::Todo, 0, None).with_children(vec![child]);
+
+        assert_eq!(parent.status, Status::Todo);
+        assert_eq!(parent.rolled_up_status, Status::Doing);
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use timely_macros::EnumFromString;
+
+use super::{Status, UnixTimestamp};
+
+/// How one task is linked to another, read from the task the link was made on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, sqlx::Type, EnumFromString)]
+#[sqlx(type_name = "TEXT")]
+pub enum LinkType {
+    /// The task is a duplicate of the linked task.
+    Duplicates,
+    /// The tasks are related, which reads the same from either side.
+    RelatesTo,
+    /// The task follows up on the linked task.
+    FollowUpOf,
+}
+
+impl LinkType {
+    /// Describe the link as seen from one of its tasks.
+    ///
+    /// ### Args
+    /// * outgoing - Whether the link was made on the task it is seen from.
+    pub fn label(&self, outgoing: bool) -> &'static str {
+        match (self, outgoing) {
+            (LinkType::Duplicates, true) => "duplicates",
+            (LinkType::Duplicates, false) => "is duplicated by",
+            (LinkType::RelatesTo, _) => "relates to",
+            (LinkType::FollowUpOf, true) => "follow-up of",
+            (LinkType::FollowUpOf, false) => "followed up by",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TaskLink {
+    pub id: i64,
+    pub task_id: i64,
+    pub linked_task_id: i64,
+    pub link_type: LinkType,
+    pub created: UnixTimestamp,
+}
+
+/// A link as seen from one of its tasks, along with the task on the other end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskLinkRead {
+    pub id: i64,
+    pub link_type: LinkType,
+    /// Whether the link was made on this task rather than on the other one.
+    pub outgoing: bool,
+    /// How the link reads from this task, e.g. `followed up by`.
+    pub label: String,
+    pub other_task_id: i64,
+    pub other_task_title: String,
+    pub other_task_status: Status,
+}
+
+/// A link loaded along with the task on each end, before it is seen from either side.
+#[derive(Debug, Clone, FromRow)]
+pub struct TaskLinkRow {
+    pub id: i64,
+    pub task_id: i64,
+    pub linked_task_id: i64,
+    pub link_type: LinkType,
+    pub task_title: String,
+    pub task_status: Status,
+    pub linked_task_title: String,
+    pub linked_task_status: Status,
+}
+
+impl TaskLinkRow {
+    /// See the link from one of its tasks.
+    pub fn seen_from(&self, task_id: i64) -> TaskLinkRead {
+        let outgoing = self.task_id == task_id;
+
+        let (other_task_id, other_task_title, other_task_status) = match outgoing {
+            true => (
+                self.linked_task_id,
+                self.linked_task_title.clone(),
+                self.linked_task_status.clone(),
+            ),
+            false => (
+                self.task_id,
+                self.task_title.clone(),
+                self.task_status.clone(),
+            ),
+        };
+
+        TaskLinkRead {
+            id: self.id,
+            link_type: self.link_type,
+            outgoing,
+            label: self.link_type.label(outgoing).to_string(),
+            other_task_id,
+            other_task_title,
+            other_task_status,
+        }
+    }
+}
@@ -2,7 +2,9 @@ use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
-use super::UnixTimestamp;
+use crate::{FieldKind, FilterableField};
+
+use super::{OptionalUnixTimestamp, UnixTimestamp};
 
 
 #[derive(Debug, Clone, FromRow, PartialEq)]
@@ -13,6 +15,119 @@ pub struct TaskWorkHistory {
     pub end_date: UnixTimestamp,
 }
 
+/// A session of work as it is stored, with no end date while it is still running.
+#[derive(Debug, Clone, FromRow, PartialEq)]
+pub struct WorkSession {
+    pub id: i64,
+    pub task_id: i64,
+    pub start_date: UnixTimestamp,
+    pub end_date: OptionalUnixTimestamp,
+}
+
+impl WorkSession {
+    pub fn is_running(&self) -> bool {
+        Option::<UnixTimestamp>::from(self.end_date).is_none()
+    }
+
+    /// Whether the timestamp falls strictly inside the session, with a running session ending now.
+    pub fn contains(&self, at: UnixTimestamp) -> bool {
+        self.start_date < at && at < self.end_date.unwrap_or_now()
+    }
+
+    /// How long the session lasted in seconds, with a running session ending now.
+    pub fn duration(&self) -> i64 {
+        self.end_date.unwrap_or_now() - self.start_date
+    }
+
+    /// The parts of the session before and after the timestamp, which must fall inside it.
+    ///
+    /// The second part keeps the end of the session so that a running session carries on
+    /// running. It has not been saved yet, so its id is left as 0.
+    pub fn split_at(&self, at: UnixTimestamp) -> anyhow::Result<(WorkSession, WorkSession)> {
+        if !self.contains(at) {
+            anyhow::bail!("A session can only be split at a point between its start and end.");
+        }
+
+        let first = WorkSession {
+            end_date: Some(at).into(),
+            ..self.clone()
+        };
+        let second = WorkSession {
+            id: 0,
+            start_date: at,
+            ..self.clone()
+        };
+
+        Ok((first, second))
+    }
+
+    /// The part of the session between the timestamps, which default to its start and end.
+    ///
+    /// The part of a session which is still running cannot be picked, as it has no end yet.
+    pub fn part_between(
+        &self,
+        start_date: Option<UnixTimestamp>,
+        end_date: Option<UnixTimestamp>,
+    ) -> anyhow::Result<(UnixTimestamp, UnixTimestamp)> {
+        let Some(end_date) = end_date.or(self.end_date.into()) else {
+            anyhow::bail!("The running part of a session cannot be moved to another task.");
+        };
+        let start_date = start_date.unwrap_or(self.start_date);
+
+        for at in [start_date, end_date] {
+            if at < self.start_date || at > self.end_date.unwrap_or_now() {
+                anyhow::bail!("The part to move must lie within the session.");
+            }
+        }
+
+        if end_date <= start_date {
+            anyhow::bail!("The part to move must end after it starts.");
+        }
+
+        Ok((start_date, end_date))
+    }
+}
+
+/// Merge sessions of the same task which follow on from each other into one.
+///
+/// ### Returns
+/// The first session stretched to the end of the last one, along with the ids of the other
+/// sessions which it replaces.
+pub fn merge_sessions(mut sessions: Vec<WorkSession>) -> anyhow::Result<(WorkSession, Vec<i64>)> {
+    sessions.sort_by_key(|session| (session.start_date, session.id));
+    sessions.dedup_by_key(|session| session.id);
+
+    if sessions.len() < 2 {
+        anyhow::bail!("At least two sessions are needed to merge.");
+    }
+
+    let first = &sessions[0];
+    let last = &sessions[sessions.len() - 1];
+
+    if sessions
+        .iter()
+        .any(|session| session.task_id != first.task_id)
+    {
+        anyhow::bail!("Only sessions of the same task can be merged.");
+    }
+
+    for (earlier, later) in sessions.iter().zip(sessions.iter().skip(1)) {
+        if Option::<UnixTimestamp>::from(earlier.end_date) != Some(later.start_date) {
+            anyhow::bail!(
+                "Only sessions which follow on from each other without a gap can be merged."
+            );
+        }
+    }
+
+    let merged = WorkSession {
+        end_date: last.end_date,
+        ..first.clone()
+    };
+    let replaced = sessions.iter().skip(1).map(|session| session.id).collect();
+
+    Ok((merged, replaced))
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskWorkHistoryRead {
@@ -52,4 +167,311 @@ impl From<TaskWorkHistory> for TaskWorkHistoryRead {
             elapsed_duration: delta,
         }
     }
-}
\ No newline at end of file
+}
+
+/// What to do when a session being saved overlaps other sessions.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlapPolicy {
+    /// Refuse to save the session.
+    #[default]
+    Reject,
+    /// Save the session and trim the other sessions around it. Sessions it lies entirely within
+    /// are split in two, and sessions lying entirely within it are deleted.
+    Trim,
+}
+
+/// Another session overlapping one being saved, with no end date while it is still running.
+#[derive(Debug, Clone, FromRow, PartialEq)]
+pub struct OverlappingSession {
+    pub id: i64,
+    pub task_id: i64,
+    pub task_title: String,
+    pub start_date: UnixTimestamp,
+    pub end_date: OptionalUnixTimestamp,
+}
+
+/// A way to resolve an overlap between two sessions.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WorkHistoryFix {
+    /// End the earlier session when the later one starts.
+    EndEarlierAt {
+        history_id: i64,
+        end_date: Timestamp,
+    },
+    /// Start the later session when the earlier one ends.
+    StartLaterAt {
+        history_id: i64,
+        start_date: Timestamp,
+    },
+    /// Delete a session which lies entirely within the other one.
+    Delete { history_id: i64 },
+    /// Split a session around the other one, which lies entirely within it.
+    SplitAround {
+        history_id: i64,
+        start_date: Timestamp,
+        end_date: Timestamp,
+    },
+}
+
+/// Two sessions which overlap, with running sessions treated as ending now.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkHistoryConflict {
+    /// The session which started first.
+    pub earlier: TaskWorkHistoryRead,
+    pub later: TaskWorkHistoryRead,
+    /// How long the sessions overlap, in seconds.
+    pub overlap_duration: i64,
+    pub suggested_fixes: Vec<WorkHistoryFix>,
+}
+
+impl WorkHistoryConflict {
+    pub fn new(earlier: TaskWorkHistory, later: TaskWorkHistory) -> Self {
+        let overlap_end = earlier.end_date.min(later.end_date);
+        let overlap_duration = overlap_end - later.start_date;
+
+        let suggested_fixes = match later.end_date <= earlier.end_date {
+            true => vec![
+                WorkHistoryFix::Delete {
+                    history_id: later.id,
+                },
+                WorkHistoryFix::SplitAround {
+                    history_id: earlier.id,
+                    start_date: later.start_date.into(),
+                    end_date: later.end_date.into(),
+                },
+            ],
+            false => vec![
+                WorkHistoryFix::EndEarlierAt {
+                    history_id: earlier.id,
+                    end_date: later.start_date.into(),
+                },
+                WorkHistoryFix::StartLaterAt {
+                    history_id: later.id,
+                    start_date: earlier.end_date.into(),
+                },
+            ],
+        };
+
+        Self {
+            earlier: earlier.into(),
+            later: later.into(),
+            overlap_duration,
+            suggested_fixes,
+        }
+    }
+}
+
+/// The work history fields which advanced searches may filter and sort on.
+///
+/// A session which is still running is treated as ending now.
+pub const WORK_HISTORY_FILTER_FIELDS: &[FilterableField] = &[
+    FilterableField::new("id", "twh.id", FieldKind::Integer),
+    FilterableField::new("task_id", "twh.task_id", FieldKind::Integer),
+    FilterableField::new("start_date", "twh.start_date", FieldKind::Timestamp),
+    FilterableField::new(
+        "end_date",
+        "COALESCE(twh.end_date, CAST(strftime('%s', 'now') AS INTEGER))",
+        FieldKind::Timestamp,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: i64, start_date: i64, end_date: Option<i64>) -> WorkSession {
+        WorkSession {
+            id,
+            task_id: 1,
+            start_date: at(start_date),
+            end_date: end_date.into(),
+        }
+    }
+
+    fn at(seconds: i64) -> UnixTimestamp {
+        UnixTimestamp::from(seconds)
+    }
+
+    #[test]
+    fn is_running_until_the_session_has_an_end() {
+        assert!(session(1, 100, None).is_running());
+        assert!(!session(1, 100, Some(200)).is_running());
+    }
+
+    #[test]
+    fn contains_only_points_strictly_inside_the_session() {
+        let cases = [
+            (99, false),
+            (100, false),
+            (150, true),
+            (199, true),
+            (200, false),
+        ];
+
+        for (seconds, expected) in cases {
+            assert_eq!(
+                session(1, 100, Some(200)).contains(at(seconds)),
+                expected,
+                "{:?}",
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn contains_points_up_to_now_in_a_running_session() {
+        let now = UnixTimestamp::now().as_seconds();
+        let running = session(1, now - 100, None);
+
+        assert!(running.contains(at(now - 50)));
+        assert!(!running.contains(at(now + 3600)));
+    }
+
+    #[test]
+    fn split_at_keeps_the_total_duration() {
+        let (first, second) = session(1, 100, Some(400)).split_at(at(250)).unwrap();
+
+        assert_eq!(first, session(1, 100, Some(250)));
+        assert_eq!(second, session(0, 250, Some(400)));
+        assert_eq!(first.duration() + second.duration(), 300);
+    }
+
+    #[test]
+    fn split_at_leaves_the_second_part_running() {
+        let now = UnixTimestamp::now().as_seconds();
+        let (first, second) = session(1, now - 100, None).split_at(at(now - 40)).unwrap();
+
+        assert_eq!(first.duration(), 60);
+        assert!(second.is_running());
+    }
+
+    #[test]
+    fn split_at_rejects_points_outside_the_session() {
+        for seconds in [100, 400, 500] {
+            assert!(
+                session(1, 100, Some(400)).split_at(at(seconds)).is_err(),
+                "{:?}",
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn part_between_defaults_to_the_whole_session() {
+        let cases = [
+            ((None, None), (100, 400)),
+            ((Some(150), None), (150, 400)),
+            ((None, Some(300)), (100, 300)),
+            ((Some(150), Some(300)), (150, 300)),
+        ];
+
+        for ((start_date, end_date), (expected_start, expected_end)) in cases {
+            let (start, end) = session(1, 100, Some(400))
+                .part_between(start_date.map(at), end_date.map(at))
+                .unwrap();
+
+            assert_eq!(
+                (start.as_seconds(), end.as_seconds()),
+                (expected_start, expected_end),
+                "{:?}",
+                (start_date, end_date)
+            );
+            assert_eq!(end - start, expected_end - expected_start);
+        }
+    }
+
+    #[test]
+    fn part_between_rejects_parts_which_do_not_fit() {
+        let cases = [
+            (Some(50), None),
+            (None, Some(500)),
+            (Some(300), Some(300)),
+            (Some(300), Some(200)),
+        ];
+
+        for (start_date, end_date) in cases {
+            let part =
+                session(1, 100, Some(400)).part_between(start_date.map(at), end_date.map(at));
+
+            assert!(part.is_err(), "{:?}", (start_date, end_date));
+        }
+    }
+
+    #[test]
+    fn part_between_needs_an_end_for_a_running_session() {
+        let now = UnixTimestamp::now().as_seconds();
+        let running = session(1, now - 100, None);
+
+        assert!(running.part_between(None, None).is_err());
+
+        let (start, end) = running.part_between(None, Some(at(now - 40))).unwrap();
+        assert_eq!(end - start, 60);
+    }
+
+    #[test]
+    fn merge_sessions_keeps_the_total_duration() {
+        let sessions = vec![
+            session(3, 250, Some(400)),
+            session(1, 100, Some(200)),
+            session(2, 200, Some(250)),
+            session(1, 100, Some(200)),
+        ];
+        let total: i64 = sessions[..3].iter().map(WorkSession::duration).sum();
+
+        let (merged, replaced_ids) = merge_sessions(sessions).unwrap();
+
+        assert_eq!(merged, session(1, 100, Some(400)));
+        assert_eq!(merged.duration(), total);
+        assert_eq!(replaced_ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn merge_sessions_keeps_a_running_last_session_running() {
+        let now = UnixTimestamp::now().as_seconds();
+        let (merged, _) = merge_sessions(vec![
+            session(1, now - 100, Some(now - 40)),
+            session(2, now - 40, None),
+        ])
+        .unwrap();
+
+        assert!(merged.is_running());
+        assert_eq!(merged.start_date, at(now - 100));
+    }
+
+    #[test]
+    fn merge_sessions_rejects_sessions_which_do_not_join_up() {
+        let other_task = WorkSession {
+            task_id: 2,
+            ..session(2, 200, Some(300))
+        };
+        let cases = [
+            vec![session(1, 100, Some(200))],
+            vec![session(1, 100, Some(200)), session(1, 100, Some(200))],
+            vec![session(1, 100, Some(200)), session(2, 250, Some(300))],
+            vec![session(1, 100, Some(200)), other_task],
+        ];
+
+        for sessions in cases {
+            assert!(merge_sessions(sessions.clone()).is_err(), "{:?}", sessions);
+        }
+    }
+
+    #[test]
+    fn elapsed_duration_is_the_length_of_the_session() {
+        let history = TaskWorkHistory {
+            id: 1,
+            task_id: 1,
+            start_date: at(100),
+            end_date: at(400),
+        };
+
+        assert_eq!(TaskWorkHistoryRead::from(history).elapsed_duration, 300);
+    }
+}
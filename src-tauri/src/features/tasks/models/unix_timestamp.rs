@@ -71,7 +71,7 @@ impl Sub for UnixTimestamp {
     }
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Type, PartialOrd, Ord, Eq, FromRow)]
+#[derive(Debug, Default, Copy, Clone, Deserialize, Serialize, PartialEq, Type, PartialOrd, Ord, Eq, FromRow)]
 #[sqlx(transparent)]
 pub struct OptionalUnixTimestamp(Option<i64>);
 
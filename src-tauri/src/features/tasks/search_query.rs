@@ -0,0 +1,629 @@
+use std::fmt::Display;
+
+use jiff::{civil::Date, civil::Weekday, Timestamp, ToSpan, Zoned};
+
+use super::{DateFilter, DurationFilter, Status, TaskSearchParams};
+
+/// A search typed into the task list, e.g. `tag:client-a status:doing due:<friday -tag:blocked "exact phrase"`.
+///
+/// ### Grammar
+/// * `word` - Matches tasks containing a word starting with `word`.
+/// * `"exact phrase"` - Matches tasks containing the phrase.
+/// * `tag:value` - Matches tasks with the tag, `-tag:value` matches tasks without it.
+/// * `status:doing,paused` - Matches tasks in any of the statuses, `-status:` excludes them.
+/// * `due:` and `start:` - Compares the scheduled dates with `today`, `tomorrow`, `yesterday`,
+///   a weekday such as `friday` or a date such as `2024-12-31`, optionally prefixed with one of
+///   `<`, `<=`, `>` or `>=`.
+/// * `est:` - Compares the estimate with a duration such as `30m`, `1.5h` or `2d`, optionally
+///   prefixed with a comparison. A number without a unit is in hours.
+///
+/// Values containing spaces can be quoted, e.g. `tag:"client a"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    pub words: Vec<String>,
+    pub phrases: Vec<String>,
+    pub tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub statuses: Vec<Status>,
+    pub excluded_statuses: Vec<Status>,
+    pub start_by_filter: Option<DateFilter>,
+    pub due_by_filter: Option<DateFilter>,
+    pub estimate_filter: Option<DurationFilter>,
+}
+
+impl TaskQuery {
+    /// Compile the query into the search parameters, replacing the filters it specifies.
+    ///
+    /// Tags are required on top of the quick filter rather than replacing it.
+    pub fn apply_to(self, mut params: TaskSearchParams) -> TaskSearchParams {
+        if !self.statuses.is_empty() {
+            params.statuses = self.statuses;
+        }
+
+        params
+            .statuses
+            .retain(|status| !self.excluded_statuses.contains(status));
+
+        params.required_tags.extend(self.tags);
+        params.excluded_tags.extend(self.excluded_tags);
+
+        if self.start_by_filter.is_some() {
+            params.start_by_filter = self.start_by_filter;
+        }
+
+        if self.due_by_filter.is_some() {
+            params.due_by_filter = self.due_by_filter;
+        }
+
+        if self.estimate_filter.is_some() {
+            params.estimate_filter = self.estimate_filter;
+        }
+
+        let text = self
+            .phrases
+            .iter()
+            .map(|phrase| format!("\"{}\"", phrase))
+            .chain(self.words)
+            .chain(params.query_string.take())
+            .collect::<Vec<_>>();
+
+        if !text.is_empty() {
+            params.query_string = Some(text.join(" "));
+        }
+
+        params
+    }
+}
+
+/// The search could not be understood, `position` is the character where the problem starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryParseError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+/// A single whitespace separated part of the query.
+#[derive(Debug)]
+struct Term {
+    position: usize,
+    negated: bool,
+    key: Option<String>,
+    value: String,
+    value_position: usize,
+    quoted: bool,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Lexer {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next_term(&mut self) -> Result<Option<Term>, QueryParseError> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+
+        let position = self.position;
+
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+
+        let negated = self.peek() == Some('-')
+            && self
+                .chars
+                .get(self.position + 1)
+                .is_some_and(|next| !next.is_whitespace());
+
+        if negated {
+            self.position += 1;
+        }
+
+        let key = self.read_key();
+        let value_position = self.position;
+
+        let (value, quoted) = match self.peek() {
+            Some('"') => (self.read_quoted()?, true),
+            _ => (self.read_bare(), false),
+        };
+
+        Ok(Some(Term {
+            position,
+            negated,
+            key,
+            value,
+            value_position,
+            quoted,
+        }))
+    }
+
+    /// Read `name:` when the term starts with one, leaving the position after the colon.
+    fn read_key(&mut self) -> Option<String> {
+        let length = self.chars[self.position..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count();
+
+        match self.chars.get(self.position + length) {
+            Some(':') if length > 0 => {
+                let key: String = self.chars[self.position..self.position + length]
+                    .iter()
+                    .collect();
+                self.position += length + 1;
+                Some(key.to_lowercase())
+            }
+            _ => None,
+        }
+    }
+
+    fn read_quoted(&mut self) -> Result<String, QueryParseError> {
+        let opening = self.position;
+        self.position += 1;
+
+        let length = self.chars[self.position..]
+            .iter()
+            .take_while(|c| **c != '"')
+            .count();
+
+        if self.position + length >= self.chars.len() {
+            return Err(QueryParseError::new(opening, "Unterminated quote"));
+        }
+
+        let value = self.chars[self.position..self.position + length]
+            .iter()
+            .collect();
+        self.position += length + 1;
+        Ok(value)
+    }
+
+    fn read_bare(&mut self) -> String {
+        let length = self.chars[self.position..]
+            .iter()
+            .take_while(|c| !c.is_whitespace())
+            .count();
+
+        let value = self.chars[self.position..self.position + length]
+            .iter()
+            .collect();
+        self.position += length;
+        value
+    }
+}
+
+/// Parse a search typed into the task list, resolving relative dates against `now`.
+pub fn parse_task_query(input: &str, now: &Zoned) -> Result<TaskQuery, QueryParseError> {
+    let mut lexer = Lexer::new(input);
+    let mut query = TaskQuery::default();
+
+    while let Some(term) = lexer.next_term()? {
+        let key = match term.key.as_deref() {
+            Some(key @ ("tag" | "status" | "due" | "start" | "est")) => key,
+            // Unknown keys such as in `http://` are ordinary text.
+            Some(key) => {
+                query.words.push(format!("{}:{}", key, term.value));
+                continue;
+            }
+            None if term.negated => {
+                return Err(QueryParseError::new(
+                    term.position,
+                    "Only tag: and status: filters can be negated",
+                ))
+            }
+            None if term.quoted => {
+                query.phrases.push(term.value);
+                continue;
+            }
+            None => {
+                query.words.push(term.value);
+                continue;
+            }
+        };
+
+        if term.value.trim().is_empty() {
+            return Err(QueryParseError::new(
+                term.value_position,
+                format!("Expected a value after '{}:'", key),
+            ));
+        }
+
+        if term.negated && !matches!(key, "tag" | "status") {
+            return Err(QueryParseError::new(
+                term.position,
+                "Only tag: and status: filters can be negated",
+            ));
+        }
+
+        match (key, term.negated) {
+            ("tag", false) => query.tags.push(term.value),
+            ("tag", true) => query.excluded_tags.push(term.value),
+            ("status", negated) => {
+                let statuses = parse_statuses(&term.value, term.value_position)?;
+                match negated {
+                    false => query.statuses.extend(statuses),
+                    true => query.excluded_statuses.extend(statuses),
+                }
+            }
+            ("due", _) => {
+                query.due_by_filter = Some(parse_date_filter(&term.value, term.value_position, now)?)
+            }
+            ("start", _) => {
+                query.start_by_filter = Some(parse_date_filter(&term.value, term.value_position, now)?)
+            }
+            ("est", _) => {
+                query.estimate_filter = Some(parse_duration_filter(&term.value, term.value_position)?)
+            }
+            (key, _) => {
+                return Err(QueryParseError::new(
+                    term.position,
+                    format!("Unsupported filter '{}:'", key),
+                ))
+            }
+        }
+    }
+
+    Ok(query)
+}
+
+fn parse_statuses(value: &str, position: usize) -> Result<Vec<Status>, QueryParseError> {
+    let mut offset = 0;
+    let mut statuses = Vec::new();
+
+    for name in value.split(',') {
+        let normalized: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_lowercase();
+
+        let status = match normalized.as_str() {
+            "todo" => Status::Todo,
+            "doing" => Status::Doing,
+            "paused" => Status::Paused,
+            "done" => Status::Done,
+            "cancelled" | "canceled" => Status::Cancelled,
+            _ => {
+                return Err(QueryParseError::new(
+                    position + offset,
+                    format!(
+                        "Unknown status '{}', expected one of todo, doing, paused, done or cancelled",
+                        name
+                    ),
+                ))
+            }
+        };
+
+        statuses.push(status);
+        offset += name.chars().count() + 1;
+    }
+
+    Ok(statuses)
+}
+
+/// How a value is compared, parsed from the start of the value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Equal,
+}
+
+fn split_comparison(value: &str) -> (Comparison, &str, usize) {
+    for (prefix, comparison) in [
+        ("<=", Comparison::LessThanOrEqual),
+        (">=", Comparison::GreaterThanOrEqual),
+        ("<", Comparison::LessThan),
+        (">", Comparison::GreaterThan),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (comparison, rest, prefix.len());
+        }
+    }
+
+    (Comparison::Equal, value, 0)
+}
+
+fn parse_date_filter(value: &str, position: usize, now: &Zoned) -> Result<DateFilter, QueryParseError> {
+    let (comparison, date, offset) = split_comparison(value);
+    let position = position + offset;
+    let day = parse_date(date, now)
+        .ok_or_else(|| QueryParseError::new(position, format!("Unknown date '{}'", date)))?;
+
+    let invalid = |_| QueryParseError::new(position, format!("Date '{}' is out of range", date));
+    let time_zone = now.time_zone().clone();
+    let start_of_day = day.to_zoned(time_zone.clone()).map_err(invalid)?;
+    let start_of_next_day = day
+        .tomorrow()
+        .and_then(|next| next.to_zoned(time_zone))
+        .map_err(invalid)?;
+
+    let first_second: Timestamp = start_of_day.timestamp();
+    let last_second: Timestamp = start_of_next_day
+        .timestamp()
+        .checked_sub(1.second())
+        .map_err(invalid)?;
+
+    let (start, end) = match comparison {
+        Comparison::LessThan => (None, Some(first_second.checked_sub(1.second()).map_err(invalid)?)),
+        Comparison::LessThanOrEqual => (None, Some(last_second)),
+        Comparison::GreaterThan => (Some(start_of_next_day.timestamp()), None),
+        Comparison::GreaterThanOrEqual => (Some(first_second), None),
+        Comparison::Equal => (Some(first_second), Some(last_second)),
+    };
+
+    Ok(DateFilter { start, end })
+}
+
+/// Resolve a date word relative to today. Weekdays refer to the next such day, including today.
+fn parse_date(value: &str, now: &Zoned) -> Option<Date> {
+    let today = now.date();
+    let lowered = value.to_lowercase();
+
+    let weekday = match lowered.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.tomorrow().ok(),
+        "yesterday" => return today.yesterday().ok(),
+        "monday" | "mon" => Weekday::Monday,
+        "tuesday" | "tue" => Weekday::Tuesday,
+        "wednesday" | "wed" => Weekday::Wednesday,
+        "thursday" | "thu" => Weekday::Thursday,
+        "friday" | "fri" => Weekday::Friday,
+        "saturday" | "sat" => Weekday::Saturday,
+        "sunday" | "sun" => Weekday::Sunday,
+        _ => return value.parse::<Date>().ok(),
+    };
+
+    let days_until = (weekday.to_monday_zero_offset() - today.weekday().to_monday_zero_offset())
+        .rem_euclid(7);
+
+    today.checked_add(i64::from(days_until).days()).ok()
+}
+
+fn parse_duration_filter(value: &str, position: usize) -> Result<DurationFilter, QueryParseError> {
+    let (comparison, duration, offset) = split_comparison(value);
+    let position = position + offset;
+    let seconds = parse_duration(duration).ok_or_else(|| {
+        QueryParseError::new(
+            position,
+            format!("Unknown duration '{}', expected something like 30m, 2h or 1d", duration),
+        )
+    })?;
+
+    // Anything this long would saturate when converted and overflow the bounds below.
+    if seconds >= i64::MAX as f64 {
+        return Err(QueryParseError::new(
+            position,
+            format!("Duration '{}' is out of range", duration),
+        ));
+    }
+    let seconds = seconds as i64;
+
+    let (min, max) = match comparison {
+        Comparison::LessThan => (None, Some(seconds - 1)),
+        Comparison::LessThanOrEqual => (None, Some(seconds)),
+        Comparison::GreaterThan => (Some(seconds + 1), None),
+        Comparison::GreaterThanOrEqual => (Some(seconds), None),
+        Comparison::Equal => (Some(seconds), Some(seconds)),
+    };
+
+    Ok(DurationFilter { min, max })
+}
+
+/// Parse a duration such as `30m`, `1.5h` or `2d` into seconds, hours being the default unit.
+fn parse_duration(value: &str) -> Option<f64> {
+    let lowered = value.to_lowercase();
+    let unit_start = lowered
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(lowered.len());
+
+    let (amount, unit) = lowered.split_at(unit_start);
+    let amount: f64 = amount.parse().ok()?;
+
+    let seconds_per_unit = match unit {
+        "s" | "sec" | "secs" => 1.0,
+        "m" | "min" | "mins" => 60.0,
+        "" | "h" | "hr" | "hrs" => 3_600.0,
+        "d" | "day" | "days" => 86_400.0,
+        _ => return None,
+    };
+
+    Some((amount * seconds_per_unit).round())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::tasks::QuickFilter;
+
+    /// A Wednesday.
+    fn now() -> Zoned {
+        "2026-10-14T10:00[Europe/Amsterdam]".parse().unwrap()
+    }
+
+    fn at(date: &str) -> Timestamp {
+        format!("{}[Europe/Amsterdam]", date)
+            .parse::<Zoned>()
+            .unwrap()
+            .timestamp()
+    }
+
+    fn parse(input: &str) -> TaskQuery {
+        parse_task_query(input, &now()).unwrap()
+    }
+
+    fn parse_error(input: &str) -> QueryParseError {
+        parse_task_query(input, &now()).unwrap_err()
+    }
+
+    #[test]
+    fn parses_words_and_quoted_phrases() {
+        let query = parse(r#"fix  "exact phrase" login http://example.com"#);
+
+        assert_eq!(query.words, vec!["fix", "login", "http://example.com"]);
+        assert_eq!(query.phrases, vec!["exact phrase"]);
+    }
+
+    #[test]
+    fn parses_tags_with_quotes_and_negation() {
+        let query = parse(r#"tag:client-a TAG:"client b" -tag:blocked"#);
+
+        assert_eq!(query.tags, vec!["client-a", "client b"]);
+        assert_eq!(query.excluded_tags, vec!["blocked"]);
+    }
+
+    #[test]
+    fn parses_statuses_with_negation() {
+        let query = parse("status:todo,Doing,canceled -status:done");
+
+        assert_eq!(
+            query.statuses,
+            vec![Status::Todo, Status::Doing, Status::Cancelled]
+        );
+        assert_eq!(query.excluded_statuses, vec![Status::Done]);
+    }
+
+    #[test]
+    fn parses_dates_relative_to_now() {
+        let cases = [
+            (
+                "due:friday",
+                Some(at("2026-10-16T00:00")),
+                Some(at("2026-10-16T23:59:59")),
+            ),
+            ("due:<today", None, Some(at("2026-10-13T23:59:59"))),
+            ("due:<=tomorrow", None, Some(at("2026-10-15T23:59:59"))),
+            ("due:>yesterday", Some(at("2026-10-14T00:00")), None),
+            ("due:>=2026-12-31", Some(at("2026-12-31T00:00")), None),
+            (
+                "due:wed",
+                Some(at("2026-10-14T00:00")),
+                Some(at("2026-10-14T23:59:59")),
+            ),
+        ];
+
+        for (input, start, end) in cases {
+            assert_eq!(
+                parse(input).due_by_filter,
+                Some(DateFilter { start, end }),
+                "{}",
+                input
+            );
+        }
+
+        assert_eq!(
+            parse("start:>=2026-12-31").start_by_filter,
+            Some(DateFilter {
+                start: Some(at("2026-12-31T00:00")),
+                end: None,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_estimates() {
+        let cases = [
+            ("est:30m", Some(1_800), Some(1_800)),
+            ("est:<=1.5h", None, Some(5_400)),
+            ("est:<2", None, Some(7_199)),
+            ("est:>1d", Some(86_401), None),
+        ];
+
+        for (input, min, max) in cases {
+            assert_eq!(
+                parse(input).estimate_filter,
+                Some(DurationFilter { min, max }),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_estimates_which_are_out_of_range() {
+        for input in ["est:>99999999999999999999d", "est:<=99999999999999999999"] {
+            let error = parse_error(input);
+
+            assert_eq!(error.position, 4, "{}", input);
+            assert!(error.message.contains("out of range"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn reports_where_malformed_queries_go_wrong() {
+        let cases = [
+            (r#"fix "open"#, 4),
+            ("fix -word", 4),
+            ("-due:today", 0),
+            ("tag:", 4),
+            (r#"tag:"""#, 4),
+            ("status:doing,soon", 13),
+            ("due:<someday", 5),
+            ("est:5x", 4),
+        ];
+
+        for (input, position) in cases {
+            assert_eq!(parse_error(input).position, position, "{}", input);
+        }
+    }
+
+    #[test]
+    fn apply_to_combines_the_query_with_the_existing_filters() {
+        let params: TaskSearchParams = serde_json::from_value(serde_json::json!({
+            "page": 1,
+            "pageSize": 10,
+            "queryString": "typed",
+            "statuses": ["To Do", "Doing"],
+            "quickFilter": "overdue",
+            "startByFilter": null,
+            "dueByFilter": null,
+            "ordering": { "orderBy": "title", "sortDirection": 0 },
+            "excludedTags": ["archived"],
+            "estimateFilter": null,
+            "searchQuery": null,
+        }))
+        .unwrap();
+
+        let params =
+            parse(r#"tag:client "exact phrase" -tag:blocked -status:doing"#).apply_to(params);
+
+        assert_eq!(params.quick_filter, Some(QuickFilter::Overdue));
+        assert_eq!(params.required_tags, vec!["client"]);
+        assert_eq!(params.excluded_tags, vec!["archived", "blocked"]);
+        assert_eq!(params.statuses, vec![Status::Todo]);
+        assert_eq!(
+            params.query_string.as_deref(),
+            Some(r#""exact phrase" typed"#)
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use anyhow_tauri::{IntoTAResult, TAResult};
+use jiff::Zoned;
+use sqlx::{Sqlite, Transaction};
+use tauri::State;
+
+use crate::{
+    features::{
+        tags::Tag,
+        tasks::{create_task, CreateTask, Status},
+    },
+    Data,
+};
+
+use super::{
+    fill_placeholders, parse_offset, resolve_offset, with_built_in_values, EditTaskTemplate,
+    NewTaskTemplate, TaskTemplate, TaskTemplateRead,
+};
+
+fn not_found_message(template_id: i64) -> String {
+    format!("Unable to find a task template with the id {}", template_id)
+}
+
+fn validate_template(template: &NewTaskTemplate) -> TAResult<()> {
+    if template.name.trim().is_empty() {
+        anyhow_tauri::bail!("A task template must have a name.");
+    }
+
+    if template.title.trim().is_empty() {
+        anyhow_tauri::bail!("A task template must have a title.");
+    }
+
+    for offset in [&template.start_offset, &template.due_offset]
+        .into_iter()
+        .flatten()
+    {
+        parse_offset(offset).into_ta_result()?;
+    }
+
+    Ok(())
+}
+
+async fn find_template(template_id: i64, db: &Data) -> TAResult<TaskTemplate> {
+    let template = sqlx::query_as!(
+        TaskTemplate,
+        "SELECT * FROM task_templates WHERE task_templates.id = ?",
+        template_id
+    )
+    .fetch_optional(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    match template {
+        Some(template) => Ok(template),
+        None => anyhow_tauri::bail!(not_found_message(template_id)),
+    }
+}
+
+/// The tags of a template, leaving out any which are in the trash.
+async fn find_template_tags(template_id: i64, db: &Data) -> TAResult<Vec<Tag>> {
+    sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT tags.*
+        FROM tags
+        INNER JOIN task_template_tags ttt ON ttt.tag_id = tags.id
+        WHERE ttt.template_id = ?
+        AND tags.deleted_at IS NULL
+        ORDER BY tags.value ASC
+    "#,
+        template_id
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()
+}
+
+async fn replace_template_tags(
+    template_id: i64,
+    tag_ids: &Vec<i64>,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    sqlx::query!(
+        "DELETE FROM task_template_tags WHERE task_template_tags.template_id = ?",
+        template_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    for tag_id in tag_ids.iter() {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO task_template_tags (template_id, tag_id) VALUES (?, ?)",
+            template_id,
+            tag_id
+        )
+        .execute(&mut **transaction)
+        .await
+        .into_ta_result()?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_task_templates(db: State<'_, Data>) -> TAResult<Vec<TaskTemplateRead>> {
+    let templates = sqlx::query_as!(
+        TaskTemplate,
+        "SELECT * FROM task_templates ORDER BY LOWER(task_templates.name) ASC"
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let mut template_reads = Vec::with_capacity(templates.len());
+    for template in templates {
+        let tags = find_template_tags(template.id, &db).await?;
+        template_reads.push(TaskTemplateRead::new(template, tags));
+    }
+
+    Ok(template_reads)
+}
+
+#[tauri::command]
+pub async fn get_task_template(
+    template_id: i64,
+    db: State<'_, Data>,
+) -> TAResult<TaskTemplateRead> {
+    let template = find_template(template_id, &db).await?;
+    let tags = find_template_tags(template_id, &db).await?;
+
+    Ok(TaskTemplateRead::new(template, tags))
+}
+
+#[tauri::command]
+pub async fn add_task_template(
+    new_template: NewTaskTemplate,
+    db: State<'_, Data>,
+) -> TAResult<TaskTemplateRead> {
+    validate_template(&new_template)?;
+    let name = new_template.name.trim();
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result: TAResult<i64> = async {
+        let template_id = sqlx::query!(
+            r#"
+            INSERT INTO task_templates (name, title, description, priority, estimated_duration, start_offset, due_offset, project_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+            name,
+            new_template.title,
+            new_template.description,
+            new_template.priority,
+            new_template.estimated_duration,
+            new_template.start_offset,
+            new_template.due_offset,
+            new_template.project_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?
+        .last_insert_rowid();
+
+        replace_template_tags(template_id, &new_template.tag_ids, &mut transaction).await?;
+        Ok(template_id)
+    }
+    .await;
+
+    let template_id = match result {
+        Ok(template_id) => {
+            transaction.commit().await.into_ta_result()?;
+            template_id
+        }
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            return Err(e);
+        }
+    };
+
+    get_task_template(template_id, db).await
+}
+
+#[tauri::command]
+pub async fn edit_task_template(
+    edit_template: EditTaskTemplate,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let template_id = edit_template.id;
+    find_template(template_id, &db).await?;
+
+    let template = NewTaskTemplate::from(edit_template);
+    validate_template(&template)?;
+    let name = template.name.trim();
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result = async {
+        sqlx::query!(
+            r#"
+            UPDATE task_templates
+            SET name = ?,
+            title = ?,
+            description = ?,
+            priority = ?,
+            estimated_duration = ?,
+            start_offset = ?,
+            due_offset = ?,
+            project_id = ?
+            WHERE id = ?
+        "#,
+            name,
+            template.title,
+            template.description,
+            template.priority,
+            template.estimated_duration,
+            template.start_offset,
+            template.due_offset,
+            template.project_id,
+            template_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .into_ta_result()?;
+
+        replace_template_tags(template_id, &template.tag_ids, &mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn delete_task_template(template_id: i64, db: State<'_, Data>) -> TAResult<()> {
+    sqlx::query!(
+        "DELETE FROM task_templates WHERE task_templates.id = ?",
+        template_id
+    )
+    .execute(&db.pool)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Create a task from a template.
+///
+/// ### Args
+/// * variables - The values of the placeholders in the title and description. `{today}` is
+///   filled with today's date unless a value is given for it.
+#[tauri::command]
+pub async fn create_task_from_template(
+    template_id: i64,
+    variables: HashMap<String, String>,
+    db: State<'_, Data>,
+) -> TAResult<()> {
+    let template = find_template(template_id, &db).await?;
+    let tags = find_template_tags(template_id, &db).await?;
+
+    let now = Zoned::now();
+    let values = with_built_in_values(variables, &now);
+
+    let new_task = CreateTask {
+        title: fill_placeholders(&template.title, &values).into_ta_result()?,
+        description: fill_placeholders(&template.description, &values).into_ta_result()?,
+        status: Status::Todo,
+        scheduled_start_date: resolve_offset(template.start_offset.as_deref(), &now)
+            .into_ta_result()?,
+        scheduled_complete_date: resolve_offset(template.due_offset.as_deref(), &now)
+            .into_ta_result()?,
+        estimated_duration: template.estimated_duration,
+        tags: Some(tags),
+        parent_task_id: None,
+        priority: template.priority,
+        project_id: template.project_id,
+        custom_fields: Vec::new(),
+    };
+
+    create_task(new_task, db).await
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use jiff::{Span, Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+use crate::features::{tags::Tag, tasks::Priority};
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct TaskTemplate {
+    pub id: i64,
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub priority: Priority,
+    pub estimated_duration: Option<i64>,
+    /// When the task is scheduled to start, as an ISO 8601 span from the start of today.
+    pub start_offset: Option<String>,
+    /// When the task is due, as an ISO 8601 span from the start of today.
+    pub due_offset: Option<String>,
+    pub project_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTemplateRead {
+    pub id: i64,
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub priority: Priority,
+    pub estimated_duration: Option<i64>,
+    pub start_offset: Option<String>,
+    pub due_offset: Option<String>,
+    pub project_id: Option<i64>,
+    pub tags: Vec<Tag>,
+    /// The placeholders which need a value when creating a task, in the order they appear.
+    pub placeholders: Vec<String>,
+}
+
+impl TaskTemplateRead {
+    pub fn new(template: TaskTemplate, tags: Vec<Tag>) -> Self {
+        let placeholders =
+            find_placeholders(&format!("{} {}", template.title, template.description))
+                .into_iter()
+                .filter(|placeholder| !BUILT_IN_PLACEHOLDERS.contains(&placeholder.as_str()))
+                .collect();
+
+        Self {
+            id: template.id,
+            name: template.name,
+            title: template.title,
+            description: template.description,
+            priority: template.priority,
+            estimated_duration: template.estimated_duration,
+            start_offset: template.start_offset,
+            due_offset: template.due_offset,
+            project_id: template.project_id,
+            tags,
+            placeholders,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTaskTemplate {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub priority: Priority,
+    pub estimated_duration: Option<i64>,
+    pub start_offset: Option<String>,
+    pub due_offset: Option<String>,
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub tag_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditTaskTemplate {
+    pub id: i64,
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub priority: Priority,
+    pub estimated_duration: Option<i64>,
+    pub start_offset: Option<String>,
+    pub due_offset: Option<String>,
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub tag_ids: Vec<i64>,
+}
+
+impl From<EditTaskTemplate> for NewTaskTemplate {
+    fn from(value: EditTaskTemplate) -> Self {
+        Self {
+            name: value.name,
+            title: value.title,
+            description: value.description,
+            priority: value.priority,
+            estimated_duration: value.estimated_duration,
+            start_offset: value.start_offset,
+            due_offset: value.due_offset,
+            project_id: value.project_id,
+            tag_ids: value.tag_ids,
+        }
+    }
+}
+
+/// Placeholders which are filled in automatically unless a value is given for them.
+const BUILT_IN_PLACEHOLDERS: &[&str] = &["today"];
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Find the distinct `{placeholder}` names in the text, in the order they first appear.
+pub fn find_placeholders(text: &str) -> Vec<String> {
+    let mut placeholders: Vec<String> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        let name = &rest[..end];
+        if is_placeholder_name(name) {
+            if !placeholders.iter().any(|placeholder| placeholder == name) {
+                placeholders.push(name.to_string());
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    placeholders
+}
+
+/// Replace every `{placeholder}` in the text with its value.
+///
+/// Braces around anything which is not a placeholder name are left as they are, so that a
+/// description can still contain e.g. code. A placeholder without a value is an error.
+pub fn fill_placeholders(text: &str, values: &HashMap<String, String>) -> anyhow::Result<String> {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let name = rest[1..].find('}').map(|end| &rest[1..end + 1]);
+
+        match name {
+            Some(name) if is_placeholder_name(name) => {
+                let Some(value) = values.get(name) else {
+                    bail!("No value was given for the placeholder '{{{}}}'.", name);
+                };
+
+                filled.push_str(value);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// The values of the built in placeholders, which the given values take precedence over.
+pub fn with_built_in_values(
+    values: HashMap<String, String>,
+    now: &Zoned,
+) -> HashMap<String, String> {
+    let mut all_values = HashMap::from([("today".to_string(), now.date().to_string())]);
+    all_values.extend(values);
+    all_values
+}
+
+pub fn parse_offset(offset: &str) -> anyhow::Result<Span> {
+    offset.parse::<Span>().map_err(|e| {
+        anyhow::anyhow!(
+            "'{}' is not a valid offset, use an ISO 8601 duration such as P1D or P1DT9H: {}",
+            offset,
+            e
+        )
+    })
+}
+
+/// Resolve an offset relative to the start of today.
+pub fn resolve_offset(offset: Option<&str>, now: &Zoned) -> anyhow::Result<Option<Timestamp>> {
+    let Some(offset) = offset else {
+        return Ok(None);
+    };
+
+    let span = parse_offset(offset)?;
+    let start_of_today = now.date().to_zoned(now.time_zone().clone())?;
+
+    Ok(Some(start_of_today.checked_add(span)?.timestamp()))
+}
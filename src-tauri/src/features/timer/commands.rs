@@ -0,0 +1,195 @@
+use std::thread;
+
+use anyhow_tauri::{IntoTAResult, TAResult};
+use sqlx::SqlitePool;
+use tauri::{async_runtime::block_on, AppHandle, Emitter, Manager, State};
+
+use crate::{features::tasks::UnixTimestamp, Data};
+
+use super::{
+    CountdownTimer, CountdownTimerRead, TimerService, TimerStatus, TIMER_EXPIRED_EVENT,
+    TIMER_TICK_EVENT, TIMER_TICK_INTERVAL,
+};
+
+pub async fn load_timer(pool: &SqlitePool) -> anyhow::Result<CountdownTimer> {
+    let timer = sqlx::query_as!(
+        CountdownTimer,
+        "SELECT * FROM countdown_timer WHERE countdown_timer.id = 1"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(timer)
+}
+
+async fn save_timer(timer: &CountdownTimer, pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE countdown_timer
+        SET initial_duration = ?,
+        remaining_duration = ?,
+        status = ?,
+        started_at = ?,
+        message = ?
+        WHERE countdown_timer.id = ?
+    "#,
+        timer.initial_duration,
+        timer.remaining_duration,
+        timer.status,
+        timer.started_at,
+        timer.message,
+        timer.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Change the timer, keeping it as it was when the change cannot be saved.
+async fn update_timer(
+    service: &TimerService,
+    db: &Data,
+    change: impl FnOnce(&mut CountdownTimer, UnixTimestamp) -> TAResult<()>,
+) -> TAResult<CountdownTimerRead> {
+    let mut timer = service.timer.lock().await;
+    let now = UnixTimestamp::now();
+
+    let mut updated = timer.clone();
+    change(&mut updated, now)?;
+    save_timer(&updated, &db.pool).await.into_ta_result()?;
+    *timer = updated;
+
+    Ok(timer.read_at(now))
+}
+
+/// Count the running timer down in the background for as long as the app runs.
+///
+/// A timer which ran out while the app was closed expires on the first tick, before the
+/// frontend may be listening, so it should also ask for the timer once it has loaded.
+pub fn start_timer_ticker(app: AppHandle, pool: SqlitePool) {
+    thread::spawn(move || loop {
+        let tick = block_on(async {
+            let service = app.state::<TimerService>();
+            let mut timer = service.timer.lock().await;
+            if timer.status != TimerStatus::Running {
+                return Ok(None);
+            }
+
+            let now = UnixTimestamp::now();
+            if timer.remaining_at(now) > 0 {
+                return Ok(Some((TIMER_TICK_EVENT, timer.read_at(now))));
+            }
+
+            let mut expired = timer.clone();
+            expired.expire();
+            save_timer(&expired, &pool).await?;
+            *timer = expired;
+
+            anyhow::Ok(Some((TIMER_EXPIRED_EVENT, timer.read_at(now))))
+        });
+
+        match tick {
+            Ok(Some((event, timer))) => {
+                if let Err(e) = app.emit(event, timer) {
+                    println!("Unable to report the timer: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => println!("Unable to count the timer down: {}", e),
+        }
+
+        thread::sleep(TIMER_TICK_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub async fn get_timer(service: State<'_, TimerService>) -> TAResult<CountdownTimerRead> {
+    let timer = service.timer.lock().await;
+
+    Ok(timer.read_at(UnixTimestamp::now()))
+}
+
+/// Start the timer, or carry on from where it was paused.
+///
+/// ### Args
+/// * duration - Set the timer to this many seconds before starting it, otherwise it continues
+///   with the time it has left.
+/// * message - Shown when the timer runs out, left unchanged when not given.
+#[tauri::command]
+pub async fn start_timer(
+    duration: Option<i64>,
+    message: Option<String>,
+    service: State<'_, TimerService>,
+    db: State<'_, Data>,
+) -> TAResult<CountdownTimerRead> {
+    update_timer(&service, &db, |timer, now| {
+        if let Some(duration) = duration {
+            if duration <= 0 {
+                anyhow_tauri::bail!("The timer must be set to more than 0 seconds.");
+            }
+
+            timer.reset(duration);
+        }
+
+        if let Some(message) = message {
+            timer.message = message;
+        }
+
+        if timer.status == TimerStatus::Running {
+            return Ok(());
+        }
+
+        if timer.remaining_duration <= 0 {
+            anyhow_tauri::bail!("The timer has no time left, reset it before starting it again.");
+        }
+
+        timer.start(now);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn pause_timer(
+    service: State<'_, TimerService>,
+    db: State<'_, Data>,
+) -> TAResult<CountdownTimerRead> {
+    update_timer(&service, &db, |timer, now| {
+        if timer.status == TimerStatus::Running {
+            timer.pause(now);
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+/// Stop the timer with its full duration left.
+///
+/// ### Args
+/// * duration - Set the timer to this many seconds, otherwise it keeps the duration it had.
+/// * message - Shown when the timer runs out, left unchanged when not given.
+#[tauri::command]
+pub async fn reset_timer(
+    duration: Option<i64>,
+    message: Option<String>,
+    service: State<'_, TimerService>,
+    db: State<'_, Data>,
+) -> TAResult<CountdownTimerRead> {
+    update_timer(&service, &db, |timer, _| {
+        let duration = duration.unwrap_or(timer.initial_duration);
+        if duration < 0 {
+            anyhow_tauri::bail!("The timer cannot be set to less than 0 seconds.");
+        }
+
+        timer.reset(duration);
+
+        if let Some(message) = message {
+            timer.message = message;
+        }
+
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use tauri::async_runtime::Mutex;
+use timely_macros::EnumFromString;
+
+use crate::features::tasks::{OptionalUnixTimestamp, UnixTimestamp};
+
+/// How often a running timer reports the time it has left.
+pub const TIMER_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Emitted with the timer every tick while it runs.
+pub const TIMER_TICK_EVENT: &str = "timer-tick";
+
+/// Emitted with the timer once it runs out.
+pub const TIMER_EXPIRED_EVENT: &str = "timer-expired";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, sqlx::Type, EnumFromString)]
+#[sqlx(type_name = "TEXT")]
+pub enum TimerStatus {
+    /// The timer is set to its full duration and has not been started.
+    Stopped,
+    Running,
+    Paused,
+    /// The timer ran out and needs to be reset or given a new duration.
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct CountdownTimer {
+    pub id: i64,
+    /// The duration the timer was set to, in seconds.
+    pub initial_duration: i64,
+    /// The time left when the timer was last started or paused, in seconds.
+    pub remaining_duration: i64,
+    pub status: TimerStatus,
+    /// When the running timer was last started.
+    pub started_at: OptionalUnixTimestamp,
+    /// Shown to the user when the timer runs out.
+    pub message: String,
+}
+
+impl CountdownTimer {
+    /// The time the timer has left at a given moment, in seconds.
+    pub fn remaining_at(&self, now: UnixTimestamp) -> i64 {
+        let started_at: Option<UnixTimestamp> = self.started_at.into();
+
+        match (self.status, started_at) {
+            (TimerStatus::Running, Some(started_at)) => {
+                (self.remaining_duration - (now - started_at)).max(0)
+            }
+            _ => self.remaining_duration,
+        }
+    }
+
+    pub fn start(&mut self, now: UnixTimestamp) {
+        self.status = TimerStatus::Running;
+        self.started_at = Some(now).into();
+    }
+
+    pub fn pause(&mut self, now: UnixTimestamp) {
+        self.remaining_duration = self.remaining_at(now);
+        self.status = TimerStatus::Paused;
+        self.started_at = OptionalUnixTimestamp::none();
+    }
+
+    /// Stop the timer with the full duration left.
+    pub fn reset(&mut self, duration: i64) {
+        self.initial_duration = duration;
+        self.remaining_duration = duration;
+        self.status = TimerStatus::Stopped;
+        self.started_at = OptionalUnixTimestamp::none();
+    }
+
+    pub fn expire(&mut self) {
+        self.remaining_duration = 0;
+        self.status = TimerStatus::Expired;
+        self.started_at = OptionalUnixTimestamp::none();
+    }
+
+    pub fn read_at(&self, now: UnixTimestamp) -> CountdownTimerRead {
+        let remaining_duration = self.remaining_at(now);
+
+        CountdownTimerRead {
+            initial_duration: self.initial_duration,
+            remaining_duration,
+            status: self.status,
+            ends_at: match self.status {
+                TimerStatus::Running => {
+                    Some(UnixTimestamp::from(now.as_seconds() + remaining_duration).into())
+                }
+                _ => None,
+            },
+            message: self.message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountdownTimerRead {
+    pub initial_duration: i64,
+    pub remaining_duration: i64,
+    pub status: TimerStatus,
+    /// When the timer will run out, only while it is running.
+    pub ends_at: Option<Timestamp>,
+    pub message: String,
+}
+
+/// The countdown timer, kept in memory while the app runs and saved on every change.
+#[derive(Debug)]
+pub struct TimerService {
+    pub timer: Mutex<CountdownTimer>,
+}
+
+impl TimerService {
+    pub fn new(timer: CountdownTimer) -> Self {
+        Self {
+            timer: Mutex::new(timer),
+        }
+    }
+}
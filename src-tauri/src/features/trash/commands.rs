@@ -0,0 +1,456 @@
+use anyhow_tauri::{IntoTAResult, TAResult};
+use jiff::Timestamp;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use tauri::State;
+
+use crate::{
+    features::{
+        activity::{record_task_event, TaskEventDetails},
+        journal::JournalRecording,
+        tags::record_tagged_task_events,
+        tasks::UnixTimestamp,
+    },
+    Data,
+};
+
+use super::{TrashItem, TrashItemKind, TrashItemRef};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Every task nested beneath the given task which was trashed along with it, including itself.
+const TRASHED_WITH_TASK: &str = r#"
+    WITH RECURSIVE trashed(id) AS (
+        SELECT tasks.id
+        FROM tasks
+        WHERE tasks.id = ?
+        AND tasks.deleted_at = ?
+        UNION
+        SELECT tasks.id
+        FROM tasks
+        INNER JOIN trashed ON tasks.parent_task_id = trashed.id
+        WHERE tasks.deleted_at = ?
+    )
+"#;
+
+fn to_timestamp(seconds: i64) -> Timestamp {
+    UnixTimestamp::from(seconds).into()
+}
+
+/// List everything in the trash, most recently deleted first.
+///
+/// Subtasks which were trashed along with their parent are left out, they are restored and
+/// purged together with it.
+#[tauri::command]
+pub async fn get_trash(db: State<'_, Data>) -> TAResult<Vec<TrashItem>> {
+    let tasks = sqlx::query!(
+        r#"
+        SELECT tasks.id, tasks.title, tasks.deleted_at AS "deleted_at!: i64"
+        FROM tasks
+        WHERE tasks.deleted_at IS NOT NULL
+        AND NOT EXISTS (
+            SELECT 1
+            FROM tasks parent
+            WHERE parent.id = tasks.parent_task_id
+            AND parent.deleted_at = tasks.deleted_at
+        )
+    "#
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let tags = sqlx::query!(
+        r#"
+        SELECT tags.id, tags.value, tags.deleted_at AS "deleted_at!: i64"
+        FROM tags
+        WHERE tags.deleted_at IS NOT NULL
+    "#
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let comments = sqlx::query!(
+        r#"
+        SELECT comments.id, comments.task_id, comments.message, comments.deleted_at AS "deleted_at!: i64"
+        FROM comments
+        WHERE comments.deleted_at IS NOT NULL
+    "#
+    )
+    .fetch_all(&db.pool)
+    .await
+    .into_ta_result()?;
+
+    let mut items: Vec<TrashItem> = tasks
+        .into_iter()
+        .map(|task| TrashItem {
+            kind: TrashItemKind::Task,
+            id: task.id,
+            name: task.title,
+            task_id: None,
+            deleted_at: to_timestamp(task.deleted_at),
+        })
+        .chain(tags.into_iter().map(|tag| TrashItem {
+            kind: TrashItemKind::Tag,
+            id: tag.id,
+            name: tag.value,
+            task_id: None,
+            deleted_at: to_timestamp(tag.deleted_at),
+        }))
+        .chain(comments.into_iter().map(|comment| TrashItem {
+            kind: TrashItemKind::Comment,
+            id: comment.id,
+            name: comment.message,
+            task_id: Some(comment.task_id),
+            deleted_at: to_timestamp(comment.deleted_at),
+        }))
+        .collect();
+
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+
+    Ok(items)
+}
+
+/// Take the items back out of the trash.
+///
+/// A task comes back with the subtasks that were trashed along with it. When its parent is
+/// still in the trash it is restored as a top level task.
+#[tauri::command]
+pub async fn restore_from_trash(items: Vec<TrashItemRef>, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result = async {
+        let task_ids = restored_task_ids(&items, &mut transaction).await?;
+        let recording =
+            JournalRecording::start("Restore from trash", task_ids, &mut transaction).await?;
+
+        for item in items.iter() {
+            match item.kind {
+                TrashItemKind::Task => restore_task(item.id, &mut transaction).await?,
+                TrashItemKind::Tag => restore_tag(item.id, &mut transaction).await?,
+                TrashItemKind::Comment => restore_comment(item.id, &mut transaction).await?,
+            }
+        }
+
+        recording.finish(&mut transaction).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+/// The tasks which taking the items out of the trash changes, for the journal.
+///
+/// Restoring a tag changes no task, as the links to it are kept while it is in the trash.
+async fn restored_task_ids(
+    items: &[TrashItemRef],
+    connection: &mut SqliteConnection,
+) -> TAResult<Vec<i64>> {
+    let mut task_ids = Vec::new();
+
+    for item in items.iter() {
+        match item.kind {
+            TrashItemKind::Task => {
+                let deleted_at = sqlx::query_scalar!(
+                    r#"SELECT tasks.deleted_at AS "deleted_at?: i64" FROM tasks WHERE tasks.id = ?"#,
+                    item.id
+                )
+                .fetch_optional(&mut *connection)
+                .await
+                .into_ta_result()?
+                .flatten();
+
+                let Some(deleted_at) = deleted_at else {
+                    continue;
+                };
+
+                let trashed: Vec<i64> = sqlx::query_scalar(&format!(
+                    "{} SELECT trashed.id FROM trashed",
+                    TRASHED_WITH_TASK
+                ))
+                .bind(item.id)
+                .bind(deleted_at)
+                .bind(deleted_at)
+                .fetch_all(&mut *connection)
+                .await
+                .into_ta_result()?;
+
+                task_ids.extend(trashed);
+            }
+            TrashItemKind::Comment => {
+                let task_id = sqlx::query_scalar!(
+                    "SELECT comments.task_id FROM comments WHERE comments.id = ?",
+                    item.id
+                )
+                .fetch_optional(&mut *connection)
+                .await
+                .into_ta_result()?;
+
+                task_ids.extend(task_id);
+            }
+            TrashItemKind::Tag => {}
+        }
+    }
+
+    Ok(task_ids)
+}
+
+async fn find_task_deleted_at(
+    task_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<i64> {
+    let deleted_at = sqlx::query_scalar!(
+        r#"SELECT tasks.deleted_at AS "deleted_at!: i64" FROM tasks WHERE tasks.id = ? AND tasks.deleted_at IS NOT NULL"#,
+        task_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    match deleted_at {
+        Some(deleted_at) => Ok(deleted_at),
+        None => anyhow_tauri::bail!(format!("Task with id '{}' is not in the trash.", task_id)),
+    }
+}
+
+async fn restore_task(task_id: i64, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
+    let deleted_at = find_task_deleted_at(task_id, transaction).await?;
+
+    sqlx::query(&format!(
+        "{} UPDATE tasks SET deleted_at = NULL WHERE tasks.id IN (SELECT trashed.id FROM trashed)",
+        TRASHED_WITH_TASK
+    ))
+    .bind(task_id)
+    .bind(deleted_at)
+    .bind(deleted_at)
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET parent_task_id = NULL
+        WHERE tasks.id = ?
+        AND EXISTS (
+            SELECT 1
+            FROM tasks parent
+            WHERE parent.id = tasks.parent_task_id
+            AND parent.deleted_at IS NOT NULL
+        )
+    "#,
+        task_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+async fn restore_tag(tag_id: i64, transaction: &mut Transaction<'_, Sqlite>) -> TAResult<()> {
+    let tag = sqlx::query!(
+        "SELECT tags.value FROM tags WHERE tags.id = ? AND tags.deleted_at IS NOT NULL",
+        tag_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let Some(tag) = tag else {
+        anyhow_tauri::bail!(format!("Tag with id '{}' is not in the trash.", tag_id));
+    };
+
+    let duplicate = sqlx::query_scalar!(
+        "SELECT tags.id FROM tags WHERE tags.value = ? AND tags.deleted_at IS NULL",
+        tag.value
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    if duplicate.is_some() {
+        anyhow_tauri::bail!(format!(
+            "A tag named '{}' already exists, rename or delete it before restoring this one.",
+            tag.value
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE tags SET deleted_at = NULL WHERE tags.id = ?",
+        tag_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let event = TaskEventDetails::TagAdded {
+        tag_id,
+        tag: Some(tag.value),
+    };
+    record_tagged_task_events(tag_id, &event, "Restore from trash", transaction).await
+}
+
+async fn restore_comment(
+    comment_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> TAResult<()> {
+    let comment = sqlx::query!(
+        r#"
+        SELECT comments.task_id, tasks.deleted_at AS task_deleted_at
+        FROM comments
+        INNER JOIN tasks ON tasks.id = comments.task_id
+        WHERE comments.id = ?
+        AND comments.deleted_at IS NOT NULL
+    "#,
+        comment_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .into_ta_result()?;
+
+    let Some(comment) = comment else {
+        anyhow_tauri::bail!(format!(
+            "Comment with id '{}' is not in the trash.",
+            comment_id
+        ));
+    };
+
+    if comment.task_deleted_at.is_some() {
+        anyhow_tauri::bail!(format!(
+            "The task with id '{}' is in the trash, restore it before its comments.",
+            comment.task_id
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE comments SET deleted_at = NULL WHERE comments.id = ?",
+        comment_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map(|_| ())
+    .into_ta_result()
+}
+
+/// Permanently delete the items from the trash, along with everything that belongs to them.
+///
+/// The contents of attachments which nothing uses any more are deleted the next time the
+/// application starts.
+#[tauri::command]
+pub async fn purge_from_trash(items: Vec<TrashItemRef>, db: State<'_, Data>) -> TAResult<()> {
+    let mut transaction = db.pool.begin().await.into_ta_result()?;
+
+    let result = async {
+        for item in items.iter() {
+            match item.kind {
+                TrashItemKind::Task => {
+                    let deleted_at = find_task_deleted_at(item.id, &mut transaction).await?;
+
+                    sqlx::query(&format!(
+                        "{} DELETE FROM tasks WHERE tasks.id IN (SELECT trashed.id FROM trashed)",
+                        TRASHED_WITH_TASK
+                    ))
+                    .bind(item.id)
+                    .bind(deleted_at)
+                    .bind(deleted_at)
+                    .execute(&mut *transaction)
+                    .await
+                    .into_ta_result()?;
+                }
+                // The tasks were told the tag was removed when it went into the trash.
+                TrashItemKind::Tag => {
+                    sqlx::query!(
+                        "DELETE FROM tags WHERE tags.id = ? AND tags.deleted_at IS NOT NULL",
+                        item.id
+                    )
+                    .execute(&mut *transaction)
+                    .await
+                    .into_ta_result()?;
+                }
+                TrashItemKind::Comment => {
+                    let task_id = sqlx::query_scalar!(
+                        "DELETE FROM comments WHERE comments.id = ? AND comments.deleted_at IS NOT NULL RETURNING comments.task_id",
+                        item.id
+                    )
+                    .fetch_optional(&mut *transaction)
+                    .await
+                    .into_ta_result()?;
+
+                    if let Some(task_id) = task_id {
+                        let event = TaskEventDetails::CommentDeleted {
+                            comment_id: item.id,
+                        };
+                        let created = UnixTimestamp::now();
+                        record_task_event(
+                            task_id,
+                            &event,
+                            "Purge from trash",
+                            created,
+                            &mut *transaction,
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => transaction.commit().await.into_ta_result(),
+        Err(e) => {
+            transaction.rollback().await.into_ta_result()?;
+            Err(e)
+        }
+    }
+}
+
+/// Permanently delete everything in the trash.
+#[tauri::command]
+pub async fn empty_trash(db: State<'_, Data>) -> TAResult<()> {
+    purge_trash_deleted_before(i64::MAX, &db.pool)
+        .await
+        .into_ta_result()
+}
+
+/// Permanently delete whatever has been in the trash for longer than the retention period
+/// in the user settings. Run when the application starts.
+pub async fn purge_expired_trash(pool: &SqlitePool) -> anyhow::Result<()> {
+    let retention_days =
+        sqlx::query_scalar!("SELECT trash_retention_days FROM user_settings LIMIT 1")
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or(0);
+
+    if retention_days <= 0 {
+        return Ok(());
+    }
+
+    let cutoff = UnixTimestamp::now().as_seconds() - retention_days * SECONDS_PER_DAY;
+    purge_trash_deleted_before(cutoff, pool).await
+}
+
+async fn purge_trash_deleted_before(cutoff: i64, pool: &SqlitePool) -> anyhow::Result<()> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!("DELETE FROM tasks WHERE tasks.deleted_at < ?", cutoff)
+        .execute(&mut *transaction)
+        .await?;
+    sqlx::query!("DELETE FROM tags WHERE tags.deleted_at < ?", cutoff)
+        .execute(&mut *transaction)
+        .await?;
+    sqlx::query!("DELETE FROM comments WHERE comments.deleted_at < ?", cutoff)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
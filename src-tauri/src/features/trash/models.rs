@@ -0,0 +1,30 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of rows which are moved to the trash instead of being deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashItemKind {
+    Task,
+    Tag,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub kind: TrashItemKind,
+    pub id: i64,
+    /// The title of a task, the value of a tag or the message of a comment.
+    pub name: String,
+    /// The task a comment belongs to.
+    pub task_id: Option<i64>,
+    pub deleted_at: Timestamp,
+}
+
+/// Identifies an item in the trash to restore or purge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItemRef {
+    pub kind: TrashItemKind,
+    pub id: i64,
+}
@@ -20,6 +20,19 @@ pub fn run() {
                 std::fs::create_dir(&path).expect("Could not create application bundle location in data directory");
             }
             let pool = block_on(establish_connection_pool(path));
+            if let Err(e) = block_on(features::trash::purge_expired_trash(&pool)) {
+                println!("Unable to purge expired items from the trash: {}", e);
+            }
+            let attachment_store = features::attachments::attachment_store_path(&path);
+            if let Err(e) = block_on(features::attachments::collect_orphaned_attachments(&attachment_store, &pool)) {
+                println!("Unable to remove orphaned attachments: {}", e);
+            }
+            features::sessions::start_heartbeat(app.handle().clone(), pool.clone());
+            app.manage(features::idle::IdleTracker::default());
+            features::idle::start_idle_watcher(app.handle().clone(), pool.clone(), features::idle::SystemIdleSource);
+            let timer = block_on(features::timer::load_timer(&pool)).expect("The countdown timer is required to run this application.");
+            app.manage(features::timer::TimerService::new(timer));
+            features::timer::start_timer_ticker(app.handle().clone(), pool.clone());
             app.manage(Data { pool });
             Ok(())
         })
@@ -40,9 +53,30 @@ pub fn run() {
             features::tasks::add_comment,
             features::tasks::update_comment,
             features::tasks::delete_comment,
+            features::tasks::add_checklist_item,
+            features::tasks::edit_checklist_item,
+            features::tasks::set_checklist_item_checked,
+            features::tasks::delete_checklist_item,
+            features::tasks::reorder_checklist_items,
             features::tasks::add_task_work_history,
             features::tasks::edit_task_work_history,
             features::tasks::delete_task_work_history,
+            features::tasks::find_work_history_conflicts,
+            features::tasks::split_task_work_history,
+            features::tasks::merge_task_work_history,
+            features::tasks::move_task_work_history,
+            features::tasks::nest_task,
+            features::tasks::unnest_task,
+            features::tasks::add_task_dependency,
+            features::tasks::remove_task_dependency,
+            features::tasks::set_task_recurrence,
+            features::tasks::remove_task_recurrence,
+            features::tasks::link_tasks,
+            features::tasks::unlink_tasks,
+            features::tasks::create_follow_up_task,
+            features::tasks::search_tasks_advanced,
+            features::tasks::search_task_work_history_advanced,
+            features::tasks::get_next_tasks,
             features::tags::get_tags,
             features::tags::edit_tag,
             features::tags::delete_tag,
@@ -51,6 +85,50 @@ pub fn run() {
             features::tags::get_all_tags,
             features::tags::add_tag_to_task,
             features::tags::add_new_tag,
+            features::tags::search_tags_advanced,
+            features::saved_searches::get_saved_searches,
+            features::saved_searches::get_saved_search,
+            features::saved_searches::add_saved_search,
+            features::saved_searches::edit_saved_search,
+            features::saved_searches::delete_saved_search,
+            features::saved_searches::run_saved_search,
+            features::custom_fields::get_custom_fields,
+            features::custom_fields::add_custom_field,
+            features::custom_fields::edit_custom_field,
+            features::custom_fields::delete_custom_field,
+            features::projects::get_projects,
+            features::projects::get_project,
+            features::projects::add_project,
+            features::projects::edit_project,
+            features::projects::delete_project,
+            features::projects::set_task_project,
+            features::projects::get_project_summary,
+            features::trash::get_trash,
+            features::trash::restore_from_trash,
+            features::trash::purge_from_trash,
+            features::trash::empty_trash,
+            features::journal::undo_last,
+            features::journal::redo,
+            features::journal::get_journal,
+            features::activity::get_task_activity,
+            features::templates::get_task_templates,
+            features::templates::get_task_template,
+            features::templates::add_task_template,
+            features::templates::edit_task_template,
+            features::templates::delete_task_template,
+            features::templates::create_task_from_template,
+            features::attachments::attach_file,
+            features::attachments::get_task_attachments,
+            features::attachments::open_attachment,
+            features::attachments::remove_attachment,
+            features::sessions::get_stale_sessions,
+            features::sessions::resolve_stale_session,
+            features::idle::get_idle_span,
+            features::idle::resolve_idle_span,
+            features::timer::get_timer,
+            features::timer::start_timer,
+            features::timer::pause_timer,
+            features::timer::reset_timer,
             features::settings::get_user_settings,
             features::settings::update_user_settings,
             features::metrics::get_metrics,
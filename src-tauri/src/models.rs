@@ -1,5 +1,11 @@
+use anyhow::{anyhow, bail, Context};
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use sqlx::{QueryBuilder, Sqlite};
+
+/// The most items a generic query may return in a single page.
+pub const MAX_PAGE_SIZE: u64 = 1_000;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -96,3 +102,473 @@ pub enum FilterOption {
     Any,
     All,
 }
+
+/// How the values of a filterable field are parsed and compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Integer,
+    /// Compared as unix seconds, given as RFC 3339 timestamps such as `2024-12-01T08:00:00Z`.
+    Timestamp,
+}
+
+/// A field which generic queries are allowed to filter and sort on, and the SQL column behind it.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterableField {
+    pub name: &'static str,
+    pub column: &'static str,
+    pub kind: FieldKind,
+    /// For fields with many values per row, a correlated subquery ending in `AND` that the
+    /// condition is appended to. Such a condition matches when any of the values match it,
+    /// negated operators match when none of the values match the positive operator.
+    pub subquery: Option<&'static str>,
+}
+
+impl FilterableField {
+    pub const fn new(name: &'static str, column: &'static str, kind: FieldKind) -> Self {
+        Self {
+            name,
+            column,
+            kind,
+            subquery: None,
+        }
+    }
+
+    pub const fn many(
+        name: &'static str,
+        column: &'static str,
+        kind: FieldKind,
+        subquery: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            column,
+            kind,
+            subquery: Some(subquery),
+        }
+    }
+
+    /// Bind the value after converting it to the type of the field.
+    fn push_value(
+        &self,
+        builder: &mut QueryBuilder<'_, Sqlite>,
+        value: &str,
+    ) -> anyhow::Result<()> {
+        match self.kind {
+            FieldKind::Text => {
+                builder.push_bind(value.to_string());
+            }
+            FieldKind::Integer => {
+                let value: i64 = value
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("'{}' is not a number for '{}'.", value, self.name))?;
+                builder.push_bind(value);
+            }
+            FieldKind::Timestamp => {
+                let value: Timestamp = value.trim().parse().with_context(|| {
+                    format!("'{}' is not a timestamp for '{}'.", value, self.name)
+                })?;
+                builder.push_bind(value.as_second());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn find_field<'f>(
+    fields: &'f [FilterableField],
+    name: &str,
+) -> anyhow::Result<&'f FilterableField> {
+    fields
+        .iter()
+        .find(|field| field.name == name)
+        .ok_or_else(|| anyhow!("Filtering or sorting on '{}' is not allowed.", name))
+}
+
+impl Operator {
+    /// Split a negated operator into its positive form, e.g. `NotIn` into `In`.
+    fn split_negation(&self) -> (bool, Operator) {
+        match self {
+            Operator::NotContains => (true, Operator::Contains),
+            Operator::NotLike => (true, Operator::Like),
+            Operator::NotEquals => (true, Operator::Equals),
+            Operator::NotIn => (true, Operator::In),
+            operator => (false, operator.clone()),
+        }
+    }
+}
+
+/// Escape the wildcards in a value used inside a `LIKE` pattern with `ESCAPE '\'`.
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl QueryCondition {
+    fn push_sql(
+        &self,
+        builder: &mut QueryBuilder<'_, Sqlite>,
+        fields: &[FilterableField],
+    ) -> anyhow::Result<()> {
+        let field = find_field(fields, &self.field)?;
+
+        match field.subquery {
+            Some(subquery) => {
+                let (negated, operator) = self.operator.split_negation();
+
+                builder.push(match negated {
+                    true => "NOT EXISTS (",
+                    false => "EXISTS (",
+                });
+                builder.push(subquery);
+                builder.push(" ");
+                push_comparison(builder, field, &operator, &self.condition)?;
+                builder.push(")");
+            }
+            None => push_comparison(builder, field, &self.operator, &self.condition)?,
+        }
+
+        Ok(())
+    }
+}
+
+fn push_comparison(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    field: &FilterableField,
+    operator: &Operator,
+    condition: &QueryConditionType,
+) -> anyhow::Result<()> {
+    let column = field.column;
+
+    match (operator, condition) {
+        (Operator::In | Operator::NotIn, QueryConditionType::Multiple(values)) => {
+            if values.is_empty() {
+                // Nothing is in an empty list, and everything is outside of it.
+                builder.push(match operator {
+                    Operator::In => "1=0",
+                    _ => "1=1",
+                });
+                return Ok(());
+            }
+
+            builder.push(column);
+            builder.push(match operator {
+                Operator::In => " IN (",
+                _ => " NOT IN (",
+            });
+
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    builder.push(", ");
+                }
+                field.push_value(builder, value)?;
+            }
+
+            builder.push(")");
+        }
+        (Operator::In | Operator::NotIn, QueryConditionType::Single(_)) => {
+            bail!(
+                "'{:?}' on '{}' requires multiple values.",
+                operator,
+                field.name
+            )
+        }
+        (_, QueryConditionType::Multiple(_)) => {
+            bail!(
+                "'{:?}' on '{}' requires a single value.",
+                operator,
+                field.name
+            )
+        }
+        (
+            Operator::StartsWith
+            | Operator::EndsWith
+            | Operator::Contains
+            | Operator::NotContains
+            | Operator::Like
+            | Operator::NotLike,
+            QueryConditionType::Single(_),
+        ) if field.kind != FieldKind::Text => {
+            bail!(
+                "'{:?}' can only be used on text, not on '{}'.",
+                operator,
+                field.name
+            )
+        }
+        (Operator::Like | Operator::NotLike, QueryConditionType::Single(value)) => {
+            builder.push(column);
+            builder.push(match operator {
+                Operator::Like => " LIKE ",
+                _ => " NOT LIKE ",
+            });
+            builder.push_bind(value.clone());
+        }
+        (
+            Operator::StartsWith | Operator::EndsWith | Operator::Contains | Operator::NotContains,
+            QueryConditionType::Single(value),
+        ) => {
+            let value = escape_like(value);
+            let pattern = match operator {
+                Operator::StartsWith => format!("{}%", value),
+                Operator::EndsWith => format!("%{}", value),
+                _ => format!("%{}%", value),
+            };
+
+            builder.push(column);
+            builder.push(match operator {
+                Operator::NotContains => " NOT LIKE ",
+                _ => " LIKE ",
+            });
+            builder.push_bind(pattern);
+            builder.push(" ESCAPE '\\'");
+        }
+        (Operator::Equals, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " = ", value)?
+        }
+        (Operator::NotEquals, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " <> ", value)?
+        }
+        (Operator::GreaterThan, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " > ", value)?
+        }
+        (Operator::GreaterThanOrEqual, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " >= ", value)?
+        }
+        (Operator::LessThan, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " < ", value)?
+        }
+        (Operator::LessThanOrEqual, QueryConditionType::Single(value)) => {
+            push_binary_comparison(builder, field, " <= ", value)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare the column of the field with a single value using the given SQL operator.
+fn push_binary_comparison(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    field: &FilterableField,
+    sql_operator: &str,
+    value: &str,
+) -> anyhow::Result<()> {
+    builder.push(field.column);
+    builder.push(sql_operator);
+    field.push_value(builder, value)
+}
+
+/// Join the items with `AND` or `OR` inside parentheses.
+///
+/// An empty `and` group matches everything and an empty `or` group matches nothing.
+fn push_group<T>(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    operation_type: &str,
+    items: &[T],
+    mut push_item: impl FnMut(&mut QueryBuilder<'_, Sqlite>, &T) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let (separator, empty) = match operation_type.to_lowercase().as_str() {
+        "and" => (" AND ", "1=1"),
+        "or" => (" OR ", "1=0"),
+        _ => bail!(
+            "Unknown operation type '{}', expected 'and' or 'or'.",
+            operation_type
+        ),
+    };
+
+    if items.is_empty() {
+        builder.push(empty);
+        return Ok(());
+    }
+
+    builder.push("(");
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            builder.push(separator);
+        }
+        push_item(builder, item)?;
+    }
+    builder.push(")");
+
+    Ok(())
+}
+
+impl QueryExpression {
+    fn push_sql(
+        &self,
+        builder: &mut QueryBuilder<'_, Sqlite>,
+        fields: &[FilterableField],
+    ) -> anyhow::Result<()> {
+        push_group(
+            builder,
+            &self.operation_type,
+            &self.operations,
+            |builder, operation| match operation {
+                QueryOperation::Condition(condition) => condition.push_sql(builder, fields),
+                QueryOperation::Expression(expression) => expression.push_sql(builder, fields),
+            },
+        )
+    }
+}
+
+impl Query {
+    /// Append the filters of the query as a single boolean expression, with every value bound
+    /// as a parameter. Only the given fields may be used.
+    pub fn push_conditions(
+        &self,
+        builder: &mut QueryBuilder<'_, Sqlite>,
+        fields: &[FilterableField],
+    ) -> anyhow::Result<()> {
+        push_group(
+            builder,
+            &self.operation_type,
+            &self.expressions,
+            |builder, expression| expression.push_sql(builder, fields),
+        )
+    }
+
+    /// Append the `ORDER BY` for the query, if it has one.
+    pub fn push_ordering(
+        &self,
+        builder: &mut QueryBuilder<'_, Sqlite>,
+        fields: &[FilterableField],
+    ) -> anyhow::Result<()> {
+        if self.ordering.order_by.is_empty() {
+            return Ok(());
+        }
+
+        let field = find_field(fields, &self.ordering.order_by)?;
+
+        if field.subquery.is_some() {
+            bail!("Sorting on '{}' is not allowed.", field.name);
+        }
+
+        let direction = match self.ordering.sort_direction {
+            SortDirection::Ascending => "ASC",
+            SortDirection::Descending => "DESC",
+        };
+
+        match field.kind {
+            FieldKind::Text => {
+                builder.push(format!(" ORDER BY LOWER({}) {}", field.column, direction))
+            }
+            _ => builder.push(format!(
+                " ORDER BY {} IS NULL {}, {} {}",
+                field.column, direction, field.column, direction
+            )),
+        };
+
+        Ok(())
+    }
+
+    /// Append the `LIMIT` and `OFFSET` for the page of the query.
+    ///
+    /// Fails when the page size is outside of `1..=MAX_PAGE_SIZE` or the page is too far out
+    /// for its offset to be represented.
+    pub fn push_paging(&self, builder: &mut QueryBuilder<'_, Sqlite>) -> anyhow::Result<()> {
+        if !(1..=MAX_PAGE_SIZE).contains(&self.page_size) {
+            bail!(
+                "The page size must be between 1 and {}, not {}.",
+                MAX_PAGE_SIZE,
+                self.page_size
+            );
+        }
+
+        let offset = (self.page.max(1) - 1)
+            .checked_mul(self.page_size)
+            .and_then(|offset| i64::try_from(offset).ok())
+            .ok_or_else(|| anyhow!("Page {} is out of range.", self.page))?;
+
+        builder.push(format!(" LIMIT {} OFFSET {}", self.page_size, offset));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[FilterableField] = &[FilterableField::new(
+        "estimate",
+        "tasks.estimated_duration",
+        FieldKind::Integer,
+    )];
+
+    fn query(page: u64, page_size: u64, operations: Vec<QueryOperation>) -> Query {
+        Query {
+            operation_type: "and".to_string(),
+            expressions: vec![QueryExpression {
+                operation_type: "and".to_string(),
+                operations,
+            }],
+            page,
+            page_size,
+            ordering: Ordering {
+                order_by: String::new(),
+                sort_direction: SortDirection::Ascending,
+            },
+        }
+    }
+
+    fn paging_sql(page: u64, page_size: u64) -> anyhow::Result<String> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT 1");
+        query(page, page_size, Vec::new()).push_paging(&mut builder)?;
+
+        Ok(builder.sql().to_string())
+    }
+
+    #[test]
+    fn push_paging_offsets_by_whole_pages() {
+        assert_eq!(paging_sql(0, 20).unwrap(), "SELECT 1 LIMIT 20 OFFSET 0");
+        assert_eq!(paging_sql(1, 20).unwrap(), "SELECT 1 LIMIT 20 OFFSET 0");
+        assert_eq!(paging_sql(3, 20).unwrap(), "SELECT 1 LIMIT 20 OFFSET 40");
+    }
+
+    #[test]
+    fn push_paging_rejects_page_sizes_out_of_range() {
+        assert!(paging_sql(1, 0).is_err());
+        assert!(paging_sql(1, MAX_PAGE_SIZE).is_ok());
+        assert!(paging_sql(1, MAX_PAGE_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn push_paging_rejects_pages_which_overflow_the_offset() {
+        assert!(paging_sql(u64::MAX, MAX_PAGE_SIZE).is_err());
+        assert!(paging_sql(u64::MAX / 2, 10).is_err());
+    }
+
+    #[test]
+    fn push_conditions_uses_the_sql_operator_of_each_comparison() {
+        let cases = [
+            (Operator::Equals, "="),
+            (Operator::NotEquals, "<>"),
+            (Operator::GreaterThan, ">"),
+            (Operator::GreaterThanOrEqual, ">="),
+            (Operator::LessThan, "<"),
+            (Operator::LessThanOrEqual, "<="),
+        ];
+
+        for (operator, sql_operator) in cases {
+            let condition = QueryOperation::Condition(QueryCondition {
+                field: "estimate".to_string(),
+                operator: operator.clone(),
+                condition: QueryConditionType::Single("60".to_string()),
+            });
+
+            let mut builder = QueryBuilder::<Sqlite>::new("");
+            query(1, 10, vec![condition])
+                .push_conditions(&mut builder, FIELDS)
+                .unwrap();
+
+            assert_eq!(
+                builder.sql(),
+                format!("((tasks.estimated_duration {} ?))", sql_operator),
+                "{:?}",
+                operator
+            );
+        }
+    }
+}
@@ -18,3 +18,33 @@ where
 
     builder.push(") ");
 }
+
+
+/// Convert free text from the user into an FTS5 match expression.
+///
+/// Each word is quoted so that FTS5 syntax characters in the text are matched literally, and is
+/// treated as a prefix so that results show up while the user is still typing. Text inside double
+/// quotes is matched as an exact phrase.
+/// Returns `None` when there are no words to search for.
+pub fn to_fts_match_expression(text: &str) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for (i, part) in text.split('"').enumerate() {
+        // Every other part sits between a pair of quotes.
+        let is_phrase = i % 2 == 1;
+
+        if is_phrase && !part.trim().is_empty() {
+            terms.push(format!("\"{}\"", part));
+        } else if !is_phrase {
+            terms.extend(
+                part.split_whitespace()
+                    .map(|term| format!("\"{}\"*", term)),
+            );
+        }
+    }
+
+    match terms.is_empty() {
+        true => None,
+        false => Some(terms.join(" ")),
+    }
+}
@@ -0,0 +1,82 @@
+import { Group, Select, Stack, Text } from "@mantine/core";
+import dayjs from "dayjs";
+import { useState } from "react";
+import StyledButton from "../../components/StyledButton";
+import { TaskStatus } from "../../models/TaskStatus";
+import { TimeSpan } from "../../models/TimeSpan";
+import { IdleSpan } from "../../models/ZodModels";
+import { useSearchTasks } from "../tasks/services/tasksService";
+import { taskSearchParams } from "../tasks/types/TaskSearchParams";
+import { IdleResolution } from "./services/idleService";
+
+export interface IdleSpanPromptProps {
+  span: IdleSpan;
+  onResolve: (resolution: IdleResolution) => void;
+}
+
+/** Asks whether the time the user was away counts as work, and on which task. */
+function IdleSpanPrompt({ span, onResolve }: IdleSpanPromptProps) {
+  const [taskId, setTaskId] = useState<string | null>(null);
+  const { data: tasks } = useSearchTasks(
+    taskSearchParams(
+      1,
+      1000,
+      [TaskStatus.Todo, TaskStatus.Doing, TaskStatus.Paused],
+      null,
+      "title",
+      "asc",
+      null,
+      null,
+      null
+    )
+  );
+
+  const taskTitles = span.sessions.map((session) => `"${session.taskTitle}"`);
+
+  return (
+    <Stack>
+      <Text>
+        You were away for {TimeSpan.fromSeconds(span.duration).toString()}{" "}
+        since {dayjs(span.start).format("MM/DD/YYYY hh:mm:ss A")}, so{" "}
+        {taskTitles.join(", ")} {taskTitles.length === 1 ? "was" : "were"}{" "}
+        paused. What should happen to that time?
+      </Text>
+      <Group justify="flex-end">
+        <StyledButton
+          label="Keep"
+          tooltipLabel="Count the time as work on the paused tasks"
+          onClick={() => onResolve({ type: "keep" })}
+        />
+        <StyledButton
+          label="Discard"
+          tooltipLabel="Leave the time out, the tasks stay paused"
+          onClick={() => onResolve({ type: "discard" })}
+        />
+      </Group>
+      <Group align="flex-end">
+        <Select
+          label="Another Task"
+          placeholder="Pick a task"
+          data={tasks.data.map((task) => ({
+            value: task.id.toString(),
+            label: task.title,
+          }))}
+          value={taskId}
+          onChange={setTaskId}
+          searchable
+          style={{ flex: 1 }}
+        />
+        <StyledButton
+          label="Reassign"
+          tooltipLabel="Count the time as work on the chosen task"
+          disabled={taskId === null}
+          onClick={() =>
+            onResolve({ type: "reassign", taskId: Number(taskId) })
+          }
+        />
+      </Group>
+    </Stack>
+  );
+}
+
+export default IdleSpanPrompt;
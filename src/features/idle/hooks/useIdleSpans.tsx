@@ -0,0 +1,70 @@
+import { modals } from "@mantine/modals";
+import { useQueryClient } from "@tanstack/react-query";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { IdleSpan } from "../../../models/ZodModels";
+import {
+  showErrorNotification,
+  showInfoNotification,
+} from "../../../utilities/notificationUtilities";
+import IdleSpanPrompt from "../IdleSpanPrompt";
+import {
+  getIdleSpan,
+  IdleResolution,
+  resolveIdleSpan,
+} from "../services/idleService";
+
+const modalId = "idle-span";
+
+/** Tell the user when their tasks are paused for inactivity and ask about the time once back. */
+const useIdleSpans = () => {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    const resolve = async (resolution: IdleResolution) => {
+      try {
+        await resolveIdleSpan(resolution);
+        modals.close(modalId);
+        await queryClient.invalidateQueries({ queryKey: ["searchTasks"] });
+      } catch (error) {
+        showErrorNotification(String(error));
+      }
+    };
+
+    // Only the latest idle span is kept, so a newer one replaces any prompt still open.
+    const showSpan = (span: IdleSpan) => {
+      modals.close(modalId);
+      modals.open({
+        modalId,
+        title: "Welcome Back",
+        closeOnClickOutside: false,
+        children: <IdleSpanPrompt span={span} onResolve={resolve} />,
+      });
+    };
+
+    const unlistenStarted = listen("idle-started", async (event) => {
+      const span = IdleSpan.parse(event.payload);
+      showInfoNotification(
+        `Paused ${span.sessions.length} task(s) as you seem to be away.`
+      );
+      await queryClient.invalidateQueries({ queryKey: ["searchTasks"] });
+    });
+    const unlistenEnded = listen("idle-ended", (event) => {
+      showSpan(IdleSpan.parse(event.payload));
+    });
+
+    // The user may have come back before the window started listening.
+    getIdleSpan()
+      .then((span) => {
+        if (span !== null) showSpan(span);
+      })
+      .catch((error) => showErrorNotification(String(error)));
+
+    return () => {
+      unlistenStarted.then((unlisten) => unlisten());
+      unlistenEnded.then((unlisten) => unlisten());
+    };
+  }, []);
+};
+
+export default useIdleSpans;
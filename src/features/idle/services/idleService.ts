@@ -0,0 +1,17 @@
+import { invoke } from "@tauri-apps/api/core";
+import { IdleSpan } from "../../../models/ZodModels";
+
+/** What to do with the time the user was away. */
+export type IdleResolution =
+  | { type: "keep" }
+  | { type: "discard" }
+  | { type: "reassign"; taskId: number };
+
+/** Fetch the idle span the user has not decided what to do with yet. */
+export async function getIdleSpan(): Promise<IdleSpan | null> {
+  return IdleSpan.nullable().parse(await invoke("get_idle_span"));
+}
+
+export async function resolveIdleSpan(resolution: IdleResolution) {
+  await invoke("resolve_idle_span", { resolution });
+}
@@ -0,0 +1,102 @@
+import { Group, Stack, Text } from "@mantine/core";
+import { modals } from "@mantine/modals";
+import { useQueryClient } from "@tanstack/react-query";
+import { listen } from "@tauri-apps/api/event";
+import dayjs from "dayjs";
+import { useEffect } from "react";
+import StyledButton from "../../../components/StyledButton";
+import { TimeSpan } from "../../../models/TimeSpan";
+import { StaleSession } from "../../../models/ZodModels";
+import { showErrorNotification } from "../../../utilities/notificationUtilities";
+import {
+  getStaleSessions,
+  resolveStaleSession,
+  StaleSessionResolution,
+} from "../services/sessionService";
+
+const modalId = (historyId: number) => `stale-session-${historyId}`;
+
+/** Ask what to do with each session which was left running while the app was closed or asleep. */
+const useStaleSessions = () => {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    let shownIds: number[] = [];
+
+    const resolve = async (
+      session: StaleSession,
+      resolution: StaleSessionResolution
+    ) => {
+      try {
+        await resolveStaleSession(session.historyId, resolution);
+        modals.close(modalId(session.historyId));
+        await queryClient.invalidateQueries({ queryKey: ["searchTasks"] });
+      } catch (error) {
+        showErrorNotification(String(error));
+      }
+    };
+
+    const showSessions = (sessions: StaleSession[]) => {
+      // Sessions which are no longer stale have been dealt with elsewhere.
+      for (const historyId of shownIds) {
+        if (!sessions.some((session) => session.historyId === historyId)) {
+          modals.close(modalId(historyId));
+        }
+      }
+
+      for (const session of sessions) {
+        if (shownIds.includes(session.historyId)) continue;
+
+        modals.open({
+          modalId: modalId(session.historyId),
+          title: "Session Left Running",
+          closeOnClickOutside: false,
+          children: (
+            <Stack>
+              <Text>
+                "{session.taskTitle}" was last seen running at{" "}
+                {dayjs(session.lastHeartbeat).format("MM/DD/YYYY hh:mm:ss A")},{" "}
+                {TimeSpan.fromSeconds(session.unaccountedDuration).toString()}{" "}
+                ago. What should happen to the time since then?
+              </Text>
+              <Group justify="flex-end">
+                <StyledButton
+                  label="Stop There"
+                  tooltipLabel="End the session when it was last seen and pause the task"
+                  onClick={() => resolve(session, "closeAtLastHeartbeat")}
+                />
+                <StyledButton
+                  label="Keep"
+                  tooltipLabel="Count the time and keep the session running"
+                  onClick={() => resolve(session, "keep")}
+                />
+                <StyledButton
+                  label="Discard"
+                  tooltipLabel="Delete the whole session and pause the task"
+                  onClick={() => resolve(session, "discard")}
+                />
+              </Group>
+            </Stack>
+          ),
+        });
+      }
+
+      shownIds = sessions.map((session) => session.historyId);
+    };
+
+    const unlisten = listen("stale-sessions-detected", (event) => {
+      showSessions(StaleSession.array().parse(event.payload));
+    });
+
+    // The sessions may have been found before the window started listening.
+    getStaleSessions()
+      .then(showSessions)
+      .catch((error) => showErrorNotification(String(error)));
+
+    return () => {
+      unlisten.then((unlisten) => unlisten());
+    };
+  }, []);
+};
+
+export default useStaleSessions;
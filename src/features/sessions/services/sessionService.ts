@@ -0,0 +1,17 @@
+import { invoke } from "@tauri-apps/api/core";
+import { StaleSession } from "../../../models/ZodModels";
+
+/** What to do with a session which was left running while the app was not. */
+export type StaleSessionResolution = "closeAtLastHeartbeat" | "keep" | "discard";
+
+/** Fetch the running sessions which have not had a heartbeat for a while. */
+export async function getStaleSessions(): Promise<StaleSession[]> {
+  return StaleSession.array().parse(await invoke("get_stale_sessions"));
+}
+
+export async function resolveStaleSession(
+  historyId: number,
+  resolution: StaleSessionResolution
+) {
+  await invoke("resolve_stale_session", { historyId, resolution });
+}
@@ -98,6 +98,9 @@ function Settings() {
     gradientTo: string;
     gradientDegrees?: number;
     navbarOpened: string;
+    allowConcurrentTimers: string;
+    trashRetentionDays: string;
+    idleTimeout: string;
     defaultTimerHours: string;
     defaultTimerMinutes: string;
     defaultTimerSeconds: string;
@@ -114,6 +117,9 @@ function Settings() {
       gradientTo: colorPalette.gradient.to,
       gradientDegrees: colorPalette.gradient.deg,
       navbarOpened: userSettings.navbarOpened.toString(),
+      allowConcurrentTimers: userSettings.allowConcurrentTimers.toString(),
+      trashRetentionDays: userSettings.trashRetentionDays.toString(),
+      idleTimeout: userSettings.idleTimeout.toString(),
       defaultTimerHours: TimeSpan.fromSeconds(
         userSettings.defaultTimer
       ).hours.toString(),
@@ -132,6 +138,9 @@ function Settings() {
       gradientTo: false,
       gradientDegrees: false,
       navbarOpened: false,
+      allowConcurrentTimers: false,
+      trashRetentionDays: false,
+      idleTimeout: false,
       defaultTimerSeconds: false,
       defaultTimerHours: false,
       defaultTimerMinutes: false,
@@ -144,6 +153,9 @@ function Settings() {
       gradientTo: false,
       gradientDegrees: false,
       navbarOpened: false,
+      allowConcurrentTimers: false,
+      trashRetentionDays: false,
+      idleTimeout: false,
       defaultTimerSeconds: false,
       defaultTimerHours: false,
       defaultTimerMinutes: false,
@@ -158,6 +170,9 @@ function Settings() {
       gradientTo,
       gradientDegrees: gradientDegrees ?? 0,
       navbarOpened: settings.navbarOpened === "true",
+      allowConcurrentTimers: settings.allowConcurrentTimers === "true",
+      trashRetentionDays: Number(settings.trashRetentionDays),
+      idleTimeout: Number(settings.idleTimeout),
       notificationSettings: notificationSettings,
       defaultTimer: TimeSpan.add(
         TimeSpan.fromHours(Number(settings.defaultTimerHours)),
@@ -311,6 +326,42 @@ function Settings() {
                     allowDeselect={false}
                   />
                 </Grid.Col>
+                <Grid.Col span={12}>
+                  <Select
+                    label="Allow Multiple Tasks In Progress"
+                    data={toSelectOptions([true, false], ["True", "False"])}
+                    {...form.getInputProps("allowConcurrentTimers")}
+                    allowDeselect={false}
+                  />
+                </Grid.Col>
+                <Grid.Col span={12}>
+                  <Select
+                    label="Empty Trash After"
+                    data={toSelectOptions(
+                      [7, 30, 90, 365, 0],
+                      ["7 Days", "30 Days", "90 Days", "1 Year", "Never"]
+                    )}
+                    {...form.getInputProps("trashRetentionDays")}
+                    allowDeselect={false}
+                  />
+                </Grid.Col>
+                <Grid.Col span={12}>
+                  <Select
+                    label="Pause Tasks When Idle For"
+                    data={toSelectOptions(
+                      [60, 300, 600, 1800, 0],
+                      [
+                        "1 Minute",
+                        "5 Minutes",
+                        "10 Minutes",
+                        "30 Minutes",
+                        "Never",
+                      ]
+                    )}
+                    {...form.getInputProps("idleTimeout")}
+                    allowDeselect={false}
+                  />
+                </Grid.Col>
                 <Grid.Col span={4}>
                   <Select
                     label="Timer Hours"
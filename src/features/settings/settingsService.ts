@@ -19,6 +19,9 @@ const defaultUserSettings = {
   navbarOpened: true,
   notificationSettings: [],
   defaultTimer: 0,
+  allowConcurrentTimers: false,
+  trashRetentionDays: 30,
+  idleTimeout: 300,
 };
 
 /** Determine if the user settings have actually been gotten from the database yet. */
@@ -9,6 +9,7 @@ import { TimelyAction } from "../../../models/TauriAction";
 import { PagedTaskData, Task, UserSettings } from "../../../models/ZodModels";
 import {
   showErrorNotification,
+  showInfoNotification,
   showSuccessNotification,
 } from "../../../utilities/notificationUtilities";
 import { EditTask, NewTask } from "../types/Task";
@@ -176,15 +177,20 @@ export function useFinishTask(
   queryClient: QueryClient
 ) {
   return useMutation({
-    mutationFn: async (task: Task) => {
-      await invoke("finish_task", { taskId: task.id });
+    mutationFn: async (task: Task): Promise<number[]> => {
+      return await invoke("finish_task", { taskId: task.id });
     },
-    onSuccess: async () => {
+    onSuccess: async (openSubtaskIds) => {
       showSuccessNotification(
         TimelyAction.FinishTask,
         userSettings,
         "Task finished successfully."
       );
+      if (openSubtaskIds.length > 0) {
+        showInfoNotification(
+          `The task still has ${openSubtaskIds.length} open subtask(s).`
+        );
+      }
       await queryClient.invalidateQueries({ queryKey: ["searchTasks"] });
     },
     onError: (error) => {
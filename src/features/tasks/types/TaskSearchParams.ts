@@ -22,6 +22,9 @@ export enum FilterName {
   Planned = "planned",
   Overdue = "overdue",
   LateStart = "lateStart",
+  OverdueChecklist = "overdueChecklist",
+  Blocked = "blocked",
+  Ready = "ready",
 }
 
 export class QuickFilter {
@@ -63,6 +66,8 @@ export interface TaskSearchParams {
   startByFilter: DateRangeFilter | null;
   dueByFilter: DateRangeFilter | null;
   quickFilter: TaggedFilter | string | null;
+  /** Text from the search box, written in the task query language e.g. `tag:work due:<friday`. */
+  searchQuery: string | null;
 }
 
 export function taskSearchParams(
@@ -85,7 +90,8 @@ export function taskSearchParams(
       sortDirection ?? "asc"
     ).serialize(),
     quickFilter: quickFilter?.serialize() ?? null,
-    queryString: !!queryString && queryString.length > 0 ? queryString : null,
+    queryString: null,
+    searchQuery: !!queryString && queryString.length > 0 ? queryString : null,
     startByFilter: startByFilter ?? null,
     dueByFilter: dueByFilter ?? null,
   };
@@ -1,18 +1,15 @@
+import { listen } from "@tauri-apps/api/event";
 import { useEffect, useMemo, useState } from "react";
 import useColorPalette from "../../../hooks/useColorPalette";
+import { CountdownTimer } from "../../../models/ZodModels";
 import { showTimerNotification } from "../../../utilities/notificationUtilities";
-import { useUserSettings } from "../../settings/settingsService";
-import { useTimerStore } from "../services/timerService";
+import { loadTimer, useTimerStore } from "../services/timerService";
 
 const useGlobalTimer = () => {
-  const time = useTimerStore((store) => store.time);
-  const isActive = useTimerStore((store) => store.isActive);
-  const message = useTimerStore((store) => store.message);
   const playing = useTimerStore((store) => store.playingSound);
-  const decrementTime = useTimerStore((store) => store.decrementTime);
+  const applyTimer = useTimerStore((store) => store.applyTimer);
   const setIsPlaying = useTimerStore((store) => store.setIsPlaying);
   const resetTimer = useTimerStore((store) => store.resetTimer);
-  const { data: userSettings } = useUserSettings();
   const colorPalette = useColorPalette();
 
   // Reference to persist the Audio object across renders
@@ -48,22 +45,35 @@ const useGlobalTimer = () => {
   }, [playing, sound]);
 
   useEffect(() => {
-    if (!isActive) return;
+    // The backend counts the timer down, so it keeps running across reloads.
+    const onExpired = (timer: CountdownTimer) => {
+      if (useTimerStore.getState().playingSound) return;
 
-    const intervalId = setInterval(() => {
-      decrementTime();
-    }, 1000);
-
-    if (time === 0) {
       setIsPlaying(true);
-      showTimerNotification(colorPalette, message, resetTimer);
-      clearInterval(intervalId);
-    }
+      showTimerNotification(colorPalette, timer.message, resetTimer);
+    };
+
+    const unlistenTick = listen("timer-tick", (event) => {
+      applyTimer(CountdownTimer.parse(event.payload));
+    });
+    const unlistenExpired = listen("timer-expired", (event) => {
+      const timer = CountdownTimer.parse(event.payload);
+      applyTimer(timer);
+      onExpired(timer);
+    });
+
+    // The timer may have run out while the app was closed.
+    loadTimer().then((timer) => {
+      if (timer?.status === "Expired") {
+        onExpired(timer);
+      }
+    });
 
     return () => {
-      clearInterval(intervalId);
+      unlistenTick.then((unlisten) => unlisten());
+      unlistenExpired.then((unlisten) => unlisten());
     };
-  }, [time, isActive, userSettings]);
+  }, []);
 };
 
 export default useGlobalTimer;
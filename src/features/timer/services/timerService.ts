@@ -1,10 +1,13 @@
+import { invoke, InvokeArgs } from "@tauri-apps/api/core";
 import { create } from "zustand";
 import { TimeSpan } from "../../../models/TimeSpan";
-import { showInfoNotification } from "../../../utilities/notificationUtilities";
+import { CountdownTimer } from "../../../models/ZodModels";
+import { showErrorNotification } from "../../../utilities/notificationUtilities";
 
 export interface TimerStore {
   initialTime: number;
   time: number;
+  status: CountdownTimer["status"];
   isActive: boolean;
   isPaused: boolean;
   message: string;
@@ -13,11 +16,11 @@ export interface TimerStore {
   minutes: number;
   seconds: number;
   defaultTimer: TimeSpan;
+  applyTimer: (timer: CountdownTimer) => void;
   startTimer: () => void;
   pauseTimer: () => void;
   resetTimer: () => void;
   resetToDefault: () => void;
-  decrementTime: () => void;
   setIsPlaying: (isPlaying: boolean) => void;
   setTimeoutMessage: (message: string) => void;
   setHours: (hours: number) => void;
@@ -25,77 +28,73 @@ export interface TimerStore {
   setSeconds: (second: number) => void;
   setDefaultTimer: (timer: TimeSpan) => void;
 }
-const defaultTimer = TimeSpan.fromSeconds(0);
-let initialTimer = TimeSpan.fromSeconds(defaultTimer.totalSeconds);
-let remainingTime = initialTimer.totalSeconds;
 
 const defaultMessage = "Beep beep, time has run out.";
 
-const existingTimeKey = "saved_timer";
-const existingInitialTimeKey = "saved_timer_initial_value";
-
-const existingTime = localStorage.getItem(existingTimeKey);
-const existingInitialTime = localStorage.getItem(existingInitialTimeKey);
-let isPaused = false;
+/** Run a timer command in the backend, which keeps the timer running across reloads. */
+async function invokeTimer(
+  command: string,
+  args?: InvokeArgs
+): Promise<CountdownTimer | undefined> {
+  try {
+    const timer = CountdownTimer.parse(await invoke(command, args));
+    useTimerStore.getState().applyTimer(timer);
+    return timer;
+  } catch (error) {
+    showErrorNotification(String(error));
+  }
+}
 
-if (existingTime !== null && existingInitialTime !== null) {
-  remainingTime = Number(existingTime);
-  initialTimer = TimeSpan.fromSeconds(Number(existingInitialTime));
-  isPaused = true;
-  showInfoNotification(
-    "Your previous timer has been restored and is currently paused."
-  );
+/** Fetch the timer from the backend. */
+export function loadTimer() {
+  return invokeTimer("get_timer");
 }
 
 export const useTimerStore = create<TimerStore>((set, get) => ({
-  initialTime: initialTimer.totalSeconds,
-  time: remainingTime,
+  initialTime: 0,
+  time: 0,
+  status: "Stopped",
   isActive: false,
-  isPaused,
+  isPaused: false,
   message: defaultMessage,
   playingSound: false,
-  hours: initialTimer.hours,
-  minutes: initialTimer.minutes,
-  seconds: initialTimer.seconds,
+  hours: 0,
+  minutes: 0,
+  seconds: 0,
   defaultTimer: TimeSpan.fromSeconds(0),
+  applyTimer: (timer) => {
+    const initialTimer = TimeSpan.fromSeconds(timer.initialDuration);
+
+    set({
+      initialTime: timer.initialDuration,
+      time: timer.remainingDuration,
+      status: timer.status,
+      isActive: timer.status === "Running",
+      isPaused: timer.status === "Paused",
+      message: timer.message,
+      hours: initialTimer.hours,
+      minutes: initialTimer.minutes,
+      seconds: initialTimer.seconds,
+    });
+  },
   startTimer: () => {
-    set({ isActive: true, isPaused: false });
-    localStorage.setItem(existingTimeKey, get().time.toString());
-    localStorage.setItem(existingInitialTimeKey, get().initialTime.toString());
+    invokeTimer("start_timer", {
+      duration: get().isPaused ? null : get().initialTime,
+      message: get().message,
+    });
   },
   pauseTimer: () => {
-    set({ isPaused: true, isActive: false });
+    invokeTimer("pause_timer");
   },
   resetTimer: () => {
-    set({
-      isActive: false,
-      isPaused: false,
-      time: get().initialTime,
-      playingSound: false,
-    });
-
-    localStorage.removeItem(existingTimeKey);
-    localStorage.removeItem(existingInitialTimeKey);
+    set({ playingSound: false });
+    invokeTimer("reset_timer", { duration: null, message: get().message });
   },
   resetToDefault: () => {
-    set({
-      initialTime: get().defaultTimer.totalSeconds,
-      time: get().defaultTimer.totalSeconds,
+    invokeTimer("reset_timer", {
+      duration: get().defaultTimer.totalSeconds,
       message: defaultMessage,
-      hours: get().defaultTimer.hours,
-      minutes: get().defaultTimer.minutes,
-      seconds: get().defaultTimer.seconds,
     });
-
-    localStorage.removeItem(existingTimeKey);
-    localStorage.removeItem(existingInitialTimeKey);
-  },
-  decrementTime: () => {
-    const time = get().time;
-    if (time > 0) {
-      set({ time: time - 1 });
-      localStorage.setItem(existingTimeKey, (time - 1).toString());
-    }
   },
   setIsPlaying: (playing) => {
     set({ playingSound: playing });
@@ -110,7 +109,11 @@ export const useTimerStore = create<TimerStore>((set, get) => ({
       TimeSpan.fromSeconds(get().seconds)
     );
 
-    set({ hours, initialTime: ts.totalSeconds, time: ts.totalSeconds });
+    set({ hours });
+    invokeTimer("reset_timer", {
+      duration: ts.totalSeconds,
+      message: get().message,
+    });
   },
   setMinutes: (minutes) => {
     const ts = TimeSpan.add(
@@ -119,7 +122,11 @@ export const useTimerStore = create<TimerStore>((set, get) => ({
       TimeSpan.fromSeconds(get().seconds)
     );
 
-    set({ minutes, initialTime: ts.totalSeconds, time: ts.totalSeconds });
+    set({ minutes });
+    invokeTimer("reset_timer", {
+      duration: ts.totalSeconds,
+      message: get().message,
+    });
   },
   setSeconds: (seconds) => {
     const ts = TimeSpan.add(
@@ -128,17 +135,25 @@ export const useTimerStore = create<TimerStore>((set, get) => ({
       TimeSpan.fromSeconds(seconds)
     );
 
-    set({ seconds, initialTime: ts.totalSeconds, time: ts.totalSeconds });
+    set({ seconds });
+    invokeTimer("reset_timer", {
+      duration: ts.totalSeconds,
+      message: get().message,
+    });
   },
   setDefaultTimer: (timer) => {
+    const previousDefault = get().defaultTimer.totalSeconds;
     set({ defaultTimer: timer });
-    if (!get().isActive && !get().isPaused) {
-      set({
-        initialTime: timer.totalSeconds,
-        time: timer.totalSeconds,
-        hours: timer.hours,
-        minutes: timer.minutes,
-        seconds: timer.seconds,
+
+    // Only follow a changed default when the timer was left on the old one.
+    if (
+      previousDefault > 0 &&
+      get().status === "Stopped" &&
+      get().initialTime === previousDefault
+    ) {
+      invokeTimer("reset_timer", {
+        duration: timer.totalSeconds,
+        message: get().message,
       });
     }
   },
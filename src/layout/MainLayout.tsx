@@ -11,6 +11,8 @@ import { getCurrentWindow } from "@tauri-apps/api/window";
 import { useEffect, useState } from "react";
 import { Outlet } from "react-router-dom";
 import StyledActionIcon from "../components/StyledActionIcon";
+import useIdleSpans from "../features/idle/hooks/useIdleSpans";
+import useStaleSessions from "../features/sessions/hooks/useStaleSessions";
 import { useUserSettings } from "../features/settings/settingsService";
 import useGlobalTimer from "../features/timer/hooks/useGlobalTimer";
 import Navbar from "./Navbar";
@@ -21,6 +23,8 @@ function MainLayout() {
   /** An app store dispatch function to update store values. */
 
   useGlobalTimer();
+  useStaleSessions();
+  useIdleSpans();
   const [maximized, setMaximized] = useState(false);
 
   /** Update the currently maximized state. */
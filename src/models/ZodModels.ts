@@ -120,6 +120,47 @@ export const UserSettings = z.object({
   navbarOpened: z.boolean(),
   notificationSettings: z.array(NotificationSetting),
   defaultTimer: z.number(),
+  allowConcurrentTimers: z.boolean(),
+  trashRetentionDays: z.number(),
+  idleTimeout: z.number(),
 });
 
 export type UserSettings = z.infer<typeof UserSettings>;
+
+export const CountdownTimer = z.object({
+  initialDuration: z.number(),
+  remainingDuration: z.number(),
+  status: z.enum(["Stopped", "Running", "Paused", "Expired"]),
+  endsAt: z.string().nullable().transform(maybeDate),
+  message: z.string(),
+});
+
+export type CountdownTimer = z.infer<typeof CountdownTimer>;
+
+export const StaleSession = z.object({
+  historyId: z.number(),
+  taskId: z.number(),
+  taskTitle: z.string(),
+  startDate: z.string().transform(toDate),
+  lastHeartbeat: z.string().transform(toDate),
+  unaccountedDuration: z.number(),
+});
+
+export type StaleSession = z.infer<typeof StaleSession>;
+
+export const IdleSession = z.object({
+  historyId: z.number(),
+  taskId: z.number(),
+  taskTitle: z.string(),
+});
+
+export type IdleSession = z.infer<typeof IdleSession>;
+
+export const IdleSpan = z.object({
+  start: z.string().transform(toDate),
+  end: z.string().nullable().transform(maybeDate),
+  duration: z.number(),
+  sessions: z.array(IdleSession),
+});
+
+export type IdleSpan = z.infer<typeof IdleSpan>;